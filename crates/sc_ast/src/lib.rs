@@ -3,11 +3,12 @@
 //! Re-exports the standard SWC AST and adds custom nodes for:
 //! - Pipeline operator (`|>`)
 //! - Cons operator (`::`)
+//! - Bind operator (`obj::method`, opt-in)
 //! - HKT type parameters (`F<_>`)
 
 pub use swc_ecma_ast::*;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use swc_common::Span;
 
 /// Binary operators added by sugarcube beyond the standard JS/TS set.
@@ -17,13 +18,34 @@ pub enum ScBinaryOp {
     Pipeline,
     /// Cons operator `::` — precedence 5, right-associative.
     Cons,
+    /// Bind operator `obj::method` (unspaced `::` between identifiers) —
+    /// binds as tightly as member access, left-associative.
+    Bind,
+}
+
+impl ScBinaryOp {
+    /// How tightly the operator binds. Higher binds tighter; every standard
+    /// JS operator sits between bind and cons.
+    pub const fn precedence(self) -> u8 {
+        match self {
+            ScBinaryOp::Pipeline => 1,
+            ScBinaryOp::Cons => 5,
+            // Member-access level: tighter than every standard operator.
+            ScBinaryOp::Bind => 20,
+        }
+    }
+
+    /// Whether `a op b op c` groups as `a op (b op c)`.
+    pub const fn is_right_assoc(self) -> bool {
+        matches!(self, ScBinaryOp::Cons)
+    }
 }
 
 impl std::fmt::Display for ScBinaryOp {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ScBinaryOp::Pipeline => write!(f, "|>"),
-            ScBinaryOp::Cons => write!(f, "::"),
+            ScBinaryOp::Cons | ScBinaryOp::Bind => write!(f, "::"),
         }
     }
 }
@@ -36,6 +58,8 @@ impl std::fmt::Display for ScBinaryOp {
 pub struct ScBinExpr {
     pub span: Span,
     pub op: ScBinaryOp,
+    /// The operator token alone, e.g. the `|>` in `a |> f`.
+    pub op_span: Span,
     pub left: Box<Expr>,
     pub right: Box<Expr>,
 }
@@ -51,11 +75,100 @@ pub struct HktTypeParam {
 }
 
 /// Feature flags controlling which sugarcube extensions are active.
+///
+/// Missing fields deserialize to their defaults, so partial configs work.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ScSyntax {
     pub pipeline: bool,
     pub cons: bool,
     pub hkt: bool,
+    /// Read an unspaced `::` between two identifiers (`obj::method`) as the
+    /// bind operator, lowered to `method.bind(obj)`. Spaced `x :: xs` stays
+    /// cons. Off by default because it changes the meaning of `x::xs`.
+    pub bind: bool,
+    /// Name of the type that HKT usages are applied through: `F<A>` becomes
+    /// `{hkt_apply}<F, A>`. Defaults to `$`; codebases that already use `$`
+    /// for something else can pick another identifier, e.g. `HKT`.
+    #[serde(deserialize_with = "identifier")]
+    pub hkt_apply: String,
+    /// How the right operand of `|>` is read.
+    pub pipe_style: PipeStyle,
+    /// What `::` becomes.
+    pub cons_lowering: ConsLowering,
+    /// Dedicated functions to call instead of `__binop__`, per operator.
+    pub helpers: OperatorHelpers,
+    /// Reject `|>` and `::` without whitespace on both sides (`a|>b`), which
+    /// is easy to misread as `|` then `>`. The bind form `obj::method` is
+    /// unspaced by definition and stays allowed.
+    pub require_operator_spaces: bool,
+    /// Warn when the right operand of `|>` is an arithmetic, comparison or
+    /// logical expression: `a |> b + c` pipes into all of `b + c`, which is
+    /// rarely what was meant.
+    pub lint_pipe_operands: bool,
+    /// Report likely mistakes that are otherwise passed through for SWC to
+    /// reject with a less helpful error, such as `|>` in a type.
+    pub strict: bool,
+    /// After preprocessing, make any `|>` or `::` still in code an error: a
+    /// safety net for operators that would otherwise reach SWC or the
+    /// output as written, because their feature is off or the operator
+    /// pass gave up on them.
+    pub error_on_residual: bool,
+}
+
+/// How the right operand of `|>` is read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PipeStyle {
+    /// The right operand is a function to call with the left: `a |> f` is
+    /// `f(a)`, via `__binop__`.
+    #[default]
+    Fsharp,
+    /// A right operand starting with `_.`, `_?.` or `_[` uses the left as
+    /// the receiver in place of the `_` topic: `a |> _.foo()` is `a.foo()`.
+    /// That is the only place `_` is the topic: any other right operand is
+    /// read as in [`Fsharp`](Self::Fsharp), so `a |> f(_)` calls `f(_)`
+    /// with `a`, and each `_` elsewhere in it gets a warning. Off by
+    /// default because `_` is a common identifier (lodash).
+    Hack,
+}
+
+/// What `::` becomes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConsLowering {
+    /// `a :: b` is `__binop__(a, "::", b)`, for a runtime to define.
+    #[default]
+    Binop,
+    /// `a :: b` is `[a, ...b]`, needing no runtime. A chain ending in an
+    /// array literal folds into one: `1 :: 2 :: []` is `[1, 2]`.
+    Array,
+}
+
+/// Functions that replace `__binop__` for one operator each. A set name gets
+/// a two-argument call with no operator string: with `pipeline` set to
+/// `__pipe__`, `a |> f` is `__pipe__(a, f)`. Unset operators keep going
+/// through `__binop__`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OperatorHelpers {
+    #[serde(deserialize_with = "optional_identifier")]
+    pub pipeline: Option<String>,
+    /// Not used under [`ConsLowering::Array`], which calls nothing.
+    #[serde(deserialize_with = "optional_identifier")]
+    pub cons: Option<String>,
+}
+
+impl OperatorHelpers {
+    /// The helper for `op`, if one is set. Bind lowers to `.bind` and never
+    /// has one.
+    pub fn get(&self, op: ScBinaryOp) -> Option<&str> {
+        match op {
+            ScBinaryOp::Pipeline => self.pipeline.as_deref(),
+            ScBinaryOp::Cons => self.cons.as_deref(),
+            ScBinaryOp::Bind => None,
+        }
+    }
 }
 
 impl Default for ScSyntax {
@@ -64,6 +177,67 @@ impl Default for ScSyntax {
             pipeline: true,
             cons: true,
             hkt: true,
+            bind: false,
+            hkt_apply: "$".to_string(),
+            pipe_style: PipeStyle::default(),
+            cons_lowering: ConsLowering::default(),
+            helpers: OperatorHelpers::default(),
+            require_operator_spaces: false,
+            lint_pipe_operands: false,
+            strict: false,
+            error_on_residual: false,
         }
     }
 }
+
+/// A name spliced into the output as is, e.g. [`ScSyntax::hkt_apply`]:
+/// anything but an identifier would produce invalid TypeScript.
+fn identifier<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    check_identifier(&name)?;
+    Ok(name)
+}
+
+fn optional_identifier<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    let name = Option::<String>::deserialize(deserializer)?;
+    if let Some(name) = &name {
+        check_identifier(name)?;
+    }
+    Ok(name)
+}
+
+fn check_identifier<E: serde::de::Error>(name: &str) -> Result<(), E> {
+    let mut bytes = name.bytes();
+    let valid = bytes
+        .next()
+        .is_some_and(|b| b.is_ascii_alphabetic() || b == b'_' || b == b'$')
+        && bytes.all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'$');
+    if valid {
+        Ok(())
+    } else {
+        Err(E::custom(format!(
+            "expected a TypeScript identifier, got `{name}`"
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn precedence_and_associativity_match_the_docs() {
+        assert_eq!(ScBinaryOp::Pipeline.precedence(), 1);
+        assert!(!ScBinaryOp::Pipeline.is_right_assoc());
+        assert_eq!(ScBinaryOp::Cons.precedence(), 5);
+        assert!(ScBinaryOp::Cons.is_right_assoc());
+        assert_eq!(ScBinaryOp::Bind.precedence(), 20);
+        assert!(!ScBinaryOp::Bind.is_right_assoc());
+
+        // `::` before `|>`, bind before both.
+        assert!(ScBinaryOp::Cons.precedence() > ScBinaryOp::Pipeline.precedence());
+        assert!(ScBinaryOp::Bind.precedence() > ScBinaryOp::Cons.precedence());
+    }
+}
@@ -0,0 +1,51 @@
+//! Compares `transform_many` against calling `transform` in a loop over many
+//! small snippets, the doc-example / test-case workload it is meant for.
+//!
+//! Run with `cargo bench -p sc_cli --bench transform_many`.
+
+use std::time::{Duration, Instant};
+
+use sc_ast::ScSyntax;
+use sc_cli::{transform, transform_many, TransformOptions};
+
+const SNIPPETS: usize = 2000;
+const ROUNDS: usize = 5;
+
+fn main() {
+    let inputs: Vec<(String, String)> = (0..SNIPPETS)
+        .map(|i| {
+            (
+                format!("snippet{i}.ts"),
+                format!("const r{i} = [{i}, 2, 3] |> sum;\nconst l{i} = {i} :: [];\n"),
+            )
+        })
+        .collect();
+    let syntax = ScSyntax::default();
+    let options = TransformOptions::default();
+
+    let looped = best_of(|| {
+        for (name, source) in &inputs {
+            transform(name, source, &syntax, &options).expect("snippet transforms");
+        }
+    });
+    let batched = best_of(|| {
+        for result in transform_many(&inputs, &syntax, &options) {
+            result.expect("snippet transforms");
+        }
+    });
+
+    println!("{SNIPPETS} snippets, best of {ROUNDS}:");
+    println!("  transform in a loop: {looped:?}");
+    println!("  transform_many:      {batched:?}");
+}
+
+fn best_of(mut run: impl FnMut()) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .expect("ROUNDS > 0")
+}
@@ -0,0 +1,144 @@
+//! Keeping a block comment on its own line, for
+//! [`TransformOptions::keep_comments`](crate::TransformOptions::keep_comments).
+
+use std::collections::HashSet;
+
+use swc_common::comments::{CommentKind, SingleThreadedComments};
+use swc_common::{BytePos, SourceFile, Span};
+use swc_ecma_codegen::text_writer::WriteJs;
+
+type Result = std::io::Result<()>;
+
+/// The position of the `*/` of every block comment in `comments` that ends
+/// its line in `file`, as the codegen reports it to [`WriteJs::add_srcmap`]
+/// just before writing the `*/`.
+pub(crate) fn line_ending_blocks(
+    comments: &SingleThreadedComments,
+    file: &SourceFile,
+) -> HashSet<BytePos> {
+    let (leading, trailing) = comments.borrow_all();
+    leading
+        .values()
+        .chain(trailing.values())
+        .flatten()
+        .filter(|comment| comment.kind == CommentKind::Block)
+        .filter(|comment| {
+            let end = (comment.span.hi - file.start_pos).0 as usize;
+            let rest = file.src[end..].trim_start_matches([' ', '\t']);
+            rest.is_empty() || rest.starts_with(['\n', '\r'])
+        })
+        .map(|comment| comment.span.hi - BytePos(2))
+        .collect()
+}
+
+/// Wraps a writer, ending the line after a block comment that ended its
+/// line in the source. The codegen always follows a block comment with a
+/// space, which would put `/** doc */` on the line of the declaration it
+/// documents.
+pub(crate) struct CommentBreaks<W: WriteJs> {
+    inner: W,
+    line_ending: HashSet<BytePos>,
+    /// The comment being written ends its line: the space after it
+    /// becomes a line break.
+    break_next: bool,
+}
+
+impl<W: WriteJs> CommentBreaks<W> {
+    pub(crate) fn new(inner: W, line_ending: HashSet<BytePos>) -> Self {
+        CommentBreaks {
+            inner,
+            line_ending,
+            break_next: false,
+        }
+    }
+}
+
+impl<W: WriteJs> WriteJs for CommentBreaks<W> {
+    fn increase_indent(&mut self) -> Result {
+        self.inner.increase_indent()
+    }
+
+    fn decrease_indent(&mut self) -> Result {
+        self.inner.decrease_indent()
+    }
+
+    fn write_semi(&mut self, span: Option<Span>) -> Result {
+        self.inner.write_semi(span)
+    }
+
+    fn write_space(&mut self) -> Result {
+        if std::mem::take(&mut self.break_next) {
+            self.inner.write_line()
+        } else {
+            self.inner.write_space()
+        }
+    }
+
+    fn write_keyword(&mut self, span: Option<Span>, s: &'static str) -> Result {
+        self.inner.write_keyword(span, s)
+    }
+
+    fn write_operator(&mut self, span: Option<Span>, s: &str) -> Result {
+        self.inner.write_operator(span, s)
+    }
+
+    fn write_param(&mut self, s: &str) -> Result {
+        self.inner.write_param(s)
+    }
+
+    fn write_property(&mut self, s: &str) -> Result {
+        self.inner.write_property(s)
+    }
+
+    fn write_line(&mut self) -> Result {
+        self.inner.write_line()
+    }
+
+    fn write_lit(&mut self, span: Span, s: &str) -> Result {
+        self.inner.write_lit(span, s)
+    }
+
+    fn write_comment(&mut self, s: &str) -> Result {
+        self.inner.write_comment(s)
+    }
+
+    fn write_str_lit(&mut self, span: Span, s: &str) -> Result {
+        self.inner.write_str_lit(span, s)
+    }
+
+    fn write_str(&mut self, s: &str) -> Result {
+        self.inner.write_str(s)
+    }
+
+    fn write_symbol(&mut self, span: Span, s: &str) -> Result {
+        self.inner.write_symbol(span, s)
+    }
+
+    fn write_punct(
+        &mut self,
+        span: Option<Span>,
+        s: &'static str,
+        commit_pending_semi: bool,
+    ) -> Result {
+        self.inner.write_punct(span, s, commit_pending_semi)
+    }
+
+    fn care_about_srcmap(&self) -> bool {
+        self.inner.care_about_srcmap()
+    }
+
+    /// Called with the position of a block comment's `*/` right before the
+    /// codegen writes it, and with token positions otherwise.
+    fn add_srcmap(&mut self, pos: BytePos) -> Result {
+        self.break_next = self.line_ending.contains(&pos);
+        self.inner.add_srcmap(pos)
+    }
+
+    fn commit_pending_semi(&mut self) -> Result {
+        self.inner.commit_pending_semi()
+    }
+
+    fn can_ignore_invalid_unicodes(&mut self) -> bool {
+        self.inner.can_ignore_invalid_unicodes()
+    }
+}
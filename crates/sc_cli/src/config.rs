@@ -0,0 +1,129 @@
+//! `sugarcube.toml`: per-project defaults for the feature flags and output
+//! options, so they needn't be passed on every run. Command-line flags
+//! override it.
+
+use std::collections::hash_map::{Entry, HashMap};
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use sc_ast::ScSyntax;
+use sc_cli::{Indent, Runtime};
+use serde::{Deserialize, Deserializer};
+
+pub const FILE_NAME: &str = "sugarcube.toml";
+
+/// A `sugarcube.toml`. Every key is optional:
+///
+/// ```toml
+/// [syntax]            # any ScSyntax field
+/// hkt = false
+/// cons_lowering = "array"
+///
+/// [output]
+/// indent = 2          # or "tab"
+/// runtime = "global"
+/// semicolons = false
+/// banner = "Copyright Example Corp."
+/// preserve_banner = true
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The file this was read from; `None` for the defaults.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+    pub syntax: ScSyntax,
+    pub output: OutputConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    #[serde(deserialize_with = "parsed")]
+    pub indent: Option<Indent>,
+    #[serde(deserialize_with = "parsed")]
+    pub runtime: Option<Runtime>,
+    pub semicolons: Option<bool>,
+    pub banner: Option<String>,
+    pub preserve_banner: Option<bool>,
+}
+
+/// The config for `input`: the `sugarcube.toml` in its directory (or in
+/// `input` itself, for a directory) or the nearest one above it. Defaults if
+/// there is none.
+pub fn discover(input: &Path) -> Result<Config> {
+    discover_from(&start_dir(input)?)
+}
+
+/// [`discover`] for many inputs, each with its own config, reading the one
+/// for a directory only once.
+#[derive(Debug, Default)]
+pub struct Configs {
+    by_dir: HashMap<PathBuf, Config>,
+}
+
+impl Configs {
+    pub fn discover(&mut self, input: &Path) -> Result<&Config> {
+        match self.by_dir.entry(start_dir(input)?) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                let config = discover_from(entry.key())?;
+                Ok(entry.insert(config))
+            }
+        }
+    }
+}
+
+/// Where the search for `input`'s config starts.
+fn start_dir(input: &Path) -> Result<PathBuf> {
+    let input = std::path::absolute(input)
+        .with_context(|| format!("failed to resolve {}", input.display()))?;
+    if input.is_dir() {
+        return Ok(input);
+    }
+    Ok(input.parent().map_or(input.clone(), Path::to_path_buf))
+}
+
+fn discover_from(start: &Path) -> Result<Config> {
+    match start
+        .ancestors()
+        .map(|dir| dir.join(FILE_NAME))
+        .find(|path| path.is_file())
+    {
+        Some(path) => load(&path),
+        None => Ok(Config::default()),
+    }
+}
+
+pub fn load(path: &Path) -> Result<Config> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let mut config: Config =
+        toml::from_str(&text).with_context(|| format!("invalid {}", path.display()))?;
+    config.path = Some(path.to_path_buf());
+    Ok(config)
+}
+
+/// A value the CLI also takes as a flag, read the same way: `indent = 2`
+/// or `indent = "tab"`.
+fn parsed<'de, D, T>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Scalar {
+        Int(i64),
+        Str(String),
+    }
+
+    let text = match Scalar::deserialize(deserializer)? {
+        Scalar::Int(n) => n.to_string(),
+        Scalar::Str(s) => s,
+    };
+    text.parse().map(Some).map_err(serde::de::Error::custom)
+}
@@ -0,0 +1,186 @@
+//! Unified line diffs, for `sc preprocess --assert-output`.
+
+/// Lines of unchanged context around each change.
+const CONTEXT: usize = 3;
+
+/// Above this many cells the middle of the diff isn't aligned line by line
+/// but shown as one replaced block, so a rewritten file can't exhaust memory.
+const MAX_TABLE: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+/// `old` → `new` as a unified diff with `---`/`+++` headers naming the two
+/// sides. Empty if the texts are equal.
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let ops = diff_lines(&old_lines, &new_lines);
+
+    let mut out = format!("--- {old_name}\n+++ {new_name}\n");
+    for hunk in hunks(&ops) {
+        write_hunk(&mut out, &ops, hunk);
+    }
+    out
+}
+
+/// The edit script from `old` to `new`: unchanged ends, then a longest
+/// common subsequence of the changed middle.
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Op<'a>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut ops: Vec<Op> = old[..prefix].iter().map(|line| Op::Equal(line)).collect();
+    let (n, m) = (old_mid.len(), new_mid.len());
+    if (n + 1) * (m + 1) > MAX_TABLE {
+        ops.extend(old_mid.iter().map(|line| Op::Delete(line)));
+        ops.extend(new_mid.iter().map(|line| Op::Insert(line)));
+    } else {
+        // lcs[i * (m + 1) + j]: common subsequence length of old_mid[i..]
+        // and new_mid[j..].
+        let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i * (m + 1) + j] = if old_mid[i] == new_mid[j] {
+                    lcs[(i + 1) * (m + 1) + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * (m + 1) + j].max(lcs[i * (m + 1) + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old_mid[i] == new_mid[j] {
+                ops.push(Op::Equal(old_mid[i]));
+                i += 1;
+                j += 1;
+            } else if j == m || (i < n && lcs[(i + 1) * (m + 1) + j] >= lcs[i * (m + 1) + j + 1]) {
+                ops.push(Op::Delete(old_mid[i]));
+                i += 1;
+            } else {
+                ops.push(Op::Insert(new_mid[j]));
+                j += 1;
+            }
+        }
+    }
+    ops.extend(old[old.len() - suffix..].iter().map(|line| Op::Equal(line)));
+    ops
+}
+
+/// Ranges of `ops` to print: each change with its context, merged where
+/// the context of two changes would touch.
+fn hunks(ops: &[Op]) -> Vec<std::ops::Range<usize>> {
+    let mut hunks: Vec<std::ops::Range<usize>> = Vec::new();
+    for (i, op) in ops.iter().enumerate() {
+        if matches!(op, Op::Equal(_)) {
+            continue;
+        }
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + 1 + CONTEXT).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.end => last.end = end,
+            _ => hunks.push(start..end),
+        }
+    }
+    hunks
+}
+
+fn write_hunk(out: &mut String, ops: &[Op], hunk: std::ops::Range<usize>) {
+    // 1-based line numbers where the hunk starts on each side.
+    let old_before = ops[..hunk.start]
+        .iter()
+        .filter(|op| !matches!(op, Op::Insert(_)))
+        .count();
+    let new_before = ops[..hunk.start]
+        .iter()
+        .filter(|op| !matches!(op, Op::Delete(_)))
+        .count();
+    let lines = &ops[hunk];
+    let old_len = lines
+        .iter()
+        .filter(|op| !matches!(op, Op::Insert(_)))
+        .count();
+    let new_len = lines
+        .iter()
+        .filter(|op| !matches!(op, Op::Delete(_)))
+        .count();
+    let range = |before: usize, len: usize| {
+        let start = if len == 0 { before } else { before + 1 };
+        format!("{start},{len}")
+    };
+    out.push_str(&format!(
+        "@@ -{} +{} @@\n",
+        range(old_before, old_len),
+        range(new_before, new_len)
+    ));
+
+    for op in lines {
+        let (sign, line) = match op {
+            Op::Equal(line) => (' ', line),
+            Op::Delete(line) => ('-', line),
+            Op::Insert(line) => ('+', line),
+        };
+        out.push(sign);
+        out.push_str(line);
+        if !line.ends_with('\n') {
+            out.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_texts_have_no_diff() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "old", "new"), "");
+    }
+
+    #[test]
+    fn changes_show_with_three_lines_of_context() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n";
+        let new = "1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n";
+        assert_eq!(
+            unified_diff(old, new, "a.js", "a.ts (transformed)"),
+            "--- a.js\n+++ a.ts (transformed)\n\
+             @@ -1,6 +1,6 @@\n 1\n 2\n-3\n+three\n 4\n 5\n 6\n\
+             @@ -10,3 +10,4 @@\n 10\n 11\n 12\n+13\n"
+        );
+    }
+
+    #[test]
+    fn close_changes_share_a_hunk() {
+        let diff = unified_diff("a\nb\nc\nd\ne\n", "a\nB\nc\nD\ne\n", "old", "new");
+        assert_eq!(
+            diff,
+            "--- old\n+++ new\n@@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n-d\n+D\n e\n"
+        );
+    }
+
+    #[test]
+    fn a_missing_final_newline_is_marked() {
+        let diff = unified_diff("a\n", "a\nb", "old", "new");
+        assert_eq!(
+            diff,
+            "--- old\n+++ new\n@@ -1,1 +1,2 @@\n a\n+b\n\\ No newline at end of file\n"
+        );
+
+        let diff = unified_diff("", "a\n", "old", "new");
+        assert_eq!(diff, "--- old\n+++ new\n@@ -0,0 +1,1 @@\n+a\n");
+    }
+}
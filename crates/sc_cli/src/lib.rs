@@ -0,0 +1,966 @@
+//! The parse → desugar → emit pipeline behind `sc preprocess`, as a library.
+//!
+//! [`transform`] turns one sugarcube source into standard TypeScript.
+//! [`transform_many`] does the same for a batch of inputs, reusing one
+//! [`ParseSession`] so small snippets don't each pay for parser setup;
+//! [`transform_iter`] is the lazy form, one input per `next()`. To control
+//! which `SourceMap` the inputs land in, make the session yourself
+//! ([`ParseSession::with_source_map`]) and call [`transform_in`].
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use sc_ast::ScSyntax;
+use sc_desugar::desugar_module;
+use sc_parser::preprocess::{comment_ranges, Diagnostic, PreprocessReport};
+use sc_parser::remap_to_original;
+pub use sc_parser::ParseSession;
+use swc_common::comments::{Comment, CommentKind, SingleThreadedComments};
+use swc_common::source_map::DefaultSourceMapGenConfig;
+use swc_common::{sync::Lrc, SourceMap, DUMMY_SP};
+use swc_ecma_ast::{
+    ArrowExpr, BindingIdent, Decl, FnDecl, Function, Ident, ImportSpecifier, Module, ModuleDecl,
+    ModuleItem, Param, Pat, Stmt, TsKeywordType, TsKeywordTypeKind, TsType, TsTypeAnn,
+};
+use swc_ecma_codegen::text_writer::{JsWriter, WriteJs};
+use swc_ecma_codegen::{Emitter, Node};
+use swc_ecma_visit::{Visit, VisitMut, VisitMutWith, VisitWith};
+
+mod comments;
+mod semicolons;
+
+/// Options for [`transform`] and [`transform_many`].
+#[derive(Debug, Clone, Default)]
+pub struct TransformOptions {
+    /// Parse as TSX. `None` infers it from the file name's extension.
+    pub tsx: Option<bool>,
+    /// Also produce a source map pointing at the original source.
+    pub source_map: bool,
+    /// A v3 source map (JSON) for the input, if it was itself generated.
+    /// The output map is composed with it, so it points at the authored
+    /// file instead of the sugarcube input. Only used with `source_map`.
+    pub input_source_map: Option<String>,
+    /// Start the output with a UTF-8 BOM if the input had one. The BOM is
+    /// always stripped before parsing.
+    pub keep_bom: bool,
+    /// Indentation of the emitted code.
+    pub indent: Indent,
+    /// How the emitted `__binop__` calls find the runtime.
+    pub runtime: Runtime,
+    /// Leave out the `;` ending each statement that ends its line, for
+    /// projects written without semicolons. A line that would otherwise
+    /// continue the one before it starts with `;` instead.
+    pub omit_semicolons: bool,
+    /// A comment to start the output with, after any shebang: `/* text */`,
+    /// or one ` * ` line per line of a multi-line text. Can't contain `*/`.
+    pub banner: Option<String>,
+    /// Keep the block comment the input starts with, such as a license
+    /// header, though other comments are dropped. It follows `banner`.
+    pub preserve_banner: bool,
+    /// Keep every comment in the input, JSDoc included, rather than only
+    /// triple-slash directives and the banner. For output that replaces
+    /// the input, as with `sc preprocess --in-place`. A comment inside a
+    /// rewritten `|>` or `::` is still lost, and gets a warning.
+    pub keep_comments: bool,
+}
+
+/// What the output provides for the `__binop__` calls it makes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Runtime {
+    /// Nothing: the calls assume `__binop__` is in scope.
+    #[default]
+    None,
+    /// End each file that calls `__binop__` with an ambient
+    /// `declare function __binop__(...)`, for runtimes that provide it as a
+    /// global. No import is added, so bundlers have no module to resolve.
+    Global,
+}
+
+/// `none` or `global`.
+impl std::str::FromStr for Runtime {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Runtime::None),
+            "global" => Ok(Runtime::Global),
+            _ => Err(format!("expected `none` or `global`, got `{s}`")),
+        }
+    }
+}
+
+/// As [`FromStr`](std::str::FromStr) reads it.
+impl std::fmt::Display for Runtime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Runtime::None => "none",
+            Runtime::Global => "global",
+        })
+    }
+}
+
+/// One level of indentation in emitted code. SWC's codegen has no line-width
+/// setting, so this is the only layout option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    /// This many spaces, 1 to [`Indent::MAX_SPACES`].
+    Spaces(u8),
+    /// One tab.
+    Tab,
+}
+
+impl Indent {
+    pub const MAX_SPACES: u8 = 16;
+
+    fn as_str(self) -> &'static str {
+        const SPACES: &str = "                ";
+        match self {
+            Indent::Spaces(n) => &SPACES[..usize::from(n.min(Self::MAX_SPACES))],
+            Indent::Tab => "\t",
+        }
+    }
+}
+
+/// SWC's own default.
+impl Default for Indent {
+    fn default() -> Self {
+        Indent::Spaces(4)
+    }
+}
+
+/// `tab`, or a number of spaces.
+impl std::str::FromStr for Indent {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if s == "tab" {
+            return Ok(Indent::Tab);
+        }
+        match s.parse::<u8>() {
+            Ok(n @ 1..=Self::MAX_SPACES) => Ok(Indent::Spaces(n)),
+            _ => Err(format!(
+                "expected `tab` or 1 to {} spaces, got `{s}`",
+                Self::MAX_SPACES
+            )),
+        }
+    }
+}
+
+/// As [`FromStr`](std::str::FromStr) reads it.
+impl std::fmt::Display for Indent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Indent::Spaces(n) => write!(f, "{n}"),
+            Indent::Tab => f.write_str("tab"),
+        }
+    }
+}
+
+/// Output of [`transform`].
+#[derive(Debug, Clone)]
+pub struct TransformOutput {
+    /// The emitted standard TypeScript.
+    pub code: String,
+    /// The v3 source map as JSON, if [`TransformOptions::source_map`] was set.
+    pub map: Option<String>,
+    /// Sugarcube-level warnings and rewrite counts from preprocessing.
+    pub report: PreprocessReport,
+    pub timings: Timings,
+}
+
+/// How long each stage of a [`transform`] took.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timings {
+    pub preprocess: Duration,
+    pub parse: Duration,
+    /// Desugaring, codegen, and building the source map.
+    pub emit: Duration,
+}
+
+/// Transform one sugarcube source file into standard TypeScript.
+pub fn transform(
+    name: &str,
+    source: &str,
+    syntax: &ScSyntax,
+    options: &TransformOptions,
+) -> Result<TransformOutput> {
+    transform_in(&ParseSession::new(), name, source, syntax, options)
+}
+
+/// Whether `source` makes it through [`transform`] without an error, for
+/// fuzzers and batch scripts that only need a yes or no. Nothing is printed:
+/// SWC's parse errors are discarded along with sugarcube's diagnostics, and
+/// warnings don't count against it. TSX is inferred from `filename`.
+pub fn is_valid_sugarcube(source: &str, filename: &str, syntax: &ScSyntax) -> bool {
+    let options = TransformOptions::default();
+    transform_in(&ParseSession::quiet(), filename, source, syntax, &options).is_ok()
+}
+
+/// Transform many `(name, source)` inputs, sharing one [`ParseSession`].
+///
+/// All inputs are registered in the same `SourceMap` and share the SWC error
+/// handler; each still gets its own source file. Results are in input order,
+/// and a failing input doesn't stop the rest.
+pub fn transform_many<N, S>(
+    inputs: &[(N, S)],
+    syntax: &ScSyntax,
+    options: &TransformOptions,
+) -> Vec<Result<TransformOutput>>
+where
+    N: AsRef<str>,
+    S: AsRef<str>,
+{
+    transform_iter(inputs, syntax, options).collect()
+}
+
+/// [`transform_many`], transforming each input only when the iterator gets
+/// to it, so a caller can time or report on them one by one.
+pub fn transform_iter<'a, N, S>(
+    inputs: &'a [(N, S)],
+    syntax: &'a ScSyntax,
+    options: &'a TransformOptions,
+) -> impl Iterator<Item = Result<TransformOutput>> + 'a
+where
+    N: AsRef<str>,
+    S: AsRef<str>,
+{
+    let session = ParseSession::new();
+    inputs.iter().map(move |(name, source)| {
+        transform_in(&session, name.as_ref(), source.as_ref(), syntax, options)
+    })
+}
+
+/// [`transform`], parsing into `session`'s `SourceMap` instead of a fresh
+/// one.
+pub fn transform_in(
+    session: &ParseSession,
+    name: &str,
+    source: &str,
+    syntax: &ScSyntax,
+    options: &TransformOptions,
+) -> Result<TransformOutput> {
+    if options
+        .banner
+        .as_deref()
+        .is_some_and(|text| text.contains("*/"))
+    {
+        anyhow::bail!("a banner can't contain `*/`");
+    }
+    let mut parsed = session.parse(source, name, syntax, options.tsx)?;
+    let started = Instant::now();
+    let directives = parsed.directive_comments();
+    let banners: String = options
+        .banner
+        .as_deref()
+        .map(banner_comment)
+        .into_iter()
+        .chain(
+            // Kept comments already include it.
+            (options.preserve_banner && !options.keep_comments)
+                .then(|| parsed.banner_comment())
+                .flatten(),
+        )
+        .map(|comment| format!("/*{}*/\n", comment.text))
+        .collect();
+    let mut module = desugar_module(parsed.module);
+    if options.runtime == Runtime::Global {
+        for (name, params) in runtime_functions(syntax) {
+            if needs_declaration(&module, name) {
+                module.body.push(declaration(name, params));
+            }
+        }
+    }
+    let mut tsx_params = TsxArrowParams::new(parsed.source_file.src.as_str());
+    if parsed.tsx {
+        module.visit_mut_with(&mut tsx_params);
+    }
+
+    let kept = options
+        .keep_comments
+        .then(|| comment_texts(&parsed.comments));
+
+    let mut buf = Vec::new();
+    let mut mappings = options.source_map.then(Vec::new);
+    {
+        let mut emitter = Emitter {
+            cfg: swc_ecma_codegen::Config::default().with_target(swc_ecma_ast::EsVersion::latest()),
+            cm: parsed.source_map.clone(),
+            comments: Some(if options.keep_comments {
+                &parsed.comments
+            } else {
+                &directives
+            }),
+            wr: writer(&parsed.source_map, &mut buf, mappings.as_mut(), options),
+        };
+        if options.keep_comments {
+            let line_ending = comments::line_ending_blocks(&parsed.comments, &parsed.source_file);
+            emitter.wr = Box::new(comments::CommentBreaks::new(emitter.wr, line_ending));
+        }
+        module.emit_with(&mut emitter)?;
+    }
+    if let Some(mut kept) = kept {
+        // The emitter takes each comment it writes: what's left never made
+        // it into the output.
+        for (text, count) in comment_texts(&parsed.comments) {
+            *kept.entry(text).or_default() -= count;
+        }
+        warn_dropped_comments(source, kept, &mut parsed.report);
+    }
+    let mut code = String::from_utf8(buf)?;
+    terminate_export_assignment(&module, &parsed.source_map, options, &mut code)?;
+    tsx_params.unmark(&mut code);
+    if !banners.is_empty() {
+        // Inserted rather than emitted, since the emitter keeps a block
+        // comment on the line of whatever follows it.
+        let (at, line) = match code.strip_prefix("#!") {
+            Some(rest) => (
+                code.len() - rest.len() + rest.find('\n').map_or(rest.len(), |n| n + 1),
+                1,
+            ),
+            None => (0, 0),
+        };
+        code.insert_str(at, &banners);
+        let added = banners.matches('\n').count() as u32;
+        for (_, pos) in mappings
+            .iter_mut()
+            .flatten()
+            .filter(|(_, pos)| pos.line >= line)
+        {
+            pos.line += added;
+        }
+    }
+    if options.keep_bom && source.starts_with('\u{FEFF}') {
+        code.insert(0, '\u{FEFF}');
+    }
+
+    let map = match mappings {
+        Some(mut mappings) => {
+            let original_map = remap_to_original(
+                &parsed.source_file,
+                &parsed.report.offsets,
+                source,
+                &mut mappings,
+            );
+            let input_map = options
+                .input_source_map
+                .as_deref()
+                .map(|json| swc_sourcemap::SourceMap::from_slice(json.as_bytes()))
+                .transpose()
+                .context("failed to parse input source map")?;
+            let srcmap =
+                original_map.build_source_map(&mappings, input_map, DefaultSourceMapGenConfig);
+            let mut json = Vec::new();
+            srcmap
+                .to_writer(&mut json)
+                .context("failed to serialize source map")?;
+            Some(String::from_utf8(json)?)
+        }
+        None => None,
+    };
+
+    Ok(TransformOutput {
+        code,
+        map,
+        report: parsed.report,
+        timings: Timings {
+            preprocess: parsed.preprocess_time,
+            parse: parsed.parse_time,
+            emit: started.elapsed(),
+        },
+    })
+}
+
+/// [`TransformOptions::banner`] as the comment it describes.
+fn banner_comment(text: &str) -> Comment {
+    let text = if text.contains('\n') {
+        let lines: String = text
+            .lines()
+            .map(|line| format!(" * {line}").trim_end().to_string() + "\n")
+            .collect();
+        format!("\n{lines} ")
+    } else {
+        format!(" {text} ")
+    };
+    Comment {
+        kind: CommentKind::Block,
+        span: DUMMY_SP,
+        text: text.into(),
+    }
+}
+
+/// SWC emits a generic arrow with one plain type parameter as
+/// `<T>(x: T)=>x`, which TSX reads as a `<T>` element. Before emitting,
+/// each such parameter is renamed to a mark, its name plus one character
+/// that follows the name nowhere in the source; afterwards every `<mark>`
+/// becomes `<T,>`. The two are the same length, so no mapping moves.
+struct TsxArrowParams<'a> {
+    source: &'a str,
+    marks: Vec<(String, String)>,
+}
+
+impl<'a> TsxArrowParams<'a> {
+    fn new(source: &'a str) -> Self {
+        TsxArrowParams {
+            source,
+            marks: Vec::new(),
+        }
+    }
+
+    fn unmark(&self, code: &mut String) {
+        for (mark, param) in &self.marks {
+            *code = code.replace(mark, param);
+        }
+    }
+}
+
+impl VisitMut for TsxArrowParams<'_> {
+    fn visit_mut_arrow_expr(&mut self, arrow: &mut ArrowExpr) {
+        arrow.visit_mut_children_with(self);
+        let Some(type_params) = arrow.type_params.as_deref_mut() else {
+            return;
+        };
+        let [param] = type_params.params.as_mut_slice() else {
+            return;
+        };
+        if param.constraint.is_some() || param.default.is_some() {
+            return;
+        }
+        let name = param.name.sym.to_string();
+        let Some(extra) = "_$0123456789abcdefghijklmnopqrstuvwxyz"
+            .chars()
+            .find(|&c| !self.source.contains(&format!("{name}{c}")))
+        else {
+            return;
+        };
+        let mark = format!("{name}{extra}");
+        self.marks.push((format!("{mark}>"), format!("{name},>")));
+        param.name.sym = mark.into();
+    }
+}
+
+const BINOP: &str = "__binop__";
+
+/// Parameter names and types of a runtime function.
+type Params = &'static [(&'static str, TsKeywordTypeKind)];
+
+const BINOP_PARAMS: Params = &[
+    ("left", TsKeywordTypeKind::TsAnyKeyword),
+    ("op", TsKeywordTypeKind::TsStringKeyword),
+    ("right", TsKeywordTypeKind::TsAnyKeyword),
+];
+
+const HELPER_PARAMS: Params = &[
+    ("left", TsKeywordTypeKind::TsAnyKeyword),
+    ("right", TsKeywordTypeKind::TsAnyKeyword),
+];
+
+/// The functions the output may call: `__binop__` and each
+/// [`helpers`](ScSyntax::helpers) entry.
+fn runtime_functions(syntax: &ScSyntax) -> Vec<(&str, Params)> {
+    let helpers = [&syntax.helpers.pipeline, &syntax.helpers.cons];
+    std::iter::once((BINOP, BINOP_PARAMS))
+        .chain(
+            helpers
+                .into_iter()
+                .flatten()
+                .map(|name| (name.as_str(), HELPER_PARAMS)),
+        )
+        .collect()
+}
+
+/// Whether `module` calls `name` without binding it itself.
+fn needs_declaration(module: &Module, name: &str) -> bool {
+    struct FindCall<'a>(&'a str, bool);
+    impl Visit for FindCall<'_> {
+        fn visit_ident(&mut self, ident: &Ident) {
+            self.1 |= ident.sym == *self.0;
+        }
+    }
+
+    let binds_name = module.body.iter().any(|item| match item {
+        ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => {
+            import.specifiers.iter().any(|specifier| {
+                let local = match specifier {
+                    ImportSpecifier::Named(named) => &named.local,
+                    ImportSpecifier::Default(default) => &default.local,
+                    ImportSpecifier::Namespace(namespace) => &namespace.local,
+                };
+                local.sym == *name
+            })
+        }
+        ModuleItem::Stmt(Stmt::Decl(Decl::Fn(f))) => f.ident.sym == *name,
+        _ => false,
+    });
+    if binds_name {
+        return false;
+    }
+    let mut find = FindCall(name, false);
+    module.visit_with(&mut find);
+    find.1
+}
+
+/// `declare function name(left: any, ...): any;`, e.g. for `__binop__`
+/// `declare function __binop__(left: any, op: string, right: any): any;`
+///
+/// Appended rather than prepended: triple-slash directives and `"use strict"`
+/// must stay first, and no original line moves in the source map.
+fn declaration(name: &str, params: Params) -> ModuleItem {
+    let keyword = |kind| {
+        Box::new(TsTypeAnn {
+            span: DUMMY_SP,
+            type_ann: Box::new(TsType::TsKeywordType(TsKeywordType {
+                span: DUMMY_SP,
+                kind,
+            })),
+        })
+    };
+    let param = |name: &str, kind| Param {
+        span: DUMMY_SP,
+        decorators: Vec::new(),
+        pat: Pat::Ident(BindingIdent {
+            id: Ident::new_no_ctxt(name.into(), DUMMY_SP),
+            type_ann: Some(keyword(kind)),
+        }),
+    };
+    ModuleItem::Stmt(Stmt::Decl(Decl::Fn(FnDecl {
+        ident: Ident::new_no_ctxt(name.into(), DUMMY_SP),
+        declare: true,
+        function: Box::new(Function {
+            params: params
+                .iter()
+                .map(|&(name, kind)| param(name, kind))
+                .collect(),
+            return_type: Some(keyword(TsKeywordTypeKind::TsAnyKeyword)),
+            ..Function::default()
+        }),
+    })))
+}
+
+/// The writer for emitted code, with `options`' indentation and semicolon
+/// style.
+fn writer<'a>(
+    source_map: &Lrc<SourceMap>,
+    buf: &'a mut Vec<u8>,
+    mappings: Option<&'a mut Vec<(swc_common::BytePos, swc_common::LineCol)>>,
+    options: &TransformOptions,
+) -> Box<dyn WriteJs + 'a> {
+    let mut writer = JsWriter::new(source_map.clone(), "\n", buf, mappings);
+    writer.set_indent_str(options.indent.as_str());
+    if options.omit_semicolons {
+        Box::new(semicolons::NoSemicolons::new(writer))
+    } else {
+        Box::new(writer)
+    }
+}
+
+/// How many of each comment, as written, `comments` holds.
+fn comment_texts(comments: &SingleThreadedComments) -> HashMap<String, usize> {
+    let (leading, trailing) = comments.borrow_all();
+    let mut texts = HashMap::new();
+    for comment in leading.values().chain(trailing.values()).flatten() {
+        let text = match comment.kind {
+            CommentKind::Line => format!("//{}", comment.text),
+            CommentKind::Block => format!("/*{}*/", comment.text),
+        };
+        *texts.entry(text).or_default() += 1;
+    }
+    texts
+}
+
+/// Warn about each comment in `source` that isn't among the `emitted` ones,
+/// such as one inside an operand the preprocessor rewrote.
+fn warn_dropped_comments(
+    source: &str,
+    mut emitted: HashMap<String, usize>,
+    report: &mut PreprocessReport,
+) {
+    for range in comment_ranges(source) {
+        match emitted.get_mut(&source[range.clone()]) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => report.diagnostics.push(Diagnostic::warning(
+                "comment dropped: it is inside syntax sugarcube rewrote",
+                range,
+            )),
+        }
+    }
+}
+
+/// SWC emits `export = expr` without its `;`, so a next statement starting
+/// with `(`, `[` or a template would continue the expression. Put it back,
+/// or with [`TransformOptions::omit_semicolons`] only where that could
+/// happen.
+///
+/// A module has at most one export assignment, at the top level, so it is
+/// found by emitting its expression on its own and looking for the lines
+/// `export = <expr>`. The `;` lands at the end of the last, after every
+/// mapping on it, so a source map stays valid.
+fn terminate_export_assignment(
+    module: &Module,
+    source_map: &Lrc<SourceMap>,
+    options: &TransformOptions,
+    code: &mut String,
+) -> Result<()> {
+    let Some(assignment) = module.body.iter().find_map(|item| match item {
+        ModuleItem::ModuleDecl(ModuleDecl::TsExportAssignment(assignment)) => Some(assignment),
+        _ => None,
+    }) else {
+        return Ok(());
+    };
+
+    let mut buf = Vec::new();
+    {
+        let mut emitter = Emitter {
+            cfg: swc_ecma_codegen::Config::default().with_target(swc_ecma_ast::EsVersion::latest()),
+            cm: source_map.clone(),
+            comments: None,
+            wr: writer(source_map, &mut buf, None, options),
+        };
+        assignment.expr.emit_with(&mut emitter)?;
+    }
+    let statement = format!("export = {}", String::from_utf8(buf)?);
+    let end = code
+        .match_indices(&statement)
+        .map(|(start, _)| (start, start + statement.len()))
+        .find(|&(start, end)| {
+            (start == 0 || code[..start].ends_with('\n'))
+                && (end == code.len() || code[end..].starts_with('\n'))
+        })
+        .map(|(_, end)| end);
+    if let Some(end) = end {
+        let needed = !options.omit_semicolons
+            || code[end..]
+                .trim_start()
+                .starts_with(semicolons::ASI_HAZARDS);
+        if needed {
+            code.insert(end, ';');
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sc_ast::OperatorHelpers;
+
+    #[test]
+    fn transform_many_matches_transform_per_input() {
+        let inputs = [
+            ("a.ts", "const a = x |> f;"),
+            ("b.ts", "const b = 1 :: [];"),
+            (
+                "c.ts",
+                "interface Functor<F<_>> { map: <A>(fa: F<A>) => F<A>; }",
+            ),
+        ];
+        let options = TransformOptions {
+            source_map: true,
+            ..TransformOptions::default()
+        };
+        let syntax = ScSyntax::default();
+
+        let many = transform_many(&inputs, &syntax, &options);
+        assert_eq!(many.len(), inputs.len());
+        for ((name, source), output) in inputs.iter().zip(many) {
+            let output = output.unwrap();
+            let single = transform(name, source, &syntax, &options).unwrap();
+            assert_eq!(output.code, single.code);
+            assert_eq!(output.map, single.map);
+        }
+    }
+
+    #[test]
+    fn is_valid_sugarcube_says_whether_transform_succeeds() {
+        let syntax = ScSyntax::default();
+        assert!(is_valid_sugarcube(
+            "const xs = 1 :: [] |> f;",
+            "a.ts",
+            &syntax
+        ));
+        assert!(is_valid_sugarcube(
+            "const e = <div>{x |> f}</div>;",
+            "a.tsx",
+            &syntax
+        ));
+        // An unused HKT parameter is only a warning.
+        assert!(is_valid_sugarcube("interface I<F<_>> {}", "a.ts", &syntax));
+
+        // A sugarcube error, an SWC parse error, and JSX outside a .tsx file.
+        assert!(!is_valid_sugarcube("const x = |> f;", "a.ts", &syntax));
+        assert!(!is_valid_sugarcube("const = 1;", "a.ts", &syntax));
+        assert!(!is_valid_sugarcube(
+            "const e = <div>{x |> f}</div>;",
+            "a.ts",
+            &syntax
+        ));
+        let no_pipeline = ScSyntax {
+            pipeline: false,
+            ..ScSyntax::default()
+        };
+        assert!(!is_valid_sugarcube(
+            "const y = x |> f;",
+            "a.ts",
+            &no_pipeline
+        ));
+    }
+
+    #[test]
+    fn transform_in_registers_inputs_in_the_session_map() {
+        let source_map: Lrc<SourceMap> = Default::default();
+        let session = ParseSession::with_source_map(source_map.clone());
+        let syntax = ScSyntax::default();
+        let options = TransformOptions::default();
+        for (name, source) in [("a.ts", "const a = x |> f;"), ("b.ts", "const b = y;")] {
+            let output = transform_in(&session, name, source, &syntax, &options).unwrap();
+            assert_eq!(
+                output.code,
+                transform(name, source, &syntax, &options).unwrap().code
+            );
+        }
+        let names: Vec<_> = source_map
+            .files()
+            .iter()
+            .map(|f| f.name.to_string())
+            .collect();
+        assert_eq!(names, ["a.ts", "b.ts"]);
+    }
+
+    #[test]
+    fn bom_is_dropped_unless_kept() {
+        let source = "\u{FEFF}const x = a |> f;\n";
+        let syntax = ScSyntax::default();
+        let expected = "const x = __binop__(a, \"|>\", f);\n";
+
+        let dropped = transform("bom.ts", source, &syntax, &TransformOptions::default()).unwrap();
+        assert_eq!(dropped.code, expected);
+
+        let options = TransformOptions {
+            keep_bom: true,
+            ..TransformOptions::default()
+        };
+        let kept = transform("bom.ts", source, &syntax, &options).unwrap();
+        assert_eq!(kept.code, format!("\u{FEFF}{expected}"));
+    }
+
+    #[test]
+    fn inputs_without_code_emit_nothing() {
+        let syntax = ScSyntax {
+            bind: true,
+            strict: true,
+            ..ScSyntax::default()
+        };
+        let options = TransformOptions {
+            source_map: true,
+            keep_bom: true,
+            ..TransformOptions::default()
+        };
+        for source in [
+            "",
+            " \n\t\n",
+            "// note\n/* block */",
+            "\u{FEFF}",
+            "\u{FEFF}\n",
+        ] {
+            let preprocessed = sc_parser::preprocess::preprocess_with_report(source, &syntax);
+            assert_eq!(preprocessed.code, source.trim_start_matches('\u{FEFF}'));
+            assert!(preprocessed.report.diagnostics.is_empty(), "{source:?}");
+
+            let output = transform("empty.ts", source, &syntax, &options).unwrap();
+            let expected = if source.starts_with('\u{FEFF}') {
+                "\u{FEFF}"
+            } else {
+                ""
+            };
+            assert_eq!(output.code, expected, "{source:?}");
+            assert!(output.map.is_some());
+        }
+    }
+
+    #[test]
+    fn global_runtime_declares_binop_instead_of_importing() {
+        let syntax = ScSyntax::default();
+        let options = TransformOptions {
+            runtime: Runtime::Global,
+            ..TransformOptions::default()
+        };
+        let declaration = "declare function __binop__(left: any, op: string, right: any): any;\n";
+
+        let source = "/// <reference types=\"node\" />\nconst a = x |> f;\n";
+        let output = transform("global.ts", source, &syntax, &options).unwrap();
+        assert!(!output.code.contains("import"), "{}", output.code);
+        let calls = "/// <reference types=\"node\" />\nconst a = __binop__(x, \"|>\", f);\n";
+        assert_eq!(output.code, format!("{calls}{declaration}"));
+
+        // A helper in use is declared with two parameters.
+        let helpers = ScSyntax {
+            helpers: OperatorHelpers {
+                pipeline: Some("__pipe__".into()),
+                cons: Some("__cons__".into()),
+            },
+            ..ScSyntax::default()
+        };
+        let output = transform("global.ts", "const a = x |> f;\n", &helpers, &options).unwrap();
+        assert_eq!(
+            output.code,
+            "const a = __pipe__(x, f);\ndeclare function __pipe__(left: any, right: any): any;\n"
+        );
+
+        // Nothing to declare: no calls, or the file brings its own `__binop__`.
+        for source in [
+            "const a = x;\n",
+            "const f = g::h;\n",
+            "import { __binop__ } from \"rt\";\nconst a = x |> f;\n",
+        ] {
+            let syntax = ScSyntax {
+                bind: true,
+                ..ScSyntax::default()
+            };
+            let output = transform("global.ts", source, &syntax, &options).unwrap();
+            assert!(!output.code.contains("declare"), "{}", output.code);
+        }
+
+        let output = transform("none.ts", "x |> f;", &syntax, &TransformOptions::default());
+        assert!(!output.unwrap().code.contains("declare"));
+        assert_eq!("global".parse(), Ok(Runtime::Global));
+        assert!("import".parse::<Runtime>().is_err());
+    }
+
+    #[test]
+    fn omit_semicolons_drops_statement_ends() {
+        let syntax = ScSyntax::default();
+        let options = TransformOptions {
+            omit_semicolons: true,
+            runtime: Runtime::Global,
+            ..TransformOptions::default()
+        };
+        let source = "import { f } from \"./f\";\nimport type { T } from \"./t\";\n\
+                      const a: T = x |> f;\n(a |> g)();\nfor (let i = 0; i < 2; i++) { g(i); }\n";
+        let output = transform("asi.ts", source, &syntax, &options).unwrap();
+        assert_eq!(
+            output.code,
+            "import { f } from \"./f\"\nimport type { T } from \"./t\"\n\
+             const a: T = __binop__(x, \"|>\", f)\n;(__binop__(a, \"|>\", g))()\n\
+             for(let i = 0; i < 2; i++){\n    g(i)\n}\n\
+             declare function __binop__(left: any, op: string, right: any): any\n"
+        );
+
+        // `export =` keeps its `;` only where the next line would continue it.
+        let output = transform("asi.ts", "export = x;\n[1].map(f);\n", &syntax, &options);
+        assert_eq!(output.unwrap().code, "export = x;\n[\n    1\n].map(f)\n");
+        let output = transform("asi.ts", "export = x;\ny();\n", &syntax, &options);
+        assert_eq!(output.unwrap().code, "export = x\ny()\n");
+    }
+
+    #[test]
+    fn tsx_generic_arrows_keep_their_trailing_comma() {
+        let syntax = ScSyntax::default();
+        let options = TransformOptions::default();
+        // `T_` and `T$` are taken, so the mark has to use another character.
+        let source = "const T_ = 1, T$ = 2;\nconst f = <T,>(x: T) => x |> g;\n";
+        let tsx = transform("a.tsx", source, &syntax, &options).unwrap();
+        assert_eq!(
+            tsx.code,
+            "const T_ = 1, T$ = 2;\nconst f = <T,>(x: T)=>__binop__(x, \"|>\", g);\n"
+        );
+        transform("out.tsx", &tsx.code, &syntax, &options).unwrap();
+
+        // `.ts` has no JSX to confuse it with.
+        let ts = transform("a.ts", source, &syntax, &options).unwrap();
+        assert!(ts.code.contains("<T>(x: T)"), "{}", ts.code);
+    }
+
+    #[test]
+    fn export_assignment_keeps_its_semicolon() {
+        let syntax = ScSyntax::default();
+        let options = TransformOptions::default();
+        let source = "export = [\n  x |> f,\n];\n[1, 2].forEach(g);\n";
+        let output = transform("cjs.ts", source, &syntax, &options).unwrap();
+        assert_eq!(
+            output.code,
+            "export = [\n    __binop__(x, \"|>\", f)\n];\n[\n    1,\n    2\n].forEach(g);\n"
+        );
+
+        // Text that only looks like the statement is left alone.
+        let source = "const s = `\nexport = a`;\nexport = a;\n";
+        let output = transform("cjs.ts", source, &syntax, &options).unwrap();
+        assert_eq!(output.code, "const s = `\nexport = a`;\nexport = a;\n");
+    }
+
+    #[test]
+    fn indent_sets_each_level() {
+        let syntax = ScSyntax::default();
+        let source =
+            "function f() {\n  if (a) {\n    return x |> g;\n  }\n}\nexport = {\n  f,\n};\n";
+        let emit = |indent| {
+            let options = TransformOptions {
+                indent,
+                ..TransformOptions::default()
+            };
+            transform("indent.ts", source, &syntax, &options)
+                .unwrap()
+                .code
+        };
+        let body = |pad: &str| {
+            format!(
+                "function f() {{\n{pad}if (a) {{\n{pad}{pad}return __binop__(x, \"|>\", g);\n{pad}}}\n}}\nexport = {{\n{pad}f\n}};\n"
+            )
+        };
+        assert_eq!(emit(Indent::default()), body("    "));
+        assert_eq!(emit(Indent::Spaces(2)), body("  "));
+        assert_eq!(emit(Indent::Tab), body("\t"));
+
+        assert_eq!("tab".parse(), Ok(Indent::Tab));
+        assert_eq!("2".parse(), Ok(Indent::Spaces(2)));
+        assert!("0".parse::<Indent>().is_err());
+        assert!("17".parse::<Indent>().is_err());
+    }
+
+    #[test]
+    fn output_map_chains_through_input_map() {
+        // The input was generated from `authored.ts`: its two lines came from
+        // lines 4 and 5 there (0-based 3 and 4).
+        let input_map =
+            r#"{"version":3,"sources":["authored.ts"],"names":[],"mappings":"AAGA;AACA"}"#;
+        let source = "const x = a |> f;\nconst y = b |> g;\n";
+        let options = TransformOptions {
+            source_map: true,
+            input_source_map: Some(input_map.to_string()),
+            ..TransformOptions::default()
+        };
+        let output = transform("generated.ts", source, &ScSyntax::default(), &options).unwrap();
+
+        let map = swc_sourcemap::SourceMap::from_slice(output.map.unwrap().as_bytes()).unwrap();
+        let sources: Vec<_> = map.sources().map(|s| s.to_string()).collect();
+        assert_eq!(sources, ["authored.ts"]);
+        assert!(output.code.lines().nth(1).unwrap().starts_with("const y"));
+        let token = map.lookup_token(1, 0).unwrap();
+        assert_eq!((token.get_src_line(), token.get_src_col()), (4, 0));
+        assert_eq!(map.lookup_token(0, 10).unwrap().get_src_line(), 3);
+    }
+
+    #[test]
+    fn bad_input_map_is_an_error() {
+        let options = TransformOptions {
+            source_map: true,
+            input_source_map: Some("not json".to_string()),
+            ..TransformOptions::default()
+        };
+        let err = transform("a.ts", "const a = 1;", &ScSyntax::default(), &options).unwrap_err();
+        assert_eq!(err.to_string(), "failed to parse input source map");
+    }
+
+    #[test]
+    fn transform_many_keeps_going_after_a_failure() {
+        let inputs = [
+            ("bad.ts", "const x = |> f;"),
+            ("good.ts", "const y = a |> g;"),
+        ];
+        let results = transform_many(&inputs, &ScSyntax::default(), &TransformOptions::default());
+        assert!(results[0].is_err());
+        assert_eq!(
+            results[1].as_ref().unwrap().code,
+            "const y = __binop__(a, \"|>\", g);\n"
+        );
+    }
+}
@@ -1,42 +1,187 @@
-use std::path::PathBuf;
+mod config;
+mod diff;
+
+use std::collections::hash_map::{Entry, HashMap};
+use std::fmt;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{ColorChoice, Parser, Subcommand};
 use sc_ast::ScSyntax;
-use sc_desugar::desugar_module;
-use sc_parser::parse_sugarcube;
-use swc_common::source_map::DefaultSourceMapGenConfig;
-use swc_ecma_codegen::{text_writer::JsWriter, Emitter, Node};
+use sc_cli::{
+    transform, transform_in, Indent, ParseSession, Runtime, Timings, TransformOptions,
+    TransformOutput,
+};
+use sc_parser::preprocess::{
+    analyze_hkt, line_col, preprocess_with_passes, preprocess_with_report, render_diagnostic,
+    render_diagnostic_colored, Associativity, HktInfo, HktPass, PreprocessReport, Rewrite,
+    RewriteKind, Severity,
+};
+use sc_parser::{parse_sugarcube, PreprocessError};
 
 #[derive(Parser)]
-#[command(name = "sc", about = "sugarcube — TypeScript with extended syntax")]
+#[command(
+    name = "sc",
+    version,
+    about = "sugarcube — TypeScript with extended syntax"
+)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Color sugarcube's diagnostics: `auto` when stderr is a terminal and
+    /// `NO_COLOR` isn't set.
+    #[arg(long, global = true, value_name = "WHEN", default_value = "auto")]
+    color: ColorChoice,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Parse, desugar, and emit standard TypeScript.
     Preprocess {
-        /// Input .ts/.tsx file.
-        input: PathBuf,
+        /// Input .ts/.tsx file(s). More than one requires `--dry-run` or
+        /// `--out-dir`, which also accepts directories.
+        #[arg(required = true)]
+        input: Vec<PathBuf>,
         /// Output file (stdout if omitted).
         #[arg(short, long)]
         output: Option<PathBuf>,
+        /// Write nothing; instead fail, printing a unified diff, if the
+        /// output differs from this existing file (e.g. a committed build).
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with_all = ["output", "out_dir", "source_map", "dry_run"]
+        )]
+        assert_output: Option<PathBuf>,
+        /// Overwrite the input with its output, for a one-time migration
+        /// to standard TypeScript. Unlike other outputs, this keeps every
+        /// comment, JSDoc included, except one inside a rewritten `|>` or
+        /// `::`, which gets a warning. The input is left as it was if the
+        /// transform fails.
+        #[arg(
+            long,
+            conflicts_with_all = ["output", "assert_output", "out_dir", "source_map", "dry_run"]
+        )]
+        in_place: bool,
+        /// With `--in-place`, first copy the input to `<input>.bak`.
+        #[arg(long, requires = "in_place")]
+        backup: bool,
+        /// Write one output per input into this directory. Directory inputs
+        /// are searched recursively for .ts/.tsx/.mts/.cts files (not .d.ts)
+        /// and keep their layout below it.
+        #[arg(long, conflicts_with_all = ["output", "input_source_map", "dry_run"])]
+        out_dir: Option<PathBuf>,
+        /// Extension for files written to `--out-dir` (default: keep the
+        /// input's). `js` maps .ts/.tsx/.mts/.cts to .js/.jsx/.mjs/.cjs; any
+        /// other value is used for every file.
+        #[arg(long, requires = "out_dir")]
+        out_ext: Option<String>,
+        /// Write a JSON manifest of the `--out-dir` run to this file: for
+        /// each input, its output path, a hash of the output, its rewrite
+        /// counts, and the time it took.
+        #[arg(long, requires = "out_dir")]
+        report: Option<PathBuf>,
         /// Treat the file as TSX.
         #[arg(long)]
         tsx: bool,
         /// Generate a source map.
         #[arg(long)]
         source_map: bool,
+        /// Source map of the input, if it was itself generated. The output
+        /// map is chained through it back to the authored file.
+        #[arg(long, requires = "source_map")]
+        input_source_map: Option<PathBuf>,
+        /// Keep a leading UTF-8 BOM in the output if the input has one.
+        #[arg(long)]
+        keep_bom: bool,
+        /// Indentation of the output: `tab`, or a number of spaces
+        /// (default 4).
+        #[arg(long, value_name = "N|tab")]
+        indent: Option<Indent>,
+        /// `global` to end each output that calls `__binop__` with an
+        /// ambient declaration of it, for runtimes that provide it globally
+        /// (default `none`).
+        #[arg(long, value_name = "none|global")]
+        runtime: Option<Runtime>,
+        /// Start the output with this text as a block comment, after any
+        /// shebang: a license header, say. May span lines; can't contain
+        /// `*/`.
+        #[arg(long, value_name = "TEXT")]
+        banner: Option<String>,
+        /// Keep the block comment the input starts with, though other
+        /// comments are dropped. It follows `--banner`.
+        #[arg(long)]
+        preserve_banner: bool,
+        /// Leave out statement-ending semicolons, for no-semicolon codebases.
+        #[arg(long, overrides_with = "semicolons")]
+        no_semicolons: bool,
+        /// End statements with semicolons, even if sugarcube.toml says not to.
+        #[arg(long, overrides_with = "no_semicolons")]
+        semicolons: bool,
+        /// Leave `|>` alone.
+        #[arg(long, overrides_with = "pipeline")]
+        no_pipeline: bool,
+        /// Rewrite `|>`, even if sugarcube.toml turns it off.
+        #[arg(long, overrides_with = "no_pipeline")]
+        pipeline: bool,
+        /// Leave `::` alone.
+        #[arg(long, overrides_with = "cons")]
+        no_cons: bool,
+        /// Rewrite `::`, even if sugarcube.toml turns it off.
+        #[arg(long, overrides_with = "no_cons")]
+        cons: bool,
+        /// Leave `F<_>` type parameters and their usages alone.
+        #[arg(long, overrides_with = "hkt")]
+        no_hkt: bool,
+        /// Rewrite `F<_>` type parameters, even if sugarcube.toml turns
+        /// them off.
+        #[arg(long, overrides_with = "no_hkt")]
+        hkt: bool,
+        /// Run only the HKT rewrite and print the text as it comes out,
+        /// leaving `|>` and `::` as written: the types of the output are
+        /// standard, for `tsc --emitDeclarationOnly`, while its values still
+        /// need the full transform.
+        #[arg(long, conflicts_with_all = ["no_hkt", "out_dir", "source_map", "dry_run"])]
+        hkt_only: bool,
+        /// Print the syntax features and output options in effect for each
+        /// input, as JSON on stderr, before running.
+        #[arg(long)]
+        print_config: bool,
+        /// Print each file's rewrite counts and the time spent
+        /// preprocessing, parsing and emitting it on stderr.
+        #[arg(long, conflicts_with = "dry_run")]
+        stats: bool,
+        /// Report which files contain sugarcube syntax and how many rewrites
+        /// each would get, without writing any output.
+        #[arg(long)]
+        dry_run: bool,
     },
-    /// Parse the file and report any syntax errors.
+    /// Parse the files and report any syntax errors.
     Check {
-        input: PathBuf,
+        #[arg(required_unless_present_any = ["since", "stdin"])]
+        input: Vec<PathBuf>,
+        /// Check the .ts/.tsx/.mts/.cts files (not .d.ts) that `git diff`
+        /// shows as changed since this ref, e.g. `HEAD` in a pre-commit hook.
+        #[arg(long, value_name = "REF", conflicts_with_all = ["input", "stdin"])]
+        since: Option<String>,
+        /// Read the files to check from stdin, one per line. Files that
+        /// aren't sugarcube sources are skipped.
+        #[arg(long, conflicts_with = "input")]
+        stdin: bool,
         #[arg(long)]
         tsx: bool,
     },
+    /// List every sugarcube construct with its span and operands, without
+    /// rewriting anything.
+    Analyze {
+        input: PathBuf,
+        /// Print a JSON array instead of one line per construct.
+        #[arg(long)]
+        json: bool,
+    },
     /// Parse and dump the AST as JSON.
     Parse {
         input: PathBuf,
@@ -47,83 +192,345 @@ enum Commands {
     },
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+/// Exit statuses. These are part of the CLI's interface: scripts and CI
+/// branch on them, so don't renumber.
+const EXIT_ERROR: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_IO: u8 = 3;
+
+/// A command line that clap accepted but that doesn't make sense.
+#[derive(Debug)]
+struct UsageError(&'static str);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+/// An input that can't be read as source text. Added as context to the
+/// underlying error, which stays in the chain.
+#[derive(Debug)]
+enum InputError {
+    NotFound(PathBuf),
+    NotUtf8(PathBuf),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::NotFound(path) => write!(f, "input file not found: {}", path.display()),
+            InputError::NotUtf8(path) => {
+                write!(f, "input is not valid UTF-8: {}", path.display())
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(err) => {
+            // `--help` and `--version` come through here too, on stdout.
+            let _ = err.print();
+            return if err.use_stderr() {
+                ExitCode::from(EXIT_USAGE)
+            } else {
+                ExitCode::SUCCESS
+            };
+        }
+    };
+
+    let color = use_color(cli.color);
+    match run(cli, color) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            print_error(&err, color);
+            ExitCode::from(exit_code(&err))
+        }
+    }
+}
+
+/// Whether diagnostics go out in color. An explicit `--color always` wins
+/// over `NO_COLOR`.
+fn use_color(choice: ColorChoice) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                && std::io::stderr().is_terminal()
+        }
+    }
+}
+
+/// Sugarcube's own errors with their source lines, an unreadable input on
+/// one line, and anything else with its cause chain.
+fn print_error(err: &anyhow::Error, color: bool) {
+    if let Some(err) = err.downcast_ref::<PreprocessError>() {
+        let rendered = if color {
+            err.render_colored()
+        } else {
+            err.render()
+        };
+        eprintln!("{rendered}");
+    } else if let Some(err) = err.downcast_ref::<InputError>() {
+        if color {
+            eprintln!("\x1b[1;31merror\x1b[0m: {err}");
+        } else {
+            eprintln!("error: {err}");
+        }
+    } else {
+        eprintln!("Error: {err:?}");
+    }
+}
+
+/// Map a failure to its exit status: usage, then IO anywhere in the cause
+/// chain or an unreadable input, and everything else (parse and desugar
+/// errors) as a plain error.
+fn exit_code(err: &anyhow::Error) -> u8 {
+    if err.is::<UsageError>() {
+        EXIT_USAGE
+    } else if err.is::<InputError>() || err.chain().any(|cause| cause.is::<std::io::Error>()) {
+        EXIT_IO
+    } else {
+        EXIT_ERROR
+    }
+}
 
+fn run(cli: Cli, color: bool) -> Result<()> {
     match cli.command {
         Commands::Preprocess {
             input,
             output,
+            assert_output,
+            in_place,
+            backup,
+            out_dir,
+            out_ext,
+            report,
             tsx,
             source_map,
+            input_source_map,
+            keep_bom,
+            indent,
+            runtime,
+            banner,
+            preserve_banner,
+            no_semicolons,
+            semicolons,
+            no_pipeline,
+            pipeline,
+            no_cons,
+            cons,
+            no_hkt,
+            hkt,
+            hkt_only,
+            print_config,
+            stats,
+            dry_run,
         } => {
-            let source = std::fs::read_to_string(&input)?;
+            let input_map = input_source_map.as_deref().map(read_source).transpose()?;
+            // Each file's own sugarcube.toml, with the flags on top.
+            let mut configs = config::Configs::default();
+            let settings = |config: &config::Config| -> Result<(ScSyntax, TransformOptions)> {
+                let syntax = ScSyntax {
+                    pipeline: switch(pipeline, no_pipeline, config.syntax.pipeline),
+                    cons: switch(cons, no_cons, config.syntax.cons),
+                    hkt: switch(hkt, no_hkt, config.syntax.hkt),
+                    ..config.syntax.clone()
+                };
+                let options = TransformOptions {
+                    tsx: if tsx { Some(true) } else { None },
+                    source_map,
+                    input_source_map: input_map.clone(),
+                    keep_bom,
+                    indent: indent.or(config.output.indent).unwrap_or_default(),
+                    runtime: runtime.or(config.output.runtime).unwrap_or_default(),
+                    omit_semicolons: !switch(
+                        semicolons,
+                        no_semicolons,
+                        config.output.semicolons.unwrap_or(true),
+                    ),
+                    banner: banner.clone().or(config.output.banner.clone()),
+                    preserve_banner: preserve_banner
+                        || config.output.preserve_banner.unwrap_or(false),
+                    keep_comments: in_place,
+                };
+                if options
+                    .banner
+                    .as_deref()
+                    .is_some_and(|text| text.contains("*/"))
+                {
+                    return Err(UsageError("a banner can't contain `*/`").into());
+                }
+                Ok((syntax, options))
+            };
+            if print_config {
+                for path in &input {
+                    let config = configs.discover(path)?;
+                    let (syntax, options) = settings(config)?;
+                    let config = config_json(
+                        &syntax,
+                        &options,
+                        input_source_map.as_deref(),
+                        config.path.as_deref(),
+                    )?;
+                    eprintln!("{}", serde_json::to_string_pretty(&config)?);
+                }
+            }
+            if dry_run {
+                let syntaxes = input
+                    .iter()
+                    .map(|path| Ok(settings(configs.discover(path)?)?.0))
+                    .collect::<Result<Vec<_>>>()?;
+                return print_dry_run(&input, &syntaxes);
+            }
+
+            if let Some(out_dir) = &out_dir {
+                let targets = out_dir_targets(&input, out_dir, out_ext.as_deref())?;
+                let settings = targets
+                    .iter()
+                    .map(|(input, _)| settings(configs.discover(input)?))
+                    .collect::<Result<Vec<_>>>()?;
+                return preprocess_to_dir(&targets, &settings, report.as_deref(), stats, color);
+            }
+
+            let [input] = input.as_slice() else {
+                return Err(
+                    UsageError("multiple input files require --dry-run or --out-dir").into(),
+                );
+            };
+            let (syntax, options) = settings(configs.discover(input)?)?;
+            let source = read_source(input)?;
             let filename = input.display().to_string();
-            let syntax = ScSyntax::default();
+            let transformed = if hkt_only {
+                let started = Instant::now();
+                let preprocessed = preprocess_with_passes(&source, &syntax, &[Box::new(HktPass)]);
+                if preprocessed.report.has_errors() {
+                    return Err(PreprocessError {
+                        filename,
+                        source,
+                        diagnostics: preprocessed
+                            .report
+                            .diagnostics
+                            .into_iter()
+                            .filter(|d| d.severity == Severity::Error)
+                            .collect(),
+                    }
+                    .into());
+                }
+                TransformOutput {
+                    code: preprocessed.code,
+                    map: None,
+                    report: preprocessed.report,
+                    timings: Timings {
+                        preprocess: started.elapsed(),
+                        ..Timings::default()
+                    },
+                }
+            } else {
+                transform(&filename, &source, &syntax, &options)?
+            };
+            print_diagnostics(&filename, &source, &transformed.report, color);
+            if stats {
+                print_stats(&filename, &transformed);
+            }
 
-            let tsx_opt = if tsx { Some(true) } else { None };
-            let parsed = parse_sugarcube(&source, &filename, &syntax, tsx_opt)?;
-            let module = desugar_module(parsed.module);
-
-            let mut buf = Vec::new();
-            let mut srcmap_buf = if source_map { Some(vec![]) } else { None };
-            {
-                let writer = JsWriter::new(
-                    parsed.source_map.clone(),
-                    "\n",
-                    &mut buf,
-                    srcmap_buf.as_mut(),
+            if let Some(path) = &assert_output {
+                let existing = read_source(path)?;
+                let diff = diff::unified_diff(
+                    &existing,
+                    &transformed.code,
+                    &path.display().to_string(),
+                    &format!("{filename} (preprocessed)"),
                 );
-                let mut emitter = Emitter {
-                    cfg: swc_ecma_codegen::Config::default()
-                        .with_target(swc_ecma_ast::EsVersion::latest()),
-                    cm: parsed.source_map.clone(),
-                    comments: None,
-                    wr: writer,
-                };
-                module.emit_with(&mut emitter)?;
+                if !diff.is_empty() {
+                    print!("{diff}");
+                    anyhow::bail!("{} is out of date with {filename}", path.display());
+                }
+                return Ok(());
             }
 
-            let output_str = String::from_utf8(buf)?;
+            if in_place {
+                if backup {
+                    let backup = format!("{filename}.bak");
+                    std::fs::copy(input, &backup)
+                        .with_context(|| format!("failed to write {backup}"))?;
+                }
+                return std::fs::write(input, &transformed.code)
+                    .with_context(|| format!("failed to write {filename}"));
+            }
 
             match &output {
-                Some(path) => std::fs::write(path, &output_str)?,
-                None => print!("{output_str}"),
-            }
-
-            if source_map {
-                if let Some(srcmap_data) = srcmap_buf {
-                    let srcmap = parsed
-                        .source_map
-                        .build_source_map(&srcmap_data, None, DefaultSourceMapGenConfig);
-                    let mut srcmap_json = vec![];
-                    srcmap
-                        .to_writer(&mut srcmap_json)
-                        .context("failed to serialize source map")?;
-                    let srcmap_str = String::from_utf8(srcmap_json)?;
-
-                    let map_path = match &output {
-                        Some(path) => format!("{}.map", path.display()),
-                        None => format!("{filename}.map"),
-                    };
-                    std::fs::write(&map_path, &srcmap_str)?;
-                    eprintln!("Source map written to {map_path}");
-                }
+                Some(path) => std::fs::write(path, &transformed.code)
+                    .with_context(|| format!("failed to write {}", path.display()))?,
+                None => print!("{}", transformed.code),
             }
+
+            if let Some(srcmap) = &transformed.map {
+                let map_path = match &output {
+                    Some(path) => format!("{}.map", path.display()),
+                    None => format!("{filename}.map"),
+                };
+                std::fs::write(&map_path, srcmap)
+                    .with_context(|| format!("failed to write {map_path}"))?;
+                eprintln!("Source map written to {map_path}");
+            }
+        }
+        Commands::Check {
+            input,
+            since,
+            stdin,
+            tsx,
+        } => {
+            let files = match &since {
+                Some(rev) => changed_since(rev)?,
+                None if stdin => std::io::stdin()
+                    .lines()
+                    .map(|line| Ok(PathBuf::from(line.context("failed to read stdin")?)))
+                    .filter(|path| path.as_ref().map_or(true, |p| is_source_file(p)))
+                    .collect::<Result<_>>()?,
+                None => input,
+            };
+            check_files(&files, tsx, color)?;
         }
-        Commands::Check { input, tsx } => {
-            let source = std::fs::read_to_string(&input)?;
+        Commands::Analyze { input, json } => {
+            let source = read_source(&input)?;
             let filename = input.display().to_string();
-            let syntax = ScSyntax::default();
+            let syntax = config::discover(&input)?.syntax;
+            let report = preprocess_with_report(&source, &syntax).report;
+            print_diagnostics(&filename, &source, &report, color);
+            if report.has_errors() {
+                anyhow::bail!("failed to analyze {filename}");
+            }
 
-            let tsx_opt = if tsx { Some(true) } else { None };
-            parse_sugarcube(&source, &filename, &syntax, tsx_opt)?;
-            eprintln!("OK: {filename}");
+            // Rewrites are recorded innermost-first; list them in source
+            // order, each construct before the ones nested inside it.
+            let mut rewrites = report.rewrites;
+            rewrites.sort_by_key(|r| (r.span.start, std::cmp::Reverse(r.span.end)));
+
+            if json {
+                let hkts = analyze_hkt(&source);
+                let constructs: Vec<_> = rewrites
+                    .iter()
+                    .map(|rewrite| construct_json(&source, rewrite, &hkts))
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&constructs)?);
+            } else {
+                for rewrite in &rewrites {
+                    let (line, col) = line_col(&source, rewrite.span.start);
+                    let text = &source[rewrite.span.clone()];
+                    println!("{filename}:{line}:{col}: {} `{text}`", rewrite.kind.name());
+                }
+            }
         }
         Commands::Parse { input, ast, tsx } => {
-            let source = std::fs::read_to_string(&input)?;
+            let source = read_source(&input)?;
             let filename = input.display().to_string();
-            let syntax = ScSyntax::default();
+            let syntax = config::discover(&input)?.syntax;
 
             let tsx_opt = if tsx { Some(true) } else { None };
             let parsed = parse_sugarcube(&source, &filename, &syntax, tsx_opt)?;
@@ -139,3 +546,445 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// A feature both `--x` and `--no-x` can set: the last one given wins
+/// (clap clears the other), and `default` stands without either.
+fn switch(on: bool, off: bool, default: bool) -> bool {
+    if on {
+        true
+    } else if off {
+        false
+    } else {
+        default
+    }
+}
+
+/// Check each file, reporting each as it's done. A file that fails doesn't
+/// stop the rest; the command fails at the end if any did.
+fn check_files(files: &[PathBuf], tsx: bool, color: bool) -> Result<()> {
+    let tsx = if tsx { Some(true) } else { None };
+    let mut configs = config::Configs::default();
+    let mut failed = 0;
+    for input in files {
+        let checked = read_source(input).and_then(|source| {
+            let filename = input.display().to_string();
+            let syntax = &configs.discover(input)?.syntax;
+            let parsed = parse_sugarcube(&source, &filename, syntax, tsx)?;
+            print_diagnostics(&filename, &source, &parsed.report, color);
+            eprintln!("OK: {filename}");
+            Ok(())
+        });
+        if let Err(err) = checked {
+            // A single file keeps its error, and so its exit status.
+            if files.len() == 1 {
+                return Err(err);
+            }
+            print_error(&err, color);
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("failed to check {failed} of {} files", files.len());
+    }
+    Ok(())
+}
+
+/// The sugarcube sources that `git diff` shows as changed between `rev` and
+/// the working tree, staged or not. Deleted files are left out.
+fn changed_since(rev: &str) -> Result<Vec<PathBuf>> {
+    let root = git(&["rev-parse", "--show-toplevel"])?;
+    let root = Path::new(root.trim_end());
+    // `-z` so git doesn't quote unusual paths.
+    let changed = git(&[
+        "diff",
+        "--name-only",
+        "-z",
+        "--diff-filter=d",
+        "--end-of-options",
+        rev,
+        "--",
+    ])?;
+    Ok(changed
+        .split('\0')
+        .filter(|name| !name.is_empty())
+        .map(|name| root.join(name))
+        .filter(|path| is_source_file(path))
+        .collect())
+}
+
+/// Run git in the current directory and return what it printed.
+fn git(args: &[&str]) -> Result<String> {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "`git {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    String::from_utf8(output.stdout).context("git printed a non-UTF-8 path")
+}
+
+fn read_source(path: &Path) -> Result<String> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Err(err).context(InputError::NotFound(path.to_path_buf()));
+        }
+        Err(err) => return Err(err).with_context(|| format!("failed to read {}", path.display())),
+    };
+    String::from_utf8(bytes).context(InputError::NotUtf8(path.to_path_buf()))
+}
+
+/// `(input, output)` pairs for `preprocess --out-dir`: a file input goes
+/// directly into `out_dir`, a directory input's files keep their paths
+/// relative to it.
+fn out_dir_targets(
+    inputs: &[PathBuf],
+    out_dir: &Path,
+    out_ext: Option<&str>,
+) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let mut targets = Vec::new();
+    for input in inputs {
+        if input.is_dir() {
+            for file in source_files(input)? {
+                let relative = file.strip_prefix(input)?;
+                let output = with_out_ext(&out_dir.join(relative), out_ext);
+                targets.push((file, output));
+            }
+        } else {
+            let name = input
+                .file_name()
+                .ok_or(UsageError("--out-dir inputs must be files or directories"))?;
+            targets.push((input.clone(), with_out_ext(&out_dir.join(name), out_ext)));
+        }
+    }
+
+    // The same file given twice (`src src/x.ts`) is written once; two files
+    // with one output (`a/x.ts b/x.ts`) would overwrite each other.
+    let mut inputs_by_output = HashMap::new();
+    let mut duplicate = false;
+    targets.retain(
+        |(input, output)| match inputs_by_output.entry(output.clone()) {
+            Entry::Vacant(entry) => {
+                entry.insert(input.canonicalize().ok());
+                true
+            }
+            Entry::Occupied(entry) => {
+                if *entry.get() != input.canonicalize().ok() {
+                    duplicate = true;
+                }
+                false
+            }
+        },
+    );
+    if duplicate {
+        return Err(UsageError("two --out-dir inputs would write the same output").into());
+    }
+
+    for (input, output) in &targets {
+        let same = output.exists() && output.canonicalize().ok() == input.canonicalize().ok();
+        if same {
+            return Err(
+                UsageError("an --out-dir output would overwrite its input; use --out-ext").into(),
+            );
+        }
+    }
+    Ok(targets)
+}
+
+/// The sugarcube sources under `dir`, recursively, in path order.
+fn source_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+    for entry in entries {
+        let path = entry
+            .with_context(|| format!("failed to read {}", dir.display()))?
+            .path();
+        if path.is_dir() {
+            files.extend(source_files(&path)?);
+            continue;
+        }
+        if is_source_file(&path) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether `path` names a .ts/.tsx/.mts/.cts file that isn't a .d.ts.
+fn is_source_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let is_source = [".ts", ".tsx", ".mts", ".cts"]
+        .iter()
+        .any(|ext| name.ends_with(ext));
+    is_source && !name.ends_with(".d.ts")
+}
+
+/// `path` with the `--out-ext` extension, if one was given.
+fn with_out_ext(path: &Path, out_ext: Option<&str>) -> PathBuf {
+    let Some(out_ext) = out_ext else {
+        return path.to_path_buf();
+    };
+    let ext = match (out_ext, path.extension().and_then(|e| e.to_str())) {
+        ("js", Some("tsx")) => "jsx",
+        ("js", Some("mts")) => "mjs",
+        ("js", Some("cts")) => "cjs",
+        (ext, _) => ext.trim_start_matches('.'),
+    };
+    path.with_extension(ext)
+}
+
+/// Transform every `(input, output)` pair with its settings, writing outputs
+/// (and maps) as they succeed. A file that fails to transform doesn't stop
+/// the rest; the command fails at the end if any did, after writing the
+/// `--report` manifest.
+fn preprocess_to_dir(
+    targets: &[(PathBuf, PathBuf)],
+    settings: &[(ScSyntax, TransformOptions)],
+    report: Option<&Path>,
+    stats: bool,
+    color: bool,
+) -> Result<()> {
+    let inputs = targets
+        .iter()
+        .map(|(input, _)| Ok((input.display().to_string(), read_source(input)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut failed = 0;
+    let mut manifest = Vec::with_capacity(targets.len());
+    let session = ParseSession::new();
+    for (((filename, source), (_, output)), (syntax, options)) in
+        inputs.iter().zip(targets).zip(settings)
+    {
+        let started = Instant::now();
+        let result = transform_in(&session, filename, source, syntax, options);
+        let elapsed = started.elapsed();
+        let mut entry = serde_json::json!({
+            "input": filename,
+            "output": output.display().to_string(),
+            "elapsed_ms": elapsed.as_secs_f64() * 1000.0,
+        });
+        let transformed = match result {
+            Ok(transformed) => transformed,
+            Err(err) => {
+                entry["error"] = err.to_string().into();
+                manifest.push(entry);
+                print_error(&err, color);
+                failed += 1;
+                continue;
+            }
+        };
+        print_diagnostics(filename, source, &transformed.report, color);
+        if stats {
+            print_stats(filename, &transformed);
+        }
+        entry["hash"] = content_hash(&transformed.code).into();
+        entry["rewrites"] = KINDS
+            .iter()
+            .map(|&kind| {
+                (
+                    kind.name().to_string(),
+                    transformed.report.count(kind).into(),
+                )
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into();
+        manifest.push(entry);
+
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        std::fs::write(output, &transformed.code)
+            .with_context(|| format!("failed to write {}", output.display()))?;
+        if let Some(srcmap) = &transformed.map {
+            let map_path = format!("{}.map", output.display());
+            std::fs::write(&map_path, srcmap)
+                .with_context(|| format!("failed to write {map_path}"))?;
+        }
+    }
+
+    if let Some(path) = report {
+        // Sorted so the same tree always gives the same manifest, whatever
+        // order the inputs were named in.
+        manifest.sort_by(|a, b| a["input"].as_str().cmp(&b["input"].as_str()));
+        let json = serde_json::to_string_pretty(&serde_json::json!({ "files": manifest }))?;
+        std::fs::write(path, json + "\n")
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+
+    if failed > 0 {
+        anyhow::bail!("failed to preprocess {failed} of {} files", targets.len());
+    }
+    Ok(())
+}
+
+/// A hex SipHash-1-3 (128-bit, fixed keys) of `code`: stable across runs,
+/// platforms and sc versions, so a build cache can compare it between runs.
+fn content_hash(code: &str) -> String {
+    use siphasher::sip128::{Hasher128, SipHasher13};
+    use std::hash::Hasher;
+
+    let mut hasher = SipHasher13::new();
+    hasher.write(code.as_bytes());
+    format!("{:032x}", hasher.finish128().as_u128())
+}
+
+fn print_diagnostics(filename: &str, source: &str, report: &PreprocessReport, color: bool) {
+    let render = if color {
+        render_diagnostic_colored
+    } else {
+        render_diagnostic
+    };
+    for diag in &report.diagnostics {
+        eprintln!("{}\n", render(filename, source, diag));
+    }
+}
+
+/// One `sc analyze --json` entry. Spans are byte offsets into the original
+/// source, with 1-based line/column of the start. `hkts` supplies the arity
+/// of each HKT declaration.
+fn construct_json(source: &str, rewrite: &Rewrite, hkts: &[HktInfo]) -> serde_json::Value {
+    let span = |range: &std::ops::Range<usize>| {
+        let (line, col) = line_col(source, range.start);
+        serde_json::json!({
+            "start": range.start,
+            "end": range.end,
+            "line": line,
+            "col": col,
+            "text": &source[range.clone()],
+        })
+    };
+
+    let mut entry = serde_json::json!({
+        "kind": rewrite.kind.name(),
+        "span": span(&rewrite.span),
+    });
+    let fields = match rewrite.kind.operator() {
+        Some(op) => serde_json::json!({
+            "operator": {
+                "text": op.text,
+                "span": span(&rewrite.head),
+                "precedence": op.precedence,
+                "associativity": match op.associativity {
+                    Associativity::Left => "left",
+                    Associativity::Right => "right",
+                },
+            },
+            "left": rewrite.operands.first().map(span),
+            "right": rewrite.operands.get(1).map(span),
+        }),
+        None if rewrite.kind == RewriteKind::HktDecl => serde_json::json!({
+            "name": span(&rewrite.head),
+            "arity": hkts
+                .iter()
+                .find(|hkt| hkt.span == rewrite.span)
+                .map(|hkt| hkt.arity),
+        }),
+        None => serde_json::json!({
+            "name": span(&rewrite.head),
+            "args": rewrite.operands.first().map(span),
+        }),
+    };
+    if let (Some(entry), serde_json::Value::Object(fields)) = (entry.as_object_mut(), fields) {
+        entry.extend(fields);
+    }
+    entry
+}
+
+/// The rewrite kinds counted by `--dry-run`, `--report` and `--stats`, in
+/// column order.
+const KINDS: [RewriteKind; 5] = [
+    RewriteKind::Pipeline,
+    RewriteKind::Cons,
+    RewriteKind::Bind,
+    RewriteKind::HktDecl,
+    RewriteKind::HktUsage,
+];
+
+/// `preprocess --stats` for one file, on stderr so the output on stdout is
+/// unchanged: a row per rewrite kind, then per stage.
+fn print_stats(filename: &str, transformed: &TransformOutput) {
+    let timings = &transformed.timings;
+    eprintln!("{filename}:");
+    for kind in KINDS {
+        eprintln!("  {:<11} {}", kind.name(), transformed.report.count(kind));
+    }
+    for (stage, time) in [
+        ("preprocess", timings.preprocess),
+        ("parse", timings.parse),
+        ("emit", timings.emit),
+    ] {
+        eprintln!("  {stage:<11} {:.3} ms", time.as_secs_f64() * 1000.0);
+    }
+}
+
+/// What `preprocess --print-config` prints: the `sugarcube.toml` read, if
+/// any, the `ScSyntax` as it serializes, and the output options in their
+/// CLI spelling.
+fn config_json(
+    syntax: &ScSyntax,
+    options: &TransformOptions,
+    input_source_map: Option<&Path>,
+    config_file: Option<&Path>,
+) -> Result<serde_json::Value> {
+    Ok(serde_json::json!({
+        "config_file": config_file.map(|path| path.display().to_string()),
+        "syntax": serde_json::to_value(syntax)?,
+        "options": {
+            "tsx": options.tsx,
+            "source_map": options.source_map,
+            "input_source_map": input_source_map.map(|path| path.display().to_string()),
+            "keep_bom": options.keep_bom,
+            "indent": options.indent.to_string(),
+            "runtime": options.runtime.to_string(),
+            "omit_semicolons": options.omit_semicolons,
+            "banner": options.banner,
+            "preserve_banner": options.preserve_banner,
+        },
+    }))
+}
+
+/// Print a per-file table of rewrite counts for `preprocess --dry-run`.
+fn print_dry_run(inputs: &[PathBuf], syntaxes: &[ScSyntax]) -> Result<()> {
+    let mut rows = Vec::with_capacity(inputs.len());
+    let mut totals = [0usize; KINDS.len()];
+    for (input, syntax) in inputs.iter().zip(syntaxes) {
+        let source = read_source(input)?;
+        let report = preprocess_with_report(&source, syntax).report;
+        let counts = KINDS.map(|kind| report.count(kind));
+        for (total, count) in totals.iter_mut().zip(counts) {
+            *total += count;
+        }
+        rows.push((input.display().to_string(), counts));
+    }
+    rows.push(("total".to_string(), totals));
+
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    print!("{:<width$}  {:<7}", "file", "sugar");
+    for kind in KINDS {
+        print!("  {:>9}", kind.name());
+    }
+    println!();
+    for (name, counts) in &rows {
+        let sugar = if counts.iter().any(|&c| c > 0) {
+            "yes"
+        } else {
+            "no"
+        };
+        print!("{name:<width$}  {sugar:<7}");
+        for count in counts {
+            print!("  {count:>9}");
+        }
+        println!();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,169 @@
+//! Emitting in no-semicolon style, for
+//! [`TransformOptions::omit_semicolons`](crate::TransformOptions::omit_semicolons).
+
+use swc_common::{BytePos, Span};
+use swc_ecma_codegen::text_writer::WriteJs;
+
+type Result = std::io::Result<()>;
+
+/// A line starting with one of these would continue the statement before it,
+/// so it keeps a `;` at its start: `;(f)()`, `;[a, b].forEach(g)`.
+pub(crate) const ASI_HAZARDS: [char; 8] = ['(', '[', '`', '+', '-', '/', '*', '<'];
+
+/// A statement-ending `;` the codegen asked for and hasn't been written yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Semi {
+    None,
+    /// Written only if something follows on the same line.
+    Pending,
+    /// A line break followed; written only if the next line starts with an
+    /// [`ASI_HAZARDS`] character.
+    LineEnded,
+}
+
+/// Wraps a writer, leaving out the `;` that ends a statement wherever a line
+/// break ends it too. `for (;;)` headers and empty statements keep theirs: the
+/// codegen writes those as punctuation, not as statement ends.
+///
+/// The `;` that stays is written through `inner` like any other token, so
+/// source-map columns still line up.
+pub(crate) struct NoSemicolons<W: WriteJs> {
+    inner: W,
+    semi: Semi,
+}
+
+impl<W: WriteJs> NoSemicolons<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        NoSemicolons {
+            inner,
+            semi: Semi::None,
+        }
+    }
+
+    /// Write or drop the held `;`, before the token `s`.
+    fn before(&mut self, s: &str) -> Result {
+        match self.semi {
+            Semi::Pending => self.inner.write_semi(None)?,
+            Semi::LineEnded if s.starts_with(ASI_HAZARDS) => {
+                self.inner.write_punct(None, ";", false)?
+            }
+            Semi::LineEnded | Semi::None => {}
+        }
+        self.semi = Semi::None;
+        Ok(())
+    }
+}
+
+impl<W: WriteJs> WriteJs for NoSemicolons<W> {
+    fn increase_indent(&mut self) -> Result {
+        self.inner.increase_indent()
+    }
+
+    fn decrease_indent(&mut self) -> Result {
+        self.inner.decrease_indent()
+    }
+
+    fn write_semi(&mut self, _span: Option<Span>) -> Result {
+        if self.semi == Semi::Pending {
+            self.inner.write_semi(None)?;
+        }
+        self.semi = Semi::Pending;
+        Ok(())
+    }
+
+    fn write_space(&mut self) -> Result {
+        self.before("")?;
+        self.inner.write_space()
+    }
+
+    fn write_keyword(&mut self, span: Option<Span>, s: &'static str) -> Result {
+        self.before(s)?;
+        self.inner.write_keyword(span, s)
+    }
+
+    fn write_operator(&mut self, span: Option<Span>, s: &str) -> Result {
+        self.before(s)?;
+        self.inner.write_operator(span, s)
+    }
+
+    fn write_param(&mut self, s: &str) -> Result {
+        self.before(s)?;
+        self.inner.write_param(s)
+    }
+
+    fn write_property(&mut self, s: &str) -> Result {
+        self.before(s)?;
+        self.inner.write_property(s)
+    }
+
+    fn write_line(&mut self) -> Result {
+        if self.semi == Semi::Pending {
+            self.semi = Semi::LineEnded;
+        }
+        self.inner.write_line()
+    }
+
+    fn write_lit(&mut self, span: Span, s: &str) -> Result {
+        self.before(s)?;
+        self.inner.write_lit(span, s)
+    }
+
+    /// A comment on a line of its own doesn't decide whether the next
+    /// line needs a `;`.
+    fn write_comment(&mut self, s: &str) -> Result {
+        if self.semi == Semi::Pending {
+            self.before(s)?;
+        }
+        self.inner.write_comment(s)
+    }
+
+    fn write_str_lit(&mut self, span: Span, s: &str) -> Result {
+        self.before(s)?;
+        self.inner.write_str_lit(span, s)
+    }
+
+    fn write_str(&mut self, s: &str) -> Result {
+        self.before(s)?;
+        self.inner.write_str(s)
+    }
+
+    fn write_symbol(&mut self, span: Span, s: &str) -> Result {
+        self.before(s)?;
+        self.inner.write_symbol(span, s)
+    }
+
+    /// A `}` on the statement's own line ends it as well as a line break
+    /// does, so the `;` is dropped there too.
+    fn write_punct(
+        &mut self,
+        span: Option<Span>,
+        s: &'static str,
+        commit_pending_semi: bool,
+    ) -> Result {
+        if self.semi == Semi::Pending && !commit_pending_semi {
+            self.semi = Semi::None;
+        } else {
+            self.before(s)?;
+        }
+        self.inner.write_punct(span, s, commit_pending_semi)
+    }
+
+    fn care_about_srcmap(&self) -> bool {
+        self.inner.care_about_srcmap()
+    }
+
+    fn add_srcmap(&mut self, pos: BytePos) -> Result {
+        self.inner.add_srcmap(pos)
+    }
+
+    fn commit_pending_semi(&mut self) -> Result {
+        if self.semi == Semi::Pending {
+            self.before("")?;
+        }
+        self.inner.commit_pending_semi()
+    }
+
+    fn can_ignore_invalid_unicodes(&mut self) -> bool {
+        self.inner.can_ignore_invalid_unicodes()
+    }
+}
@@ -0,0 +1,67 @@
+//! `sc analyze --json`: every construct with its original span, without rewriting.
+
+mod common;
+
+use common::{run_sc, scratch_file};
+
+fn analyze_json(name: &str, source: &str) -> serde_json::Value {
+    let path = scratch_file(name, source);
+    let output = run_sc(["analyze".as_ref(), "--json".as_ref(), path.as_os_str()]);
+    assert!(output.status.success(), "{output:?}");
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn lists_operators_with_operands_in_source_order() {
+    let source = "const l = 1 :: 2 :: [];\nconst r = xs |> f;\n";
+    let json = analyze_json("analyze_operators.ts", source);
+    let entries = json.as_array().unwrap();
+
+    let texts: Vec<_> = entries
+        .iter()
+        .map(|e| {
+            (
+                e["kind"].as_str().unwrap(),
+                e["span"]["text"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        texts,
+        [
+            ("cons", "1 :: 2 :: []"),
+            ("cons", "2 :: []"),
+            ("pipeline", "xs |> f"),
+        ]
+    );
+
+    let cons = &entries[0];
+    assert_eq!(cons["operator"]["text"], "::");
+    assert_eq!(cons["operator"]["precedence"], 5);
+    assert_eq!(cons["operator"]["associativity"], "right");
+    assert_eq!(cons["left"]["text"], "1");
+    assert_eq!(cons["right"]["text"], "2 :: []");
+
+    let pipeline = &entries[2];
+    assert_eq!(pipeline["operator"]["associativity"], "left");
+    assert_eq!(pipeline["span"]["line"], 2);
+    assert_eq!(pipeline["span"]["col"], 11);
+    assert_eq!(pipeline["span"]["start"], source.find("xs").unwrap());
+}
+
+#[test]
+fn lists_hkt_declarations_and_usages() {
+    let source = "interface Bifunctor<F<_, _>> { map: (fab: F<A, B>) => void; }\n";
+    let json = analyze_json("analyze_hkt.ts", source);
+    let entries = json.as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+
+    assert_eq!(entries[0]["kind"], "hkt-decl");
+    assert_eq!(entries[0]["name"]["text"], "F");
+    assert_eq!(entries[0]["arity"], 2);
+    assert!(entries[0].get("operator").is_none());
+
+    assert_eq!(entries[1]["kind"], "hkt-usage");
+    assert_eq!(entries[1]["span"]["text"], "F<A, B>");
+    assert_eq!(entries[1]["args"]["text"], "A, B");
+}
@@ -0,0 +1,50 @@
+//! `sc preprocess --assert-output`: compare with an existing output instead
+//! of writing one.
+
+mod common;
+
+use common::{preprocess, scratch_dir};
+
+#[test]
+fn up_to_date_output_passes_and_stale_output_fails_with_a_diff() {
+    let dir = scratch_dir("assert_output");
+    let input = dir.join("a.ts");
+    let committed = dir.join("a.js");
+    std::fs::write(&input, "const a = x |> f;\nconst b = 1;\n").unwrap();
+    let committed_arg = committed.display().to_string();
+
+    let output = preprocess(&input, &["-o", &committed_arg]);
+    assert!(output.status.success(), "{output:?}");
+    let output = preprocess(&input, &["--assert-output", &committed_arg]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(output.stdout.is_empty());
+
+    std::fs::write(&input, "const a = x |> g;\nconst b = 1;\n").unwrap();
+    let output = preprocess(&input, &["--assert-output", &committed_arg]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    let diff = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        diff,
+        format!(
+            "--- {committed_arg}\n+++ {} (preprocessed)\n@@ -1,2 +1,2 @@\n\
+             -const a = __binop__(x, \"|>\", f);\n+const a = __binop__(x, \"|>\", g);\n \
+             const b = 1;\n",
+            input.display()
+        )
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("is out of date"), "{stderr}");
+    // Nothing was written.
+    let kept = std::fs::read_to_string(&committed).unwrap();
+    assert!(kept.contains("\"|>\", f)"), "{kept}");
+
+    // A missing file to compare with is an IO error.
+    let output = preprocess(
+        &input,
+        &[
+            "--assert-output",
+            &dir.join("nope.js").display().to_string(),
+        ],
+    );
+    assert_eq!(output.status.code(), Some(3), "{output:?}");
+}
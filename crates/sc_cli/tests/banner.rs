@@ -0,0 +1,91 @@
+//! `sc preprocess --banner` and `--preserve-banner`: a header comment at
+//! the top of the output.
+
+mod common;
+
+use std::process::Output;
+
+use common::scratch_file;
+
+fn preprocess(name: &str, source: &str, args: &[&str]) -> Output {
+    common::preprocess(&scratch_file(&format!("banner/{name}"), source), args)
+}
+
+fn stdout(output: Output) -> String {
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+const SOURCE: &str = "\
+#!/usr/bin/env node
+/*!
+ * mylib v1.0
+ * MIT License
+ */
+/// <reference types=\"node\" />
+// dropped
+const ys = xs |> reverse;
+";
+
+const CODE: &str = "\
+/// <reference types=\"node\" />
+const ys = __binop__(xs, \"|>\", reverse);
+";
+
+#[test]
+fn banner_follows_the_shebang() {
+    let output = preprocess(
+        "single.ts",
+        SOURCE,
+        &["--banner", "Generated; do not edit."],
+    );
+    assert_eq!(
+        stdout(output),
+        format!("#!/usr/bin/env node\n/* Generated; do not edit. */\n{CODE}")
+    );
+}
+
+#[test]
+fn multi_line_banner_is_a_starred_block() {
+    let output = preprocess(
+        "multi.ts",
+        "const a = 1;\n",
+        &["--banner", "Line one\n\nLine two"],
+    );
+    assert_eq!(
+        stdout(output),
+        "/*\n * Line one\n *\n * Line two\n */\nconst a = 1;\n"
+    );
+}
+
+#[test]
+fn preserve_banner_keeps_the_leading_block_comment() {
+    let output = preprocess("preserve.ts", SOURCE, &["--preserve-banner"]);
+    assert_eq!(
+        stdout(output),
+        format!("#!/usr/bin/env node\n/*!\n * mylib v1.0\n * MIT License\n */\n{CODE}")
+    );
+
+    // Only a comment the file opens with is a banner.
+    let later = "const a = 1;\n/* not a banner */\nconst b = 2;\n";
+    let output = preprocess("later.ts", later, &["--preserve-banner"]);
+    assert_eq!(stdout(output), "const a = 1;\nconst b = 2;\n");
+}
+
+#[test]
+fn banner_comes_before_the_preserved_one() {
+    let output = preprocess(
+        "both.ts",
+        "/* MIT */\n",
+        &["--banner", "Generated", "--preserve-banner"],
+    );
+    assert_eq!(stdout(output), "/* Generated */\n/* MIT */\n");
+}
+
+#[test]
+fn banner_cannot_close_its_comment() {
+    let output = preprocess("closed.ts", "const a = 1;\n", &["--banner", "a */ b"]);
+    assert_eq!(output.status.code(), Some(2), "{output:?}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("a banner can't contain `*/`"), "{stderr}");
+}
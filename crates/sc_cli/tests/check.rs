@@ -0,0 +1,98 @@
+//! `sc check` over several files: `--since <ref>` and `--stdin`.
+
+mod common;
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use common::{sc, scratch_dir};
+
+fn git(repo: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(["-c", "user.name=sc", "-c", "user.email=sc@example.com"])
+        .args(args)
+        .current_dir(repo)
+        .status()
+        .expect("failed to run git");
+    assert!(status.success(), "git {args:?}");
+}
+
+/// A repository with one commit of `a.ts` and `b.ts`, then `b.ts` broken,
+/// `c.tsx` added, `a.ts` untouched, and `notes.md` and `d.d.ts` changed.
+fn repo(name: &str) -> PathBuf {
+    let repo = scratch_dir(name);
+    git(&repo, &["init", "-q"]);
+    std::fs::write(repo.join("a.ts"), "const a = x |> f;\n").unwrap();
+    std::fs::write(repo.join("b.ts"), "const b = 1 :: [];\n").unwrap();
+    git(&repo, &["add", "."]);
+    git(&repo, &["commit", "-qm", "init"]);
+
+    std::fs::write(repo.join("b.ts"), "const b = |> f;\n").unwrap();
+    std::fs::write(repo.join("c.tsx"), "const c = <p>{y |> g}</p>;\n").unwrap();
+    std::fs::write(repo.join("d.d.ts"), "declare const = ;\n").unwrap();
+    std::fs::write(repo.join("notes.md"), "|>\n").unwrap();
+    git(&repo, &["add", "c.tsx", "d.d.ts", "notes.md"]);
+    repo
+}
+
+fn sc_check(repo: &Path, args: &[&str], stdin: &str) -> Output {
+    let mut child = sc()
+        .arg("check")
+        .args(args)
+        .current_dir(repo)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run sc");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn since_checks_only_changed_sources() {
+    let repo = repo("check_since");
+    let output = sc_check(&repo, &["--since", "HEAD"], "");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{stderr}");
+
+    assert!(stderr.contains("b.ts:1:11"), "{stderr}");
+    assert!(stderr.contains("c.tsx"), "{stderr}");
+    assert!(stderr.contains("failed to check 1 of 2 files"), "{stderr}");
+    assert!(!stderr.contains("a.ts"), "{stderr}");
+    assert!(!stderr.contains("d.d.ts"), "{stderr}");
+
+    // With `b.ts` restored, only `c.tsx` is left.
+    git(&repo, &["checkout", "-q", "b.ts"]);
+    let output = sc_check(&repo, &["--since", "HEAD"], "");
+    assert!(output.status.success(), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stderr.lines().count(), 1, "{stderr}");
+}
+
+#[test]
+fn since_an_unknown_ref_fails() {
+    let repo = repo("check_since_unknown");
+    let output = sc_check(&repo, &["--since", "no-such-ref"], "");
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("git diff"), "{stderr}");
+}
+
+#[test]
+fn stdin_lists_the_files_to_check() {
+    let repo = repo("check_stdin");
+    let output = sc_check(&repo, &["--stdin"], "a.ts\nc.tsx\nnotes.md\n");
+    assert!(output.status.success(), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stderr, "OK: a.ts\nOK: c.tsx\n");
+
+    let output = sc_check(&repo, &["--stdin"], "a.ts\nb.ts\n");
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+}
@@ -0,0 +1,55 @@
+//! Helpers shared by the `sc` integration tests: running the binary, and
+//! scratch files under Cargo's per-target temporary directory.
+
+// Each test binary uses its own subset.
+#![allow(dead_code)]
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// A command for the `sc` binary under test, for runs that need more than
+/// arguments, e.g. stdin or environment variables.
+pub fn sc() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_sc"))
+}
+
+/// Run `sc` with `args` and collect its output.
+pub fn run_sc<I, S>(args: I) -> Output
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    sc().args(args).output().expect("failed to run sc")
+}
+
+/// `sc preprocess <input> <args>`.
+pub fn preprocess(input: &Path, args: &[&str]) -> Output {
+    sc().arg("preprocess")
+        .arg(input)
+        .args(args)
+        .output()
+        .expect("failed to run sc")
+}
+
+/// `path` within the scratch directory. Nothing is created.
+pub fn scratch_path(path: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(path)
+}
+
+/// An empty scratch directory, cleared of whatever an earlier run left.
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = scratch_path(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Write `source` to `path` within the scratch directory, creating its
+/// parent directories.
+pub fn scratch_file(path: &str, source: &str) -> PathBuf {
+    let path = scratch_path(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, source).unwrap();
+    path
+}
@@ -0,0 +1,148 @@
+//! `sugarcube.toml` discovery, and CLI flags overriding it.
+
+mod common;
+
+use std::path::PathBuf;
+
+use common::{preprocess, run_sc, scratch_dir};
+
+fn project(name: &str, config: &str) -> PathBuf {
+    let dir = scratch_dir(name);
+    std::fs::create_dir_all(dir.join("src/nested")).unwrap();
+    std::fs::write(dir.join("sugarcube.toml"), config).unwrap();
+    dir
+}
+
+#[test]
+fn discovered_config_is_overridden_by_flags() {
+    let dir = project(
+        "config_discovery",
+        "[syntax]\nhkt = false\n\n[output]\nindent = 2\n",
+    );
+    let input = dir.join("src/nested/functor.ts");
+    std::fs::write(
+        &input,
+        "interface Functor<F<_>> {\n  map: <A, B>(fa: F<A>) => F<B>;\n}\n",
+    )
+    .unwrap();
+
+    // Found two directories up: with HKT off, `F<_>` reaches SWC as written.
+    let output = preprocess(&input, &[]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+
+    let output = preprocess(&input, &["--hkt", "--print-config"]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout,
+        "interface Functor<F> {\n  map: <A, B>(fa: $<F, A>) => $<F, B>;\n}\n"
+    );
+    let config: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(
+        config["config_file"],
+        dir.join("sugarcube.toml").display().to_string()
+    );
+    assert_eq!(config["syntax"]["hkt"], true);
+    assert_eq!(config["options"]["indent"], "2");
+
+    // The last of `--hkt` and `--no-hkt` wins.
+    let output = preprocess(&input, &["--hkt", "--no-hkt"]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+}
+
+#[test]
+fn invalid_config_is_an_error() {
+    let dir = project("config_invalid", "[output]\nindent = \"wide\"\n");
+    let input = dir.join("src/a.ts");
+    std::fs::write(&input, "const a = x |> f;\n").unwrap();
+
+    let output = preprocess(&input, &[]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("invalid "), "{stderr}");
+    assert!(
+        stderr.contains("expected `tab` or 1 to 16 spaces"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn hkt_apply_must_be_an_identifier() {
+    let dir = project("config_hkt_apply", "[syntax]\nhkt_apply = \"\"\n");
+    let input = dir.join("src/a.ts");
+    std::fs::write(&input, "interface I<F<_>> { m: F<A>; }\n").unwrap();
+
+    let output = preprocess(&input, &[]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("expected a TypeScript identifier, got ``"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn helpers_must_be_identifiers() {
+    let dir = project(
+        "config_helpers",
+        "[syntax.helpers]\npipeline = \"my pipe\"\n",
+    );
+    let input = dir.join("src/a.ts");
+    std::fs::write(&input, "const a = x |> f;\n").unwrap();
+
+    let output = preprocess(&input, &[]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("expected a TypeScript identifier, got `my pipe`"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn each_file_gets_its_own_config() {
+    let dir = project("config_per_file", "[output]\nindent = 2\n");
+    std::fs::write(
+        dir.join("src/nested/sugarcube.toml"),
+        "[syntax]\nhkt = false\n\n[output]\nindent = \"tab\"\n",
+    )
+    .unwrap();
+    let source = "function f() {\n    return x |> g;\n}\n";
+    std::fs::write(dir.join("src/a.ts"), source).unwrap();
+    std::fs::write(dir.join("src/nested/b.ts"), source).unwrap();
+    let out = dir.join("out");
+    let read = |name: &str| std::fs::read_to_string(out.join(name)).unwrap();
+
+    let output = preprocess(&dir.join("src"), &["--out-dir", out.to_str().unwrap()]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        read("a.ts"),
+        "function f() {\n  return __binop__(x, \"|>\", g);\n}\n"
+    );
+    assert_eq!(
+        read("nested/b.ts"),
+        "function f() {\n\treturn __binop__(x, \"|>\", g);\n}\n"
+    );
+
+    // A flag overrides every file's config.
+    let args = ["--out-dir", out.to_str().unwrap(), "--indent", "4"];
+    let output = preprocess(&dir.join("src"), &args);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(read("a.ts"), read("nested/b.ts"));
+    assert!(read("a.ts").contains("\n    return"));
+
+    // `check` too: only the nested file has HKT syntax turned off.
+    let hkt = "interface Functor<F<_>> {}\n";
+    std::fs::write(dir.join("src/hkt.ts"), hkt).unwrap();
+    std::fs::write(dir.join("src/nested/hkt.ts"), hkt).unwrap();
+    let output = run_sc([
+        "check".as_ref(),
+        dir.join("src/hkt.ts").as_os_str(),
+        dir.join("src/nested/hkt.ts").as_os_str(),
+    ]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let ok = format!("OK: {}", dir.join("src/hkt.ts").display());
+    assert!(stderr.contains(&ok), "{stderr}");
+    assert!(stderr.contains("failed to check 1 of 2 files"), "{stderr}");
+}
@@ -0,0 +1,65 @@
+//! Sugarcube's own diagnostics are shown with their source line underlined,
+//! in color with `--color`.
+
+mod common;
+
+use common::{run_sc, sc, scratch_file};
+
+#[test]
+fn missing_operand_error_shows_the_line_with_carets() {
+    let path = scratch_file("diagnostics_missing.ts", "const a = 1;\nconst x = |> f;\n");
+    let output = run_sc(["check".as_ref(), path.as_os_str()]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(
+        stderr,
+        format!(
+            "\
+error: pipeline requires a left operand
+ --> {}:2:11
+  |
+2 | const x = |> f;
+  |           ^^
+",
+            path.display()
+        )
+    );
+}
+
+fn check_with_color(args: &[&str], no_color: Option<&str>) -> String {
+    let path = scratch_file("diagnostics_color.ts", "const x = |> f;\n");
+    let mut command = sc();
+    command.arg("check").arg(&path).args(args);
+    match no_color {
+        Some(value) => command.env("NO_COLOR", value),
+        None => command.env_remove("NO_COLOR"),
+    };
+    let output = command.output().expect("failed to run sc");
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn color_never_has_no_escape_codes() {
+    let stderr = check_with_color(&["--color", "never"], None);
+    assert!(
+        stderr.starts_with("error: pipeline requires a left operand"),
+        "{stderr:?}"
+    );
+    assert!(!stderr.contains('\x1b'), "{stderr:?}");
+    // Not a terminal, so `auto` (the default) stays plain too.
+    assert_eq!(check_with_color(&[], None), stderr);
+}
+
+#[test]
+fn color_always_paints_the_severity_and_carets() {
+    let stderr = check_with_color(&["--color", "always"], None);
+    assert!(
+        stderr.starts_with("\x1b[1;31merror\x1b[0m: pipeline requires a left operand"),
+        "{stderr:?}"
+    );
+    assert!(stderr.contains("\x1b[1;31m^^\x1b[0m"), "{stderr:?}");
+    // Asking for it outright wins over `NO_COLOR`.
+    assert_eq!(check_with_color(&["--color", "always"], Some("1")), stderr);
+}
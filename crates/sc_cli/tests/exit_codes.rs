@@ -0,0 +1,84 @@
+//! Exit statuses of the `sc` binary: 0 success, 1 parse/desugar error,
+//! 2 usage error, 3 IO error.
+
+mod common;
+
+use common::{run_sc, scratch_file, scratch_path};
+
+fn sc(args: &[&str]) -> i32 {
+    let output = run_sc(args);
+    output.status.code().expect("sc was killed by a signal")
+}
+
+/// Write `source` to a scratch file and return its path.
+fn fixture(name: &str, source: &str) -> String {
+    scratch_file(name, source).display().to_string()
+}
+
+#[test]
+fn success_is_zero() {
+    let file = fixture("exit_ok.ts", "const x = a |> f;\n");
+    assert_eq!(sc(&["check", &file]), 0);
+    assert_eq!(sc(&["preprocess", &file]), 0);
+    assert_eq!(sc(&["--help"]), 0);
+}
+
+#[test]
+fn parse_error_is_one() {
+    let ts_error = fixture("exit_ts_error.ts", "const = ;\n");
+    assert_eq!(sc(&["check", &ts_error]), 1);
+    assert_eq!(sc(&["preprocess", &ts_error]), 1);
+
+    let missing_operand = fixture("exit_missing_operand.ts", "const x = |> f;\n");
+    assert_eq!(sc(&["check", &missing_operand]), 1);
+}
+
+#[test]
+fn usage_error_is_two() {
+    let file = fixture("exit_usage.ts", "const x = 1;\n");
+    assert_eq!(sc(&[]), 2);
+    assert_eq!(sc(&["frobnicate", &file]), 2);
+    assert_eq!(sc(&["check", &file, "--no-such-flag"]), 2);
+    assert_eq!(sc(&["preprocess", &file, &file]), 2);
+    assert_eq!(sc(&["preprocess", &file, "--out-ext", "js"]), 2);
+    // An input map only makes sense when writing an output map.
+    assert_eq!(sc(&["preprocess", &file, "--input-source-map", &file]), 2);
+}
+
+#[test]
+fn io_error_is_three() {
+    let missing = scratch_path("exit_does_not_exist.ts");
+    let missing = missing.display().to_string();
+    assert_eq!(sc(&["check", &missing]), 3);
+    assert_eq!(sc(&["preprocess", "--dry-run", &missing]), 3);
+
+    let file = fixture("exit_io.ts", "const x = 1;\n");
+    let unwritable = scratch_path("no_such_dir/out.ts");
+    assert_eq!(
+        sc(&["preprocess", &file, "-o", &unwritable.display().to_string()]),
+        3
+    );
+}
+
+#[test]
+fn unreadable_inputs_are_named() {
+    let missing = scratch_path("exit_not_there.ts");
+    let not_utf8 = scratch_path("exit_not_utf8.ts");
+    std::fs::write(&not_utf8, b"const s = \"\xff\xfe\";\n").unwrap();
+
+    for (path, message) in [
+        (&missing, "input file not found: "),
+        (&not_utf8, "input is not valid UTF-8: "),
+    ] {
+        for command in ["preprocess", "check", "analyze", "parse"] {
+            let output = run_sc([command.as_ref(), path.as_os_str()]);
+            assert_eq!(output.status.code(), Some(3), "{command}: {output:?}");
+            let stderr = String::from_utf8(output.stderr).unwrap();
+            assert_eq!(
+                stderr,
+                format!("error: {message}{}\n", path.display()),
+                "{command}"
+            );
+        }
+    }
+}
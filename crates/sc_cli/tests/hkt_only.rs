@@ -0,0 +1,44 @@
+//! `sc preprocess --hkt-only`: rewrite HKT syntax and nothing else.
+
+mod common;
+
+use common::{preprocess, scratch_dir};
+
+#[test]
+fn hkt_only_leaves_pipelines_and_cons_intact() {
+    let dir = scratch_dir("hkt_only");
+    let input = dir.join("a.ts");
+    std::fs::write(
+        &input,
+        "interface Functor<F<_>> {\n  map: <A, B>(fa: F<A>) => F<B>;\n}\n\
+         export const a = x |> f;\nexport const b = 1 :: [];\n",
+    )
+    .unwrap();
+
+    let output = preprocess(&input, &["--hkt-only"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "interface Functor<F> {\n  map: <A, B>(fa: $<F, A>) => $<F, B>;\n}\n\
+         export const a = x |> f;\nexport const b = 1 :: [];\n"
+    );
+}
+
+#[test]
+fn hkt_only_fails_on_hkt_errors() {
+    let dir = scratch_dir("hkt_only_error");
+    let input = dir.join("a.ts");
+    let source = "interface I<F<_>> { m: F<A, B>; }\n";
+    std::fs::write(&input, source).unwrap();
+    let out = dir.join("a.out.ts").display().to_string();
+
+    let output = preprocess(&input, &["--hkt-only", "-o", &out]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("`F` takes 1 type argument"), "{stderr}");
+    assert!(!std::path::Path::new(&out).exists());
+
+    let output = preprocess(&input, &["--hkt-only", "--in-place"]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    assert_eq!(std::fs::read_to_string(&input).unwrap(), source);
+}
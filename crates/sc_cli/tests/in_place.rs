@@ -0,0 +1,114 @@
+//! `sc preprocess --in-place`: the input overwritten with its output.
+
+mod common;
+
+use common::{preprocess, scratch_file};
+
+const SOURCE: &str = "\
+interface Functor<F<_>> {
+    map: <A, B>(fa: F<A>) => F<B>;
+}
+const xs = 1 :: 2 :: [];
+const ys = xs |> reverse;
+";
+
+#[test]
+fn in_place_overwrites_the_input() {
+    let input = scratch_file("in_place/mod.ts", SOURCE);
+    let output = preprocess(&input, &["--in-place"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(output.stdout.is_empty(), "{output:?}");
+    assert_eq!(
+        std::fs::read_to_string(&input).unwrap(),
+        "\
+interface Functor<F> {
+    map: <A, B>(fa: $<F, A>) => $<F, B>;
+}
+const xs = __binop__(1, \"::\", __binop__(2, \"::\", []));
+const ys = __binop__(xs, \"|>\", reverse);
+"
+    );
+    assert!(!input.with_extension("ts.bak").exists());
+
+    // Already standard TypeScript: a second run leaves it as it is.
+    let migrated = std::fs::read_to_string(&input).unwrap();
+    assert!(preprocess(&input, &["--in-place"]).status.success());
+    assert_eq!(std::fs::read_to_string(&input).unwrap(), migrated);
+}
+
+#[test]
+fn in_place_keeps_comments() {
+    let source = "\
+/*!
+ * mylib
+ */
+// Lists, reversed.
+/** The list, last first. */
+export function sorted(xs: number[]): number[] {
+    /* Not in place. */
+    return xs |> reverse; // a copy
+}
+const ys = f(/* inner */ 1 :: []);
+";
+    let input = scratch_file("in_place_comments/mod.ts", source);
+    let output = preprocess(&input, &["--in-place"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        std::fs::read_to_string(&input).unwrap(),
+        "\
+/*!
+ * mylib
+ */
+// Lists, reversed.
+/** The list, last first. */
+export function sorted(xs: number[]): number[] {
+    /* Not in place. */
+    return __binop__(xs, \"|>\", reverse); // a copy
+}
+const ys = f(__binop__(1, \"::\", []));
+"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("warning: comment dropped") && stderr.contains("mod.ts:10:14"),
+        "{stderr}"
+    );
+    assert_eq!(stderr.matches("warning").count(), 1, "{stderr}");
+}
+
+#[test]
+fn backup_keeps_the_original() {
+    let input = scratch_file("in_place_backup/mod.ts", SOURCE);
+    let output = preprocess(&input, &["--in-place", "--backup"]);
+    assert!(output.status.success(), "{output:?}");
+    let backup = input.with_extension("ts.bak");
+    assert_eq!(std::fs::read_to_string(backup).unwrap(), SOURCE);
+    assert!(!std::fs::read_to_string(&input)
+        .unwrap()
+        .contains("xs |> reverse"));
+}
+
+#[test]
+fn failed_transform_leaves_the_input() {
+    let source = "const a = x |> ;\n";
+    let input = scratch_file("in_place_error/mod.ts", source);
+    let output = preprocess(&input, &["--in-place", "--backup"]);
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    assert_eq!(std::fs::read_to_string(&input).unwrap(), source);
+    assert!(!input.with_extension("ts.bak").exists());
+}
+
+#[test]
+fn in_place_conflicts_with_other_outputs() {
+    let input = scratch_file("in_place_usage/mod.ts", SOURCE);
+    for args in [
+        &["--in-place", "-o", "out.ts"][..],
+        &["--in-place", "--out-dir", "dist"],
+        &["--in-place", "--dry-run"],
+        &["--backup"],
+    ] {
+        let output = preprocess(&input, args);
+        assert_eq!(output.status.code(), Some(2), "{args:?}: {output:?}");
+    }
+    assert_eq!(std::fs::read_to_string(&input).unwrap(), SOURCE);
+}
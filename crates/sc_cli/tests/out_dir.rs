@@ -0,0 +1,171 @@
+//! `sc preprocess --out-dir`: one output per input, with `--out-ext` renaming.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::process::Output;
+
+use common::{run_sc, scratch_dir};
+
+/// A fresh source tree under the test-scratch directory:
+/// `a.ts`, `nested/b.tsx`, `nested/c.mts`, and a `types.d.ts` to skip.
+fn source_tree(name: &str) -> PathBuf {
+    let root = scratch_dir(name);
+    let src = root.join("src");
+    std::fs::create_dir_all(src.join("nested")).unwrap();
+    std::fs::write(src.join("a.ts"), "const a = x |> f;\n").unwrap();
+    std::fs::write(src.join("nested/b.tsx"), "const b = <div>{y |> g}</div>;\n").unwrap();
+    std::fs::write(src.join("nested/c.mts"), "export const c = 1 :: [];\n").unwrap();
+    std::fs::write(src.join("types.d.ts"), "declare const t: number;\n").unwrap();
+    root
+}
+
+fn sc(args: &[&Path]) -> Output {
+    run_sc([Path::new("preprocess")].iter().chain(args))
+}
+
+/// Paths of every file under `dir`, relative to it, sorted.
+fn files_under(dir: &Path) -> Vec<String> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            let sub = path.file_name().unwrap().to_str().unwrap().to_string();
+            files.extend(files_under(&path).into_iter().map(|f| format!("{sub}/{f}")));
+        } else {
+            files.push(path.file_name().unwrap().to_str().unwrap().to_string());
+        }
+    }
+    files.sort();
+    files
+}
+
+#[test]
+fn directory_mode_maps_ts_extensions_to_js() {
+    let root = source_tree("out_dir_js");
+    let out = root.join("out");
+    let output = sc(&[
+        &root.join("src"),
+        Path::new("--out-dir"),
+        &out,
+        Path::new("--out-ext"),
+        Path::new("js"),
+    ]);
+    assert!(output.status.success(), "{output:?}");
+
+    assert_eq!(files_under(&out), ["a.js", "nested/b.jsx", "nested/c.mjs"]);
+    let a = std::fs::read_to_string(out.join("a.js")).unwrap();
+    assert_eq!(a, "const a = __binop__(x, \"|>\", f);\n");
+}
+
+#[test]
+fn other_extensions_apply_to_every_file() {
+    let root = source_tree("out_dir_mjs");
+    let out = root.join("out");
+    let src = root.join("src");
+    let output = sc(&[
+        &src.join("a.ts"),
+        &src.join("nested"),
+        Path::new("--out-dir"),
+        &out,
+        Path::new("--out-ext"),
+        Path::new(".mjs"),
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(files_under(&out), ["a.mjs", "b.mjs", "c.mjs"]);
+}
+
+#[test]
+fn without_out_ext_names_are_kept_but_inputs_are_never_overwritten() {
+    let root = source_tree("out_dir_keep");
+    let src = root.join("src");
+    let out = root.join("out");
+    let output = sc(&[&src, Path::new("--out-dir"), &out]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(files_under(&out), ["a.ts", "nested/b.tsx", "nested/c.mts"]);
+
+    let output = sc(&[&src, Path::new("--out-dir"), &src]);
+    assert_eq!(output.status.code(), Some(2), "{output:?}");
+    let a = std::fs::read_to_string(src.join("a.ts")).unwrap();
+    assert_eq!(a, "const a = x |> f;\n");
+}
+
+#[test]
+fn inputs_with_the_same_output_are_rejected() {
+    let root = scratch_dir("out_dir_clash");
+    for dir in ["a", "b"] {
+        std::fs::create_dir_all(root.join(dir)).unwrap();
+        std::fs::write(root.join(dir).join("x.ts"), format!("const {dir} = 1;\n")).unwrap();
+    }
+    let out = root.join("out");
+    let (a, b) = (root.join("a"), root.join("b"));
+
+    let output = sc(&[
+        &a.join("x.ts"),
+        &b.join("x.ts"),
+        Path::new("--out-dir"),
+        &out,
+    ]);
+    assert_eq!(output.status.code(), Some(2), "{output:?}");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("the same output"));
+    assert!(!out.exists());
+
+    let output = sc(&[&a, &b, Path::new("--out-dir"), &out]);
+    assert_eq!(output.status.code(), Some(2), "{output:?}");
+    assert!(!out.exists());
+
+    // One file named twice is still one output.
+    let output = sc(&[&a, &a.join("x.ts"), Path::new("--out-dir"), &out]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(files_under(&out), ["x.ts"]);
+}
+
+#[test]
+fn report_lists_each_output_sorted_by_input() {
+    let root = source_tree("out_dir_report");
+    let src = root.join("src");
+    let out = root.join("out");
+    let report = root.join("report.json");
+    // Named out of order: the manifest sorts by input path anyway.
+    let output = sc(&[
+        &src.join("nested"),
+        &src.join("a.ts"),
+        Path::new("--out-dir"),
+        &out,
+        Path::new("--report"),
+        &report,
+    ]);
+    assert!(output.status.success(), "{output:?}");
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+    let files = json["files"].as_array().unwrap();
+    let inputs: Vec<_> = files.iter().map(|f| f["input"].as_str().unwrap()).collect();
+    let expected: Vec<_> = ["a.ts", "nested/b.tsx", "nested/c.mts"]
+        .map(|f| src.join(f).display().to_string())
+        .to_vec();
+    assert_eq!(inputs, expected);
+
+    let a = &files[0];
+    assert_eq!(a["output"], out.join("a.ts").display().to_string());
+    assert_eq!(a["rewrites"]["pipeline"], 1);
+    assert_eq!(a["rewrites"]["cons"], 0);
+    assert!(a["elapsed_ms"].as_f64().unwrap() >= 0.0);
+    let hash = a["hash"].as_str().unwrap();
+    assert_eq!(hash.len(), 32);
+    assert_ne!(hash, files[1]["hash"].as_str().unwrap());
+
+    // The same inputs give the same hashes.
+    let again = root.join("again.json");
+    let output = sc(&[
+        &src,
+        Path::new("--out-dir"),
+        &out,
+        Path::new("--report"),
+        &again,
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    let json_again: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&again).unwrap()).unwrap();
+    assert_eq!(json_again["files"][0]["hash"], hash);
+}
@@ -0,0 +1,30 @@
+//! `sc --version` and `sc preprocess --print-config`.
+
+mod common;
+
+use common::{preprocess, run_sc, scratch_file};
+
+#[test]
+fn print_config_reflects_feature_flags() {
+    let input = scratch_file("print_config/a.ts", "const a = x |> f;\n");
+
+    let output = preprocess(&input, &["--print-config", "--no-hkt", "--indent", "tab"]);
+    assert!(output.status.success(), "{output:?}");
+
+    let config: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(config["syntax"]["hkt"], false);
+    assert_eq!(config["syntax"]["pipeline"], true);
+    assert_eq!(config["options"]["indent"], "tab");
+    assert_eq!(config["options"]["runtime"], "none");
+    // Only the config goes to stderr; the output is still on stdout.
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, "const a = __binop__(x, \"|>\", f);\n");
+}
+
+#[test]
+fn version_prints_the_crate_version() {
+    let output = run_sc(["--version"]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, format!("sc {}\n", env!("CARGO_PKG_VERSION")));
+}
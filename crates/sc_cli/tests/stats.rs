@@ -0,0 +1,51 @@
+//! `sc preprocess --stats`: rewrite counts and stage timings on stderr.
+
+mod common;
+
+use common::{preprocess, scratch_file};
+
+#[test]
+fn stats_go_to_stderr() {
+    let path = scratch_file(
+        "stats.ts",
+        "interface Functor<F<_>> {\n    map: (fa: F<number>) => F<string>;\n}\nconst a = x |> f |> g;\nconst b = 1 :: [];\n",
+    );
+
+    let plain = preprocess(&path, &[]);
+    let output = preprocess(&path, &["--stats"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(output.stdout, plain.stdout);
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let lines: Vec<_> = stderr.lines().collect();
+    assert_eq!(lines[0], format!("{}:", path.display()));
+    let counts: Vec<_> = lines[1..6]
+        .iter()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .collect();
+    assert_eq!(
+        counts,
+        [
+            ["pipeline", "2"],
+            ["cons", "1"],
+            ["bind", "0"],
+            ["hkt-decl", "1"],
+            ["hkt-usage", "2"],
+        ]
+    );
+    for (line, stage) in lines[6..].iter().zip(["preprocess", "parse", "emit"]) {
+        let [name, ms, "ms"] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+            panic!("{line}");
+        };
+        assert_eq!(name, stage);
+        assert!(ms.parse::<f64>().is_ok(), "{line}");
+    }
+    assert_eq!(lines.len(), 9, "{stderr}");
+}
+
+#[test]
+fn stats_conflict_with_dry_run() {
+    let path = scratch_file("stats_dry_run.ts", "const a = x |> f;\n");
+    let output = preprocess(&path, &["--stats", "--dry-run"]);
+    assert_eq!(output.status.code(), Some(2), "{output:?}");
+}
@@ -0,0 +1,26 @@
+//! Desugaring for the bind operator (`obj::method`).
+//!
+//! `obj::method` → `method.bind(obj)`
+
+use sc_ast::ScBinExpr;
+use swc_ecma_ast as ast;
+
+/// Desugar a bind expression into a `.bind()` call on the right operand.
+pub fn desugar_bind(expr: &ScBinExpr) -> ast::Expr {
+    let span = expr.span;
+
+    ast::Expr::Call(ast::CallExpr {
+        span,
+        callee: ast::Callee::Expr(Box::new(ast::Expr::Member(ast::MemberExpr {
+            span,
+            obj: expr.right.clone(),
+            prop: ast::MemberProp::Ident(ast::IdentName::new("bind".into(), expr.op_span)),
+        }))),
+        args: vec![ast::ExprOrSpread {
+            spread: None,
+            expr: expr.left.clone(),
+        }],
+        type_args: None,
+        ..Default::default()
+    })
+}
@@ -1,6 +1,7 @@
 //! Desugaring for the cons operator (`::`).
 //!
-//! `a :: b` → `__binop__(a, "::", b)`
+//! `a :: b` → `__binop__(a, "::", b)`, or `__cons__(a, b)` with a helper
+//! (see `ScSyntax::helpers`)
 
 use sc_ast::ScBinExpr;
 use swc_ecma_ast as ast;
@@ -9,5 +10,10 @@ use crate::pipeline::make_binop_call;
 
 /// Desugar a cons binary expression into a `__binop__` call.
 pub fn desugar_cons(expr: &ScBinExpr) -> ast::Expr {
-    make_binop_call(expr.span, &expr.left, "::", &expr.right)
+    make_binop_call(expr, "::", None)
+}
+
+/// Desugar a cons binary expression into `helper(head, tail)`.
+pub fn desugar_cons_with(expr: &ScBinExpr, helper: &str) -> ast::Expr {
+    make_binop_call(expr, "::", Some(helper))
 }
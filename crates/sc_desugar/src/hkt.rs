@@ -9,11 +9,21 @@ use swc_ecma_visit::{VisitMut, VisitMutWith};
 /// Visitor that rewrites `F<A>` to `$<F, A>` for names in `hkt_names`.
 pub struct HktRewriter {
     hkt_names: HashSet<String>,
+    apply_name: String,
 }
 
 impl HktRewriter {
     pub fn new(hkt_names: HashSet<String>) -> Self {
-        Self { hkt_names }
+        Self {
+            hkt_names,
+            apply_name: "$".to_string(),
+        }
+    }
+
+    /// Apply usages through `name` instead of `$` (see `ScSyntax::hkt_apply`).
+    pub fn with_apply_name(mut self, name: impl Into<String>) -> Self {
+        self.apply_name = name.into();
+        self
     }
 }
 
@@ -30,11 +40,17 @@ impl VisitMut for HktRewriter {
             return;
         }
 
-        // F<A> → $<F, A>: wrap the original type args with F prepended.
+        // F<A> → $<F, A> (or the configured apply name): wrap the original type args with F prepended.
+        // `F` keeps its own span; `$` has no source text, so it gets the
+        // empty span where the reference starts.
         if let Some(type_params) = &node.type_params {
-            let span = node.span;
+            let span = swc_common::Span::new(node.span.lo, node.span.lo);
+            let f_span = match &node.type_name {
+                swc_ecma_ast::TsEntityName::Ident(ident) => ident.span,
+                _ => node.span,
+            };
             let f_type = Box::new(swc_ecma_ast::TsType::TsTypeRef(swc_ecma_ast::TsTypeRef {
-                span,
+                span: f_span,
                 type_name: node.type_name.clone(),
                 type_params: None,
             }));
@@ -44,9 +60,10 @@ impl VisitMut for HktRewriter {
                 new_params.push(param.clone());
             }
 
-            node.type_name = swc_ecma_ast::TsEntityName::Ident(
-                swc_ecma_ast::Ident::new_no_ctxt("$".into(), span),
-            );
+            node.type_name = swc_ecma_ast::TsEntityName::Ident(swc_ecma_ast::Ident::new_no_ctxt(
+                self.apply_name.as_str().into(),
+                span,
+            ));
             node.type_params = Some(Box::new(swc_ecma_ast::TsTypeParamInstantiation {
                 span: type_params.span,
                 params: new_params,
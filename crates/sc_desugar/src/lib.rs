@@ -3,11 +3,13 @@
 //! Transforms:
 //! - `a |> f`   → `__binop__(a, "|>", f)`
 //! - `a :: b`   → `__binop__(a, "::", b)`
+//! - `a::b`     → `b.bind(a)` (when `ScSyntax::bind` is on)
 //! - `F<_>` HKT → strips `<_>` from decl, rewrites `F<A>` to `$<F, A>` in scope
 
-pub mod pipeline;
+pub mod bind;
 pub mod cons;
-pub mod hkt;
 pub mod desugar;
+pub mod hkt;
+pub mod pipeline;
 
 pub use desugar::desugar_module;
@@ -1,6 +1,7 @@
 //! Desugaring for the pipeline operator (`|>`).
 //!
-//! `a |> f` → `__binop__(a, "|>", f)`
+//! `a |> f` → `__binop__(a, "|>", f)`, or `__pipe__(a, f)` with a helper
+//! (see `ScSyntax::helpers`)
 
 use sc_ast::ScBinExpr;
 use swc_common::Span;
@@ -8,40 +9,111 @@ use swc_ecma_ast as ast;
 
 /// Desugar a pipeline binary expression into a `__binop__` call.
 pub fn desugar_pipeline(expr: &ScBinExpr) -> ast::Expr {
-    make_binop_call(expr.span, &expr.left, "|>", &expr.right)
+    make_binop_call(expr, "|>", None)
 }
 
-/// Build `__binop__(left, op_str, right)`.
-pub(crate) fn make_binop_call(
-    span: Span,
-    left: &ast::Expr,
-    op_str: &str,
-    right: &ast::Expr,
-) -> ast::Expr {
-    let callee_ident = ast::Ident::new_no_ctxt("__binop__".into(), span);
+/// Desugar a pipeline binary expression into `helper(left, right)`.
+pub fn desugar_pipeline_with(expr: &ScBinExpr, helper: &str) -> ast::Expr {
+    make_binop_call(expr, "|>", Some(helper))
+}
+
+/// Build `__binop__(left, op_str, right)`, or `helper(left, right)` if given.
+///
+/// The call covers the whole of `expr` and the operands keep their own
+/// spans. The callee has no source text, so it gets the empty span at the
+/// start of `expr`; the `op_str` literal gets the operator's.
+pub(crate) fn make_binop_call(expr: &ScBinExpr, op_str: &str, helper: Option<&str>) -> ast::Expr {
+    let callee_ident = ast::Ident::new_no_ctxt(
+        helper.unwrap_or("__binop__").into(),
+        Span::new(expr.span.lo, expr.span.lo),
+    );
+    let arg = |arg| ast::ExprOrSpread {
+        spread: None,
+        expr: arg,
+    };
+
+    let mut args = vec![arg(expr.left.clone())];
+    if helper.is_none() {
+        args.push(arg(Box::new(ast::Expr::Lit(ast::Lit::Str(ast::Str {
+            span: expr.op_span,
+            value: op_str.into(),
+            raw: None,
+        })))));
+    }
+    args.push(arg(expr.right.clone()));
 
     ast::Expr::Call(ast::CallExpr {
-        span,
+        span: expr.span,
         callee: ast::Callee::Expr(Box::new(ast::Expr::Ident(callee_ident))),
-        args: vec![
-            ast::ExprOrSpread {
-                spread: None,
-                expr: Box::new(left.clone()),
-            },
-            ast::ExprOrSpread {
-                spread: None,
-                expr: Box::new(ast::Expr::Lit(ast::Lit::Str(ast::Str {
-                    span,
-                    value: op_str.into(),
-                    raw: None,
-                }))),
-            },
-            ast::ExprOrSpread {
-                spread: None,
-                expr: Box::new(right.clone()),
-            },
-        ],
+        args,
         type_args: None,
         ..Default::default()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sc_ast::ScBinaryOp;
+    use swc_common::BytePos;
+
+    fn ident(name: &str, lo: u32, hi: u32) -> Box<ast::Expr> {
+        let span = Span::new(BytePos(lo), BytePos(hi));
+        Box::new(ast::Expr::Ident(ast::Ident::new_no_ctxt(name.into(), span)))
+    }
+
+    #[test]
+    fn binop_call_parts_keep_their_source_spans() {
+        // `a |> f` at offsets 1..7.
+        let expr = ScBinExpr {
+            span: Span::new(BytePos(1), BytePos(7)),
+            op: ScBinaryOp::Pipeline,
+            op_span: Span::new(BytePos(3), BytePos(5)),
+            left: ident("a", 1, 2),
+            right: ident("f", 6, 7),
+        };
+        let ast::Expr::Call(call) = desugar_pipeline(&expr) else {
+            panic!("expected a call");
+        };
+        assert_eq!(call.span, expr.span);
+        let ast::Callee::Expr(callee) = &call.callee else {
+            panic!("expected an expression callee");
+        };
+        assert_eq!(
+            callee.as_ident().unwrap().span,
+            Span::new(BytePos(1), BytePos(1))
+        );
+
+        let spans: Vec<Span> = call
+            .args
+            .iter()
+            .map(|arg| match &*arg.expr {
+                ast::Expr::Ident(ident) => ident.span,
+                ast::Expr::Lit(ast::Lit::Str(op)) => op.span,
+                other => panic!("unexpected argument {other:?}"),
+            })
+            .collect();
+        let span = |lo, hi| Span::new(BytePos(lo), BytePos(hi));
+        assert_eq!(spans, [span(1, 2), expr.op_span, span(6, 7)]);
+    }
+
+    #[test]
+    fn helper_call_drops_the_operator_string() {
+        let expr = ScBinExpr {
+            span: Span::new(BytePos(1), BytePos(7)),
+            op: ScBinaryOp::Pipeline,
+            op_span: Span::new(BytePos(3), BytePos(5)),
+            left: ident("a", 1, 2),
+            right: ident("f", 6, 7),
+        };
+        let ast::Expr::Call(call) = desugar_pipeline_with(&expr, "__pipe__") else {
+            panic!("expected a call");
+        };
+        let ast::Callee::Expr(callee) = &call.callee else {
+            panic!("expected an expression callee");
+        };
+        assert_eq!(callee.as_ident().unwrap().sym, "__pipe__");
+        let args: Vec<_> = call.args.iter().map(|arg| arg.expr.clone()).collect();
+        assert_eq!(args, [expr.left, expr.right]);
+    }
+}
@@ -0,0 +1,147 @@
+//! Desugared nodes carry spans into the original source, so a source map
+//! emitted straight from them points at what was written.
+
+use std::collections::HashSet;
+
+use sc_ast::{ScBinExpr, ScBinaryOp};
+use sc_desugar::hkt::HktRewriter;
+use sc_desugar::pipeline::desugar_pipeline;
+use swc_common::source_map::DefaultSourceMapGenConfig;
+use swc_common::{sync::Lrc, BytePos, FileName, SourceMap, Span, SyntaxContext};
+use swc_ecma_ast as ast;
+use swc_ecma_codegen::{text_writer::JsWriter, Emitter, Node};
+use swc_ecma_visit::VisitMutWith;
+
+/// `module` emitted with its source map, which is then looked up at each of
+/// `tokens` in the output: the `(line, column)` in `cm` each maps to.
+fn emit_and_lookup(cm: &Lrc<SourceMap>, module: &ast::Module, tokens: &[&str]) -> Vec<(u32, u32)> {
+    let mut buf = Vec::new();
+    let mut mappings = Vec::new();
+    {
+        let mut emitter = Emitter {
+            cfg: swc_ecma_codegen::Config::default(),
+            cm: cm.clone(),
+            comments: None,
+            wr: JsWriter::new(cm.clone(), "\n", &mut buf, Some(&mut mappings)),
+        };
+        module.emit_with(&mut emitter).unwrap();
+    }
+    let code = String::from_utf8(buf).unwrap();
+    let map = cm.build_source_map(&mappings, None, DefaultSourceMapGenConfig);
+
+    tokens
+        .iter()
+        .map(|token| {
+            let col = code
+                .find(token)
+                .unwrap_or_else(|| panic!("no {token} in {code}"));
+            let found = map.lookup_token(0, col as u32).unwrap();
+            (found.get_src_line(), found.get_src_col())
+        })
+        .collect()
+}
+
+fn ident(name: &str, span: Span) -> Box<ast::Expr> {
+    Box::new(ast::Expr::Ident(ast::Ident::new_no_ctxt(name.into(), span)))
+}
+
+#[test]
+fn binop_call_maps_back_to_the_pipeline() {
+    let cm: Lrc<SourceMap> = Default::default();
+    let source = "xs |> f;\n";
+    let file = cm.new_source_file(
+        Lrc::new(FileName::Custom("a.ts".into())),
+        source.to_string(),
+    );
+    let at = |lo: usize, hi: usize| {
+        Span::new(
+            file.start_pos + BytePos(lo as u32),
+            file.start_pos + BytePos(hi as u32),
+        )
+    };
+
+    let pipeline = ScBinExpr {
+        span: at(0, 7),
+        op: ScBinaryOp::Pipeline,
+        op_span: at(3, 5),
+        left: ident("xs", at(0, 2)),
+        right: ident("f", at(6, 7)),
+    };
+    let module = ast::Module {
+        span: at(0, 8),
+        body: vec![ast::ModuleItem::Stmt(ast::Stmt::Expr(ast::ExprStmt {
+            span: at(0, 8),
+            expr: Box::new(desugar_pipeline(&pipeline)),
+        }))],
+        shebang: None,
+    };
+
+    // `__binop__(xs, "|>", f);`: the call starts where `xs |> f` does, and
+    // each argument maps to its own source.
+    assert_eq!(
+        emit_and_lookup(&cm, &module, &["__binop__", "xs", "\"|>\"", "f)"]),
+        [(0, 0), (0, 0), (0, 3), (0, 6)]
+    );
+}
+
+#[test]
+fn hkt_usage_keeps_the_name_span() {
+    let cm: Lrc<SourceMap> = Default::default();
+    let source = "type T = F<number>;\n";
+    let file = cm.new_source_file(
+        Lrc::new(FileName::Custom("a.ts".into())),
+        source.to_string(),
+    );
+    let at = |lo: usize, hi: usize| {
+        Span::new(
+            file.start_pos + BytePos(lo as u32),
+            file.start_pos + BytePos(hi as u32),
+        )
+    };
+
+    let usage = ast::TsType::TsTypeRef(ast::TsTypeRef {
+        span: at(9, 18),
+        type_name: ast::TsEntityName::Ident(ast::Ident::new_no_ctxt("F".into(), at(9, 10))),
+        type_params: Some(Box::new(ast::TsTypeParamInstantiation {
+            span: at(10, 18),
+            params: vec![Box::new(ast::TsType::TsKeywordType(ast::TsKeywordType {
+                span: at(11, 17),
+                kind: ast::TsKeywordTypeKind::TsNumberKeyword,
+            }))],
+        })),
+    });
+    let mut module = ast::Module {
+        span: at(0, 19),
+        body: vec![ast::ModuleItem::Stmt(ast::Stmt::Decl(
+            ast::Decl::TsTypeAlias(Box::new(ast::TsTypeAliasDecl {
+                span: at(0, 19),
+                declare: false,
+                id: ast::Ident::new("T".into(), at(5, 6), SyntaxContext::empty()),
+                type_params: None,
+                type_ann: Box::new(usage),
+            })),
+        ))],
+        shebang: None,
+    };
+    module.visit_mut_with(&mut HktRewriter::new(HashSet::from(["F".to_string()])));
+
+    assert_eq!(
+        emit_and_lookup(&cm, &module, &["$<", "F,", "number"]),
+        [(0, 9), (0, 9), (0, 11)]
+    );
+
+    // `F` keeps exactly its own span; `$` claims no source text.
+    let ast::ModuleItem::Stmt(ast::Stmt::Decl(ast::Decl::TsTypeAlias(alias))) = &module.body[0]
+    else {
+        panic!("expected the type alias");
+    };
+    let ast::TsType::TsTypeRef(apply) = &*alias.type_ann else {
+        panic!("expected a type reference");
+    };
+    assert_eq!(apply.type_name.as_ident().unwrap().span, at(9, 9));
+    let params = &apply.type_params.as_ref().unwrap().params;
+    let ast::TsType::TsTypeRef(f) = &*params[0] else {
+        panic!("expected `F` first");
+    };
+    assert_eq!(f.span, at(9, 10));
+}
@@ -21,6 +21,45 @@ pub enum ScToken {
     ScOperator(ScBinaryOp),
 }
 
+impl ScToken {
+    /// The sugarcube operator this token is, if any.
+    pub fn as_operator(&self) -> Option<ScBinaryOp> {
+        match self {
+            ScToken::ScOperator(op) => Some(*op),
+            ScToken::Standard(_) => None,
+        }
+    }
+
+    /// Whether this token is the sugarcube operator `op`.
+    pub fn is_operator(&self, op: ScBinaryOp) -> bool {
+        self.as_operator() == Some(op)
+    }
+}
+
+impl From<ScBinaryOp> for ScToken {
+    fn from(op: ScBinaryOp) -> Self {
+        ScToken::ScOperator(op)
+    }
+}
+
+impl From<Token> for ScToken {
+    fn from(token: Token) -> Self {
+        ScToken::Standard(token)
+    }
+}
+
+/// The operator's spelling (`|>`, `::`), or SWC's description of a standard
+/// token: its punctuator, or e.g. `string literal ("a", 'a')`. SWC's `Token`
+/// has no `Display`; its `Debug` output is that description.
+impl std::fmt::Display for ScToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScToken::Standard(token) => write!(f, "{token:?}"),
+            ScToken::ScOperator(op) => write!(f, "{op}"),
+        }
+    }
+}
+
 /// A token with its source span.
 #[derive(Debug, Clone, PartialEq)]
 pub struct ScTokenAndSpan {
@@ -29,44 +68,89 @@ pub struct ScTokenAndSpan {
     pub had_line_break: bool,
 }
 
+/// One token in a [`MergeRule`] sequence.
+///
+/// Only the punctuators that can spell a sugarcube operator are listed, which
+/// keeps rules `const`-constructible (SWC's `Token` carries atoms).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenPattern {
+    BinOp(BinOpToken),
+    Colon,
+}
+
+impl TokenPattern {
+    fn matches(self, token: &Token) -> bool {
+        match self {
+            TokenPattern::BinOp(op) => matches!(token, Token::BinOp(t) if *t == op),
+            TokenPattern::Colon => matches!(token, Token::Colon),
+        }
+    }
+}
+
+/// A sequence of byte-adjacent standard tokens that spells a sugarcube operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergeRule {
+    pub tokens: &'static [TokenPattern],
+    pub op: ScBinaryOp,
+}
+
+/// The built-in merge table. Adding an operator spelled with more tokens
+/// (e.g. `|>>` as `|` + `>>`) only needs a new entry here.
+pub const MERGE_RULES: &[MergeRule] = &[
+    MergeRule {
+        tokens: &[
+            TokenPattern::BinOp(BinOpToken::BitOr),
+            TokenPattern::BinOp(BinOpToken::Gt),
+        ],
+        op: ScBinaryOp::Pipeline,
+    },
+    MergeRule {
+        tokens: &[TokenPattern::Colon, TokenPattern::Colon],
+        op: ScBinaryOp::Cons,
+    },
+];
+
 /// Merge adjacent standard tokens into sugarcube operators.
 ///
 /// Given a stream of SWC tokens, looks for sequences like `|` `>` (without
 /// intervening whitespace/tokens) and merges them into `ScToken::ScOperator(Pipeline)`.
 pub fn merge_sc_tokens(tokens: &[TokenAndSpan], syntax: &ScSyntax) -> Vec<ScTokenAndSpan> {
+    merge_sc_tokens_with_rules(tokens, syntax, MERGE_RULES)
+}
+
+/// Like [`merge_sc_tokens`], but driven by a caller-supplied rule table.
+///
+/// At each position the longest matching rule wins. Every token in a matched
+/// sequence must be byte-adjacent to the previous one (`span.hi == next.span.lo`).
+/// With [`ScSyntax::require_operator_spaces`], the sequence must also be
+/// separated from the tokens on either side, or it isn't merged.
+pub fn merge_sc_tokens_with_rules(
+    tokens: &[TokenAndSpan],
+    syntax: &ScSyntax,
+    rules: &[MergeRule],
+) -> Vec<ScTokenAndSpan> {
     let mut result = Vec::with_capacity(tokens.len());
     let mut i = 0;
 
     while i < tokens.len() {
-        if syntax.pipeline
-            && i + 1 < tokens.len()
-            && matches!(tokens[i].token, Token::BinOp(BinOpToken::BitOr))
-            && matches!(tokens[i + 1].token, Token::BinOp(BinOpToken::Gt))
-            && tokens[i].span.hi == tokens[i + 1].span.lo
-        {
-            let span = Span::new(tokens[i].span.lo, tokens[i + 1].span.hi);
-            result.push(ScTokenAndSpan {
-                token: ScToken::ScOperator(ScBinaryOp::Pipeline),
-                span,
-                had_line_break: tokens[i].had_line_break,
-            });
-            i += 2;
-            continue;
-        }
+        let matched = rules
+            .iter()
+            .filter(|rule| op_enabled(rule.op, syntax) && rule_matches_at(rule, tokens, i))
+            .filter(|rule| {
+                !syntax.require_operator_spaces
+                    || rule.op == ScBinaryOp::Bind
+                    || is_spaced(tokens, i, i + rule.tokens.len() - 1)
+            })
+            .max_by_key(|rule| rule.tokens.len());
 
-        if syntax.cons
-            && i + 1 < tokens.len()
-            && matches!(tokens[i].token, Token::Colon)
-            && matches!(tokens[i + 1].token, Token::Colon)
-            && tokens[i].span.hi == tokens[i + 1].span.lo
-        {
-            let span = Span::new(tokens[i].span.lo, tokens[i + 1].span.hi);
+        if let Some(rule) = matched {
+            let last = i + rule.tokens.len() - 1;
             result.push(ScTokenAndSpan {
-                token: ScToken::ScOperator(ScBinaryOp::Cons),
-                span,
+                token: ScToken::ScOperator(rule.op),
+                span: Span::new(tokens[i].span.lo, tokens[last].span.hi),
                 had_line_break: tokens[i].had_line_break,
             });
-            i += 2;
+            i = last + 1;
             continue;
         }
 
@@ -81,9 +165,42 @@ pub fn merge_sc_tokens(tokens: &[TokenAndSpan], syntax: &ScSyntax) -> Vec<ScToke
     result
 }
 
+fn op_enabled(op: ScBinaryOp, syntax: &ScSyntax) -> bool {
+    match op {
+        ScBinaryOp::Pipeline => syntax.pipeline,
+        ScBinaryOp::Cons => syntax.cons,
+        ScBinaryOp::Bind => syntax.bind,
+    }
+}
+
+fn is_spaced(tokens: &[TokenAndSpan], first: usize, last: usize) -> bool {
+    let before = first == 0 || tokens[first - 1].span.hi != tokens[first].span.lo;
+    let after = tokens
+        .get(last + 1)
+        .is_none_or(|next| tokens[last].span.hi != next.span.lo);
+    before && after
+}
+
+fn rule_matches_at(rule: &MergeRule, tokens: &[TokenAndSpan], start: usize) -> bool {
+    if rule.tokens.is_empty() || start + rule.tokens.len() > tokens.len() {
+        return false;
+    }
+    let window = &tokens[start..start + rule.tokens.len()];
+
+    let shapes_match = window
+        .iter()
+        .zip(rule.tokens)
+        .all(|(tok, pat)| pat.matches(&tok.token));
+    let adjacent = window.windows(2).all(|w| w[0].span.hi == w[1].span.lo);
+
+    shapes_match && adjacent
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use swc_common::BytePos;
+    use swc_ecma_parser::token::{IdentLike, Word};
 
     #[test]
     fn sc_syntax_default_enables_all() {
@@ -92,4 +209,140 @@ mod tests {
         assert!(s.cons);
         assert!(s.hkt);
     }
+
+    fn tok(token: Token, lo: u32, hi: u32) -> TokenAndSpan {
+        TokenAndSpan {
+            token,
+            had_line_break: false,
+            span: Span::new(BytePos(lo), BytePos(hi)),
+        }
+    }
+
+    #[test]
+    fn token_operator_helpers() {
+        let pipe = ScToken::from(ScBinaryOp::Pipeline);
+        assert_eq!(pipe.as_operator(), Some(ScBinaryOp::Pipeline));
+        assert!(pipe.is_operator(ScBinaryOp::Pipeline));
+        assert!(!pipe.is_operator(ScBinaryOp::Cons));
+        assert_eq!(pipe.to_string(), "|>");
+        assert_eq!(ScToken::ScOperator(ScBinaryOp::Bind).to_string(), "::");
+
+        let standard = ScToken::from(Token::BinOp(BinOpToken::BitOr));
+        assert_eq!(standard.as_operator(), None);
+        assert!(!standard.is_operator(ScBinaryOp::Pipeline));
+        assert_eq!(standard.to_string(), "|");
+    }
+
+    #[test]
+    fn merges_adjacent_pipeline() {
+        let tokens = [
+            tok(Token::BinOp(BinOpToken::BitOr), 0, 1),
+            tok(Token::BinOp(BinOpToken::Gt), 1, 2),
+        ];
+        let merged = merge_sc_tokens(&tokens, &ScSyntax::default());
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].token, ScToken::ScOperator(ScBinaryOp::Pipeline));
+        assert_eq!(merged[0].span, Span::new(BytePos(0), BytePos(2)));
+    }
+
+    #[test]
+    fn does_not_merge_separated_tokens() {
+        let tokens = [tok(Token::Colon, 0, 1), tok(Token::Colon, 2, 3)];
+        let merged = merge_sc_tokens(&tokens, &ScSyntax::default());
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].token, ScToken::Standard(Token::Colon));
+    }
+
+    #[test]
+    fn required_operator_spaces() {
+        let syntax = ScSyntax {
+            require_operator_spaces: true,
+            ..ScSyntax::default()
+        };
+        let x = || Token::Word(Word::Ident(IdentLike::Other("x".into())));
+        // `x |> x`
+        let spaced = [
+            tok(x(), 0, 1),
+            tok(Token::BinOp(BinOpToken::BitOr), 2, 3),
+            tok(Token::BinOp(BinOpToken::Gt), 3, 4),
+            tok(x(), 5, 6),
+        ];
+        let merged = merge_sc_tokens(&spaced, &syntax);
+        assert_eq!(merged[1].token, ScToken::ScOperator(ScBinaryOp::Pipeline));
+
+        // `x|>x`
+        let unspaced = [
+            tok(x(), 0, 1),
+            tok(Token::BinOp(BinOpToken::BitOr), 1, 2),
+            tok(Token::BinOp(BinOpToken::Gt), 2, 3),
+            tok(x(), 3, 4),
+        ];
+        assert_eq!(merge_sc_tokens(&unspaced, &syntax).len(), 4);
+        let merged = merge_sc_tokens(&unspaced, &ScSyntax::default());
+        assert_eq!(merged[1].token, ScToken::ScOperator(ScBinaryOp::Pipeline));
+    }
+
+    #[test]
+    fn merges_three_token_sequence() {
+        // `<|>` lexes as `<`, `|`, `>`.
+        const RULES: &[MergeRule] = &[
+            MergeRule {
+                tokens: &[
+                    TokenPattern::BinOp(BinOpToken::BitOr),
+                    TokenPattern::BinOp(BinOpToken::Gt),
+                ],
+                op: ScBinaryOp::Cons,
+            },
+            MergeRule {
+                tokens: &[
+                    TokenPattern::BinOp(BinOpToken::Lt),
+                    TokenPattern::BinOp(BinOpToken::BitOr),
+                    TokenPattern::BinOp(BinOpToken::Gt),
+                ],
+                op: ScBinaryOp::Pipeline,
+            },
+        ];
+        let tokens = [
+            tok(Token::BinOp(BinOpToken::Lt), 0, 1),
+            tok(Token::BinOp(BinOpToken::BitOr), 1, 2),
+            tok(Token::BinOp(BinOpToken::Gt), 2, 3),
+        ];
+        let merged = merge_sc_tokens_with_rules(&tokens, &ScSyntax::default(), RULES);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].token, ScToken::ScOperator(ScBinaryOp::Pipeline));
+        assert_eq!(merged[0].span, Span::new(BytePos(0), BytePos(3)));
+    }
+
+    #[test]
+    fn three_token_sequence_requires_adjacency_throughout() {
+        const RULES: &[MergeRule] = &[MergeRule {
+            tokens: &[
+                TokenPattern::BinOp(BinOpToken::Lt),
+                TokenPattern::BinOp(BinOpToken::BitOr),
+                TokenPattern::BinOp(BinOpToken::Gt),
+            ],
+            op: ScBinaryOp::Pipeline,
+        }];
+        let tokens = [
+            tok(Token::BinOp(BinOpToken::Lt), 0, 1),
+            tok(Token::BinOp(BinOpToken::BitOr), 1, 2),
+            tok(Token::BinOp(BinOpToken::Gt), 3, 4),
+        ];
+        let merged = merge_sc_tokens_with_rules(&tokens, &ScSyntax::default(), RULES);
+        assert_eq!(merged.len(), 3);
+    }
+
+    #[test]
+    fn disabled_feature_skips_rule() {
+        let tokens = [
+            tok(Token::BinOp(BinOpToken::BitOr), 0, 1),
+            tok(Token::BinOp(BinOpToken::Gt), 1, 2),
+        ];
+        let syntax = ScSyntax {
+            pipeline: false,
+            ..ScSyntax::default()
+        };
+        let merged = merge_sc_tokens(&tokens, &syntax);
+        assert_eq!(merged.len(), 2);
+    }
 }
@@ -13,4 +13,4 @@
 pub mod parse;
 pub mod preprocess;
 
-pub use parse::parse_sugarcube;
+pub use parse::{parse_sugarcube, remap_to_original, ParseSession, PreprocessError};
@@ -1,20 +1,133 @@
+use std::time::{Duration, Instant};
+
 use anyhow::Result;
 use sc_ast::ScSyntax;
 use swc_common::{
-    comments::SingleThreadedComments, errors::Handler, sync::Lrc, FileName, SourceMap,
+    comments::{Comment, CommentKind, Comments, SingleThreadedComments},
+    errors::Handler,
+    sync::Lrc,
+    BytePos, FileName, LineCol, SourceFile, SourceMap, Spanned,
 };
 use swc_ecma_ast::EsVersion;
 use swc_ecma_parser::{Syntax, TsSyntax};
 
-use crate::preprocess;
+use crate::preprocess::{
+    self, render_diagnostic, Diagnostic, OffsetMap, PreprocessPass, PreprocessReport, Severity,
+};
 
 /// Result of parsing a sugarcube source file.
 pub struct ParseResult {
     pub module: swc_ecma_ast::Module,
     pub comments: SingleThreadedComments,
     pub source_map: Lrc<SourceMap>,
+    /// The file SWC parsed: the preprocessed source (after sugarcube rewrites).
+    pub source_file: Lrc<SourceFile>,
+    /// Sugarcube-level diagnostics from preprocessing.
+    pub report: PreprocessReport,
+    /// Whether the file was parsed as TSX.
+    pub tsx: bool,
+    /// How long the preprocess passes took.
+    pub preprocess_time: Duration,
+    /// How long SWC took to parse the preprocessed source.
+    pub parse_time: Duration,
+}
+
+impl ParseResult {
     /// The preprocessed source (after sugarcube rewrites, before SWC parsing).
-    pub preprocessed_source: String,
+    ///
+    /// Borrowed from the `SourceFile` rather than kept as a second copy.
+    pub fn preprocessed_source(&self) -> &str {
+        &self.source_file.src
+    }
+
+    /// Collect the triple-slash directives (`/// <reference ... />`,
+    /// `/// <amd-module ... />`) that precede the first statement.
+    ///
+    /// The emitter runs without comments, which would otherwise drop these
+    /// directives even though `tsc` depends on them. Passing the returned
+    /// comments to the emitter re-emits just the directives.
+    pub fn directive_comments(&self) -> SingleThreadedComments {
+        let directives = SingleThreadedComments::default();
+        let first_item = self.module.body.first().map(|item| item.span_lo());
+
+        let (leading, _) = self.comments.borrow_all();
+        for (pos, comments) in leading.iter() {
+            if first_item.is_some_and(|lo| *pos > lo) {
+                continue;
+            }
+            let kept: Vec<Comment> = comments
+                .iter()
+                .filter(|c| is_triple_slash_directive(c))
+                .cloned()
+                .collect();
+            if !kept.is_empty() {
+                directives.add_leading_comments(*pos, kept);
+            }
+        }
+
+        directives
+    }
+
+    /// The block comment the file opens with, after any shebang: a license
+    /// or banner header, which the emitter would otherwise drop.
+    pub fn banner_comment(&self) -> Option<Comment> {
+        let (leading, trailing) = self.comments.borrow_all();
+        let first = leading
+            .values()
+            .chain(trailing.values())
+            .flatten()
+            .min_by_key(|c| c.span.lo)?;
+        let offset = (first.span.lo.0 - self.source_file.start_pos.0) as usize;
+        let before = &self.source_file.src[..offset];
+        let before = match before.strip_prefix("#!") {
+            Some(shebang) => shebang.split_once('\n').map_or("", |(_, rest)| rest),
+            None => before,
+        };
+        (first.kind == CommentKind::Block && before.trim().is_empty()).then(|| first.clone())
+    }
+}
+
+/// Point emitter source-map `mappings` at `original` instead of the
+/// `preprocessed` file the module was parsed from.
+///
+/// Returns a source map holding `original` under the same file name; build
+/// the final map from it with the rewritten `mappings`.
+pub fn remap_to_original(
+    preprocessed: &SourceFile,
+    offsets: &OffsetMap,
+    original: &str,
+    mappings: &mut [(BytePos, LineCol)],
+) -> Lrc<SourceMap> {
+    let original_map: Lrc<SourceMap> = Default::default();
+    let file = original_map.new_source_file(preprocessed.name.clone(), original.to_string());
+
+    for (pos, _) in mappings.iter_mut() {
+        if pos.is_dummy() || *pos < preprocessed.start_pos || *pos > preprocessed.end_pos {
+            continue;
+        }
+        let offset = (pos.0 - preprocessed.start_pos.0) as usize;
+        let mapped = offsets.to_original(offset).min(original.len());
+        *pos = file.start_pos + BytePos(mapped as u32);
+    }
+
+    original_map
+}
+
+/// A `///` comment whose body is an XML-style tag, e.g. `<reference path="..." />`.
+///
+/// SWC stores `/// <reference />` as a line comment with text `/ <reference />`.
+fn is_triple_slash_directive(comment: &Comment) -> bool {
+    comment.kind == CommentKind::Line
+        && comment
+            .text
+            .strip_prefix('/')
+            .is_some_and(|rest| rest.trim_start().starts_with('<'))
+}
+
+fn is_declaration_file(filename: &str) -> bool {
+    [".d.ts", ".d.mts", ".d.cts"]
+        .iter()
+        .any(|ext| filename.ends_with(ext))
 }
 
 /// Parse a TypeScript/TSX source string with sugarcube extensions.
@@ -23,47 +136,260 @@ pub struct ParseResult {
 /// 2. Parse: feed the preprocessed text to the standard SWC parser.
 ///
 /// If `tsx` is `None`, TSX mode is inferred from the filename extension.
+/// A `.d.ts` (or `.d.mts`/`.d.cts`) file is parsed as a declaration file:
+/// it has no value-level code, so in practice only the HKT pass rewrites it.
+/// Preprocessing errors (e.g. an operator missing an operand) fail the parse
+/// before SWC runs, with a [`PreprocessError`].
 pub fn parse_sugarcube(
     source: &str,
     filename: &str,
     syntax: &ScSyntax,
     tsx: Option<bool>,
 ) -> Result<ParseResult> {
-    let preprocessed = preprocess::preprocess(source, syntax);
-
-    let source_map: Lrc<SourceMap> = Default::default();
-    let source_file = source_map.new_source_file(
-        Lrc::new(FileName::Custom(filename.to_string())),
-        preprocessed.clone(),
-    );
-
-    let comments = SingleThreadedComments::default();
-
-    let handler = Handler::with_emitter_writer(Box::new(std::io::stderr()), Some(source_map.clone()));
-
-    let is_tsx = tsx.unwrap_or_else(|| filename.ends_with(".tsx"));
-    let ts_syntax = Syntax::Typescript(TsSyntax {
-        tsx: is_tsx,
-        decorators: true,
-        ..Default::default()
-    });
-
-    let module = swc_ecma_parser::parse_file_as_module(
-        &source_file,
-        ts_syntax,
-        EsVersion::latest(),
-        Some(&comments),
-        &mut vec![],
-    )
-    .map_err(|e| {
-        e.into_diagnostic(&handler).emit();
-        anyhow::anyhow!("failed to parse {filename}")
-    })?;
-
-    Ok(ParseResult {
-        module,
-        comments,
-        source_map,
-        preprocessed_source: preprocessed,
-    })
+    ParseSession::new().parse(source, filename, syntax, tsx)
+}
+
+/// Why a file failed to parse before SWC saw it: the preprocessor's errors.
+///
+/// Displays as one `file:line:col: error: ...` line per error;
+/// [`render`](Self::render) shows each with its source line underlined.
+#[derive(Debug, Clone)]
+pub struct PreprocessError {
+    pub filename: String,
+    pub source: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl PreprocessError {
+    /// Every error as [`render_diagnostic`] shows it, separated by blank lines.
+    pub fn render(&self) -> String {
+        self.render_with(render_diagnostic)
+    }
+
+    /// [`render`](Self::render) in color, as
+    /// [`render_diagnostic_colored`](preprocess::render_diagnostic_colored)
+    /// shows each error.
+    pub fn render_colored(&self) -> String {
+        self.render_with(preprocess::render_diagnostic_colored)
+    }
+
+    fn render_with(&self, render: fn(&str, &str, &Diagnostic) -> String) -> String {
+        self.diagnostics
+            .iter()
+            .map(|d| render(&self.filename, &self.source, d))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+impl std::fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, d) in self.diagnostics.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let (line, col) = d.line_col(&self.source);
+            write!(
+                f,
+                "{}:{line}:{col}: {}: {}",
+                self.filename, d.severity, d.message
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for PreprocessError {}
+
+/// Setup shared by every file parsed in a session: the `SourceMap` files are
+/// registered in and the handler that renders SWC parse errors.
+///
+/// [`parse_sugarcube`] uses a fresh session per call. Tools parsing many
+/// small inputs can reuse one; each input still gets its own `SourceFile`.
+/// Tools that need several files in one `SourceMap`, e.g. to build one map
+/// across them, can hand theirs to [`with_source_map`](Self::with_source_map).
+///
+/// Not thread-safe: `Lrc` is `Rc` unless `swc_common`'s `concurrent`
+/// feature is on, so a session and its `SourceMap` stay on the thread that
+/// made them. Use one session per thread.
+pub struct ParseSession {
+    source_map: Lrc<SourceMap>,
+    handler: Handler,
+    passes: Vec<Box<dyn PreprocessPass>>,
+}
+
+impl Default for ParseSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ParseSession {
+    pub fn new() -> Self {
+        Self::with_source_map(Default::default())
+    }
+
+    /// A session that registers files in `source_map`, alongside whatever it
+    /// already holds. Byte positions stay unique across all of them.
+    pub fn with_source_map(source_map: Lrc<SourceMap>) -> Self {
+        Self::with_writer(source_map, Box::new(std::io::stderr()))
+    }
+
+    /// A session that discards SWC's parse errors instead of printing them
+    /// to stderr, for callers that only look at the `Result`.
+    pub fn quiet() -> Self {
+        Self::with_writer(Default::default(), Box::new(std::io::sink()))
+    }
+
+    fn with_writer(source_map: Lrc<SourceMap>, writer: Box<dyn std::io::Write + Send>) -> Self {
+        let handler = Handler::with_emitter_writer(writer, Some(source_map.clone()));
+        Self {
+            source_map,
+            handler,
+            passes: preprocess::default_passes(),
+        }
+    }
+
+    /// Preprocess with `passes` instead of [`preprocess::default_passes`],
+    /// e.g. the defaults with a custom [`PreprocessPass`] inserted.
+    pub fn with_passes(mut self, passes: Vec<Box<dyn PreprocessPass>>) -> Self {
+        self.passes = passes;
+        self
+    }
+
+    pub fn source_map(&self) -> &Lrc<SourceMap> {
+        &self.source_map
+    }
+
+    /// Parse one file into this session's `SourceMap`. See [`parse_sugarcube`].
+    pub fn parse(
+        &self,
+        source: &str,
+        filename: &str,
+        syntax: &ScSyntax,
+        tsx: Option<bool>,
+    ) -> Result<ParseResult> {
+        let started = Instant::now();
+        let preprocess::Preprocessed {
+            code: preprocessed,
+            report,
+        } = preprocess::preprocess_with_passes(source, syntax, &self.passes);
+        let preprocess_time = started.elapsed();
+
+        if report.has_errors() {
+            return Err(PreprocessError {
+                filename: filename.to_string(),
+                source: source.to_string(),
+                diagnostics: report
+                    .diagnostics
+                    .into_iter()
+                    .filter(|d| d.severity == Severity::Error)
+                    .collect(),
+            }
+            .into());
+        }
+
+        let source_file = self.source_map.new_source_file(
+            Lrc::new(FileName::Custom(filename.to_string())),
+            preprocessed,
+        );
+
+        let comments = SingleThreadedComments::default();
+
+        let started = Instant::now();
+        let is_tsx = tsx.unwrap_or_else(|| filename.ends_with(".tsx"));
+        let ts_syntax = Syntax::Typescript(TsSyntax {
+            tsx: is_tsx,
+            decorators: true,
+            dts: is_declaration_file(filename),
+            ..Default::default()
+        });
+
+        let module = swc_ecma_parser::parse_file_as_module(
+            &source_file,
+            ts_syntax,
+            EsVersion::latest(),
+            Some(&comments),
+            &mut vec![],
+        )
+        .map_err(|e| {
+            e.into_diagnostic(&self.handler).emit();
+            anyhow::anyhow!("failed to parse {filename}")
+        })?;
+        let parse_time = started.elapsed();
+
+        Ok(ParseResult {
+            module,
+            comments,
+            source_map: self.source_map.clone(),
+            source_file,
+            report,
+            tsx: is_tsx,
+            preprocess_time,
+            parse_time,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leading_bom_is_stripped_and_not_counted_as_a_column() {
+        let source = "\u{FEFF}const x = a |> f;\n";
+        let parsed = parse_sugarcube(source, "input.ts", &ScSyntax::default(), None).unwrap();
+        assert!(!parsed.preprocessed_source().contains('\u{FEFF}'));
+
+        let err = parse_sugarcube(
+            "\u{FEFF}const x = |> f;",
+            "input.ts",
+            &ScSyntax::default(),
+            None,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "input.ts:1:11: error: pipeline requires a left operand"
+        );
+    }
+
+    #[test]
+    fn remapped_positions_point_at_original_hkt_usage() {
+        let source = "const r = a |> f;\nfunction g<F<_>>(x: F<number>) {}\n";
+        let parsed = parse_sugarcube(source, "input.ts", &ScSyntax::default(), None).unwrap();
+
+        let file = &parsed.source_file;
+        let usage = parsed.preprocessed_source().find("$<F, number>").unwrap();
+        let generated = LineCol { line: 0, col: 0 };
+        let mut mappings = vec![(file.start_pos + BytePos(usage as u32), generated)];
+
+        let original_map = remap_to_original(
+            &parsed.source_file,
+            &parsed.report.offsets,
+            source,
+            &mut mappings,
+        );
+        let loc = original_map.lookup_char_pos(mappings[0].0);
+        assert_eq!((loc.line, loc.col_display), (2, 20));
+    }
+
+    #[test]
+    fn sessions_can_share_a_source_map() {
+        let source_map: Lrc<SourceMap> = Default::default();
+        let syntax = ScSyntax::default();
+        let a = ParseSession::with_source_map(source_map.clone())
+            .parse("const a = x |> f;", "a.ts", &syntax, None)
+            .unwrap();
+        let b = ParseSession::with_source_map(source_map.clone())
+            .parse("const b = 1 :: [];", "b.ts", &syntax, None)
+            .unwrap();
+
+        assert_eq!(source_map.files().len(), 2);
+        assert!(a.source_file.end_pos <= b.source_file.start_pos);
+        let b_start = source_map.lookup_char_pos(b.module.span.lo);
+        assert_eq!(b_start.file.name.to_string(), "b.ts");
+        assert!(Lrc::ptr_eq(&a.source_map, &b.source_map));
+    }
 }
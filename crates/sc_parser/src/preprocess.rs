@@ -6,24 +6,99 @@
 //! Processing order:
 //! 1. HKT (`F<_>`) — rewrite declarations and usages
 //! 2. Pipeline (`|>`) and Cons (`::`) — rewrite operators
+//!
+//! Each is a [`PreprocessPass`]; [`preprocess_with_passes`] runs a list that
+//! can include passes defined outside this crate.
+
+use std::ops::Range;
 
 use sc_ast::ScSyntax;
 
 mod hkt_pass;
+mod incremental;
+mod offsets;
 mod operator_pass;
+mod pass;
+mod report;
 mod util;
 
+pub use hkt_pass::{analyze_hkt, HktInfo};
+pub use incremental::{retransform, PreprocessState, TextEdit};
+pub use offsets::OffsetMap;
+pub use pass::{default_passes, HktPass, OperatorPass, PreprocessPass};
+pub use report::{
+    line_col, render_diagnostic, render_diagnostic_colored, Associativity, Diagnostic,
+    OperatorInfo, PreprocessReport, Rewrite, RewriteKind, Severity,
+};
+
+/// Output of [`preprocess_with_report`].
+#[derive(Debug, Clone)]
+pub struct Preprocessed {
+    /// The rewritten, standard TypeScript source.
+    pub code: String,
+    pub report: PreprocessReport,
+}
+
 /// Preprocess a sugarcube source string, rewriting custom syntax to standard TS.
 pub fn preprocess(source: &str, syntax: &ScSyntax) -> String {
-    let mut result = source.to_string();
+    preprocess_with_report(source, syntax).code
+}
+
+/// The byte range of every comment in `source`, in order. A line comment's
+/// range stops before its line break.
+pub fn comment_ranges(source: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    util::CodeMask::scan(source.as_bytes(), |range| ranges.push(range));
+    ranges
+}
+
+/// Like [`preprocess`], but also returns the diagnostics and rewrite records
+/// the passes produced.
+pub fn preprocess_with_report(source: &str, syntax: &ScSyntax) -> Preprocessed {
+    preprocess_with_passes(source, syntax, &default_passes())
+}
+
+/// [`preprocess_with_report`] with `passes` instead of [`default_passes`],
+/// run in order.
+pub fn preprocess_with_passes(
+    source: &str,
+    syntax: &ScSyntax,
+    passes: &[Box<dyn PreprocessPass>],
+) -> Preprocessed {
+    let mut report = PreprocessReport::default();
+    // The one working copy: every pass edits it in place.
+    let mut code = source.to_string();
 
-    if syntax.hkt {
-        result = hkt_pass::rewrite_hkt(&result);
+    // A leading BOM would otherwise read as part of the first identifier.
+    // Stripping it is an edit like any other, so spans still point into
+    // `source` as given.
+    if code.starts_with('\u{FEFF}') {
+        report
+            .offsets
+            .splice(&mut code, 0..'\u{FEFF}'.len_utf8(), "");
+    }
+
+    for pass in passes {
+        pass.run(&mut code, syntax, &mut report);
+    }
+    // An earlier error already fails the file; whatever it left unrewritten
+    // needn't be reported again.
+    if syntax.error_on_residual && !report.has_errors() {
+        operator_pass::report_residual_operators(&code, syntax, &mut report);
     }
 
-    if syntax.pipeline || syntax.cons {
-        result = operator_pass::rewrite_operators(&result, syntax);
+    // A mapped end can take in whitespace or a comment a rewrite replaced
+    // (`f )` for the `)` of `__binop__(a, "|>", f)`); trim so spans cover
+    // just the text.
+    if !report.rewrites.is_empty() {
+        let mask = util::CodeMask::new(source.as_bytes());
+        for rewrite in &mut report.rewrites {
+            rewrite.span = util::trimmed_range(source, &mask, rewrite.span.clone());
+            for operand in &mut rewrite.operands {
+                *operand = util::trimmed_range(source, &mask, operand.clone());
+            }
+        }
     }
 
-    result
+    Preprocessed { code, report }
 }
@@ -3,11 +3,34 @@
 //! Finds `F<_>` declarations in type parameter lists, strips `<_>`, and
 //! rewrites usages of `F<A>` to `$<F, A>` within the declaring scope.
 
-use super::util::{char_offset_to_byte, skip_non_code, HandleResult, TemplateState};
+use std::ops::Range;
+
+use super::offsets::OffsetMap;
+use super::report::{Diagnostic, PreprocessReport, Rewrite, RewriteKind};
+use super::util::{is_ident_byte, matching_bracket, starts_type_name, trimmed_range, CodeMask};
+
+/// An HKT parameter declaration, as [`analyze_hkt`] finds it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HktInfo {
+    /// The parameter's name: `F` for `F<_>`.
+    pub name: String,
+    /// How many type arguments it takes: 2 for `F<_, _>`.
+    pub arity: usize,
+    /// Byte range of the declaration, `F<_>`, in the source.
+    pub span: Range<usize>,
+    /// Byte range of the declaring item (`interface Functor<F<_>> { ... }`),
+    /// within which `F<A>` is rewritten.
+    pub scope: Range<usize>,
+}
 
 #[derive(Debug, Clone)]
 struct HktDecl {
     name: String,
+    /// Byte offset of the declared identifier.
+    name_start: usize,
+    /// How many `_` it was declared with, and so how many type arguments a
+    /// usage must have.
+    arity: usize,
     /// Range of `<_>` to remove (byte offsets into the original source).
     remove_start: usize,
     remove_end: usize,
@@ -20,89 +43,170 @@ struct HktDecl {
 struct HktUsage {
     /// Byte offset of the identifier (e.g. `F`).
     ident_start: usize,
+    /// Byte offset after the identifier.
+    ident_end: usize,
+    /// The type arguments between `<` and `>`, trimmed of whitespace and a
+    /// trailing comma.
+    args: Range<usize>,
     /// Byte offset after the closing `>`.
     end: usize,
-    /// The parameter name.
-    name: String,
-    /// The inner args text (between `<` and `>`).
-    args: String,
+    /// Index into the declaration list of the declaration this usage resolves to.
+    decl: usize,
 }
 
-/// Rewrite all HKT syntax in the source.
-pub fn rewrite_hkt(source: &str) -> String {
-    let chars: Vec<char> = source.chars().collect();
-
-    let decls = find_hkt_declarations(&chars, source);
+/// Rewrite all HKT syntax in `text`, in place.
+///
+/// Usages are applied through `apply` (`F<A>` → `{apply}<F, A>`). Warns about
+/// HKT parameters that are never applied (`F<A>`) in their scope, and about
+/// `F<_>` outside a type parameter list, which is left as written.
+pub fn rewrite_hkt(text: &mut String, apply: &str, report: &mut PreprocessReport) {
+    let bytes = text.as_bytes();
+    let mask = CodeMask::new(bytes);
+    let angles = AngleMatches::new(bytes, &mask);
+
+    let decls = find_hkt_declarations(bytes, &mask, &angles, text, report);
     if decls.is_empty() {
-        return source.to_string();
+        return;
     }
 
-    let usages = find_hkt_usages(&chars, source, &decls);
+    let usages = find_hkt_usages(bytes, &mask, &angles, text, &decls, report);
+    report_unused_decls(&decls, &usages, report);
+
+    let offsets = &report.offsets;
+    let decl_rewrites = decls.iter().map(|decl| Rewrite {
+        kind: RewriteKind::HktDecl,
+        span: offsets.to_original_range(decl.name_start..decl.remove_end),
+        head: offsets.to_original_range(decl.name_start..decl.name_start + decl.name.len()),
+        operands: Vec::new(),
+    });
+    let usage_rewrites = usages.iter().map(|usage| Rewrite {
+        kind: RewriteKind::HktUsage,
+        span: offsets.to_original_range(usage.ident_start..usage.end),
+        head: offsets.to_original_range(usage.ident_start..usage.ident_end),
+        operands: vec![offsets.to_original_range(usage.args.clone())],
+    });
+    let rewrites: Vec<Rewrite> = decl_rewrites.chain(usage_rewrites).collect();
+    report.rewrites.extend(rewrites);
+
+    apply_hkt_replacements(text, &decls, &usages, apply, &mut report.offsets);
+}
 
-    apply_hkt_replacements(source, &decls, &usages)
+/// Report each `F<_>` declaration, for [`strict`](sc_ast::ScSyntax::strict)
+/// mode with HKT disabled. Left as written, SWC would reject the `_`
+/// without saying the syntax is only switched off.
+pub fn report_disabled_hkt(text: &str, report: &mut PreprocessReport) {
+    let bytes = text.as_bytes();
+    let mask = CodeMask::new(bytes);
+    let angles = AngleMatches::new(bytes, &mask);
+    // Only the declarations are wanted, not the warnings found on the way.
+    let mut scratch = PreprocessReport::default();
+    for decl in find_hkt_declarations(bytes, &mask, &angles, text, &mut scratch) {
+        report.diagnostics.push(Diagnostic::error(
+            "HKT syntax found but hkt feature is disabled",
+            report
+                .offsets
+                .to_original_range(decl.name_start..decl.remove_end),
+        ));
+    }
 }
 
-fn find_hkt_declarations(chars: &[char], source: &str) -> Vec<HktDecl> {
-    let mut decls = Vec::new();
-    let mut i = 0;
-    let mut template_state = TemplateState::new();
+/// Every HKT parameter declared in `source`, in source order, without
+/// rewriting anything: for tools, such as doc generators, that annotate
+/// higher-kinded types.
+pub fn analyze_hkt(source: &str) -> Vec<HktInfo> {
+    let bytes = source.as_bytes();
+    let mask = CodeMask::new(bytes);
+    let angles = AngleMatches::new(bytes, &mask);
+    let mut scratch = PreprocessReport::default();
+    find_hkt_declarations(bytes, &mask, &angles, source, &mut scratch)
+        .into_iter()
+        .map(|decl| HktInfo {
+            arity: decl.arity,
+            span: decl.name_start..decl.remove_end,
+            scope: trimmed_range(source, &mask, decl.scope_start..decl.scope_end),
+            name: decl.name,
+        })
+        .collect()
+}
 
-    while i < chars.len() {
-        // Handle template literals (process code in interpolations, skip literal parts)
-        match template_state.handle_char(chars, i) {
-            HandleResult::Skip(n) => {
-                i += n;
-                continue;
-            }
-            HandleResult::Process => {}
+fn report_unused_decls(decls: &[HktDecl], usages: &[HktUsage], report: &mut PreprocessReport) {
+    for (idx, decl) in decls.iter().enumerate() {
+        if usages.iter().any(|u| u.decl == idx) {
+            continue;
         }
+        let span = report.offsets.to_original(decl.name_start)
+            ..report.offsets.to_original(decl.remove_end);
+        report.diagnostics.push(Diagnostic::warning(
+            format!(
+                "HKT parameter `{}` is declared with `<_>` but never applied (e.g. `{}<A>`) in its scope",
+                decl.name, decl.name
+            ),
+            span,
+        ));
+    }
+}
 
-        // Skip strings and comments.
-        if let Some(skip) = skip_non_code(chars, i) {
-            i = skip;
+fn find_hkt_declarations(
+    bytes: &[u8],
+    mask: &CodeMask,
+    angles: &AngleMatches,
+    source: &str,
+    report: &mut PreprocessReport,
+) -> Vec<HktDecl> {
+    let mut decls = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        // Strings, comments, regexes and template text.
+        if !mask.is_code(i) {
+            i += 1;
             continue;
         }
 
         // Look for uppercase identifier followed by `<_>`
-        if chars[i].is_ascii_uppercase() {
+        if starts_type_name(source, i) {
             let ident_start = i;
-            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+            let name_start = i;
+            while i < bytes.len() && is_ident_byte(bytes[i]) {
                 i += 1;
             }
-            let name: String = chars[ident_start..i].iter().collect();
+            let name = source[ident_start..i].to_string();
 
             // Skip whitespace
-            while i < chars.len() && chars[i].is_whitespace() {
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
                 i += 1;
             }
 
-            if i < chars.len() && chars[i] == '<' {
-                let angle_byte_start = char_offset_to_byte(chars, i);
+            if i < bytes.len() && bytes[i] == b'<' {
+                let angle_byte_start = i;
                 i += 1;
 
                 // Skip whitespace
-                while i < chars.len() && chars[i].is_whitespace() {
+                while i < bytes.len() && bytes[i].is_ascii_whitespace() {
                     i += 1;
                 }
 
                 // Check for `_` (possibly comma-separated)
                 let mut all_underscores = true;
 
-                if i < chars.len() && chars[i] == '_' {
+                if i < bytes.len() && bytes[i] == b'_' {
                     i += 1;
 
                     // Check for more `_, _` patterns
                     loop {
-                        while i < chars.len() && chars[i].is_whitespace() {
+                        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
                             i += 1;
                         }
-                        if i < chars.len() && chars[i] == ',' {
+                        if i < bytes.len() && bytes[i] == b',' {
                             i += 1;
-                            while i < chars.len() && chars[i].is_whitespace() {
+                            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
                                 i += 1;
                             }
-                            if i < chars.len() && chars[i] == '_' {
+                            if i < bytes.len() && bytes[i] == b'_' {
                                 i += 1;
+                            } else if i < bytes.len() && bytes[i] == b'>' {
+                                // Trailing comma: `F<_,>`
+                                break;
                             } else {
                                 all_underscores = false;
                                 break;
@@ -113,18 +217,35 @@ fn find_hkt_declarations(chars: &[char], source: &str) -> Vec<HktDecl> {
                     }
 
                     // Skip whitespace
-                    while i < chars.len() && chars[i].is_whitespace() {
+                    while i < bytes.len() && bytes[i].is_ascii_whitespace() {
                         i += 1;
                     }
 
-                    if all_underscores && i < chars.len() && chars[i] == '>' {
+                    if all_underscores && i < bytes.len() && bytes[i] == b'>' {
                         i += 1;
-                        let angle_byte_end = char_offset_to_byte(chars, i);
+                        let angle_byte_end = i;
+
+                        if !in_type_param_list(bytes, mask, angles, ident_start) {
+                            let span = report.offsets.to_original(name_start)
+                                ..report.offsets.to_original(angle_byte_end);
+                            report.diagnostics.push(Diagnostic::warning(
+                                format!(
+                                    "`{name}<_>` outside a type parameter list is not an HKT declaration; left unchanged"
+                                ),
+                                span,
+                            ));
+                            continue;
+                        }
 
-                        let scope = find_enclosing_scope(chars, ident_start, source);
+                        let scope = find_enclosing_scope(bytes, mask, ident_start);
 
                         decls.push(HktDecl {
                             name,
+                            name_start,
+                            arity: bytes[angle_byte_start..angle_byte_end]
+                                .iter()
+                                .filter(|&&b| b == b'_')
+                                .count(),
                             remove_start: angle_byte_start,
                             remove_end: angle_byte_end,
                             scope_start: scope.0,
@@ -143,69 +264,91 @@ fn find_hkt_declarations(chars: &[char], source: &str) -> Vec<HktDecl> {
     decls
 }
 
-fn find_hkt_usages(chars: &[char], _source: &str, decls: &[HktDecl]) -> Vec<HktUsage> {
+fn find_hkt_usages(
+    bytes: &[u8],
+    mask: &CodeMask,
+    angles: &AngleMatches,
+    source: &str,
+    decls: &[HktDecl],
+    report: &mut PreprocessReport,
+) -> Vec<HktUsage> {
     let mut usages = Vec::new();
     let mut i = 0;
-    let mut template_state = TemplateState::new();
-
-    while i < chars.len() {
-        // Handle template literals (process code in interpolations, skip literal parts)
-        match template_state.handle_char(chars, i) {
-            HandleResult::Skip(n) => {
-                i += n;
-                continue;
-            }
-            HandleResult::Process => {}
-        }
 
-        if let Some(skip) = skip_non_code(chars, i) {
-            i = skip;
+    while i < bytes.len() {
+        if !mask.is_code(i) {
+            i += 1;
             continue;
         }
 
-        if chars[i].is_ascii_uppercase() {
+        if starts_type_name(source, i) {
             let ident_start = i;
-            let ident_byte_start = char_offset_to_byte(chars, i);
-            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+            let ident_byte_start = i;
+            while i < bytes.len() && is_ident_byte(bytes[i]) {
                 i += 1;
             }
-            let name: String = chars[ident_start..i].iter().collect();
+            let ident_end = i;
+            let name = &source[ident_start..ident_end];
 
             // Skip whitespace
-            while i < chars.len() && chars[i].is_whitespace() {
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
                 i += 1;
             }
 
-            if i < chars.len() && chars[i] == '<' {
-                if let Some(close) = find_matching_angle(chars, i) {
+            if i < bytes.len() && bytes[i] == b'<' {
+                let close = angles.close(i);
+                if close.is_none() && find_active_decl(decls, name, ident_byte_start).is_some() {
+                    let span = report.offsets.to_original(ident_byte_start)
+                        ..report.offsets.to_original(i + 1);
+                    report.diagnostics.push(Diagnostic::warning(
+                        format!("`{name}<` has no matching `>` in its statement; left unchanged"),
+                        span,
+                    ));
+                }
+                if let Some(close) = close {
                     let inner_start = i + 1;
-                    let inner_chars: String = chars[inner_start..close].iter().collect();
+                    let inner = &source[inner_start..close];
 
                     // Check it's not just underscores (that's a declaration, not usage)
-                    let trimmed = inner_chars.trim();
+                    let trimmed = inner.trim();
                     if trimmed == "_"
                         || trimmed
-                            .chars()
-                            .all(|c| c == '_' || c == ',' || c.is_whitespace())
+                            .bytes()
+                            .all(|c| c == b'_' || c == b',' || c.is_ascii_whitespace())
                     {
                         i = close + 1;
                         continue;
                     }
 
-                    let usage_byte_start = ident_byte_start;
-                    let usage_byte_end = char_offset_to_byte(chars, close + 1);
-
                     // Check if this usage is within any HKT declaration's scope
-                    if find_active_decl(decls, &name, usage_byte_start).is_some() {
+                    if let Some(decl) = find_active_decl(decls, name, ident_byte_start) {
+                        let args = trim_trailing_comma(inner);
+                        let args_start = inner_start + (inner.len() - inner.trim_start().len());
+                        let args = args_start..args_start + args.len();
+                        let count = count_type_args(bytes, mask, angles, args.clone());
+                        let arity = decls[decl].arity;
+                        if count != arity {
+                            let declared = vec!["_"; arity].join(", ");
+                            report.diagnostics.push(Diagnostic::error(
+                                format!(
+                                    "`{name}` takes {arity} type argument{} (declared `{name}<{declared}>`) but is given {count}",
+                                    if arity == 1 { "" } else { "s" }
+                                ),
+                                report.offsets.to_original_range(ident_byte_start..close + 1),
+                            ));
+                        }
                         usages.push(HktUsage {
-                            ident_start: usage_byte_start,
-                            end: usage_byte_end,
-                            name: name.clone(),
-                            args: inner_chars.trim().to_string(),
+                            ident_start: ident_byte_start,
+                            ident_end,
+                            args,
+                            end: close + 1,
+                            decl,
                         });
                     }
 
-                    i = close + 1;
+                    // Keep scanning inside the arguments so nested usages
+                    // (`F<G<A>>`) are found too.
+                    i = inner_start;
                     continue;
                 }
             }
@@ -218,50 +361,91 @@ fn find_hkt_usages(chars: &[char], _source: &str, decls: &[HktDecl]) -> Vec<HktU
     usages
 }
 
-fn find_active_decl<'a>(decls: &'a [HktDecl], name: &str, pos: usize) -> Option<&'a HktDecl> {
+/// How many type arguments `args` holds: one more than its commas outside
+/// nested brackets (`F<Map<K, V>, (a: A, b: B) => C>` has two).
+fn count_type_args(
+    bytes: &[u8],
+    mask: &CodeMask,
+    angles: &AngleMatches,
+    args: Range<usize>,
+) -> usize {
+    let mut count = 1;
+    let mut i = args.start;
+    while i < args.end {
+        if mask.is_code(i) {
+            let close = match bytes[i] {
+                b'<' => angles.close(i),
+                b'(' | b'[' | b'{' => matching_bracket(bytes, mask, i),
+                b',' => {
+                    count += 1;
+                    None
+                }
+                _ => None,
+            };
+            i = close.unwrap_or(i);
+        }
+        i += 1;
+    }
+    count
+}
+
+/// Trim whitespace and a single trailing comma (`A, B,` → `A, B`), which TS
+/// allows in type argument lists but would be misplaced after `$<F, ...>`.
+fn trim_trailing_comma(args: &str) -> &str {
+    let trimmed = args.trim();
+    trimmed.strip_suffix(',').map_or(trimmed, str::trim_end)
+}
+
+/// Index of the innermost declaration of `name` whose scope contains `pos`.
+fn find_active_decl(decls: &[HktDecl], name: &str, pos: usize) -> Option<usize> {
     decls
         .iter()
-        .filter(|d| d.name == name && pos >= d.scope_start && pos <= d.scope_end)
-        .min_by_key(|d| d.scope_end - d.scope_start)
+        .enumerate()
+        .filter(|(_, d)| d.name == name && pos >= d.scope_start && pos <= d.scope_end)
+        .min_by_key(|(_, d)| d.scope_end - d.scope_start)
+        .map(|(idx, _)| idx)
 }
 
-fn apply_hkt_replacements(source: &str, decls: &[HktDecl], usages: &[HktUsage]) -> String {
-    // Collect all replacements sorted by position (descending for safe replacement).
-    let mut replacements: Vec<(usize, usize, String)> = Vec::new();
+fn apply_hkt_replacements(
+    text: &mut String,
+    decls: &[HktDecl],
+    usages: &[HktUsage],
+    apply: &str,
+    offsets: &mut OffsetMap,
+) {
+    // Each usage becomes three small edits around text that is kept as is:
+    // `F<A>` → `$<` + `F` + `, ` + `A` + `>`. Nested usages (`F<G<A>>`) then
+    // only ever touch disjoint ranges, so both rewrites apply and nest.
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
 
     for decl in decls {
-        replacements.push((decl.remove_start, decl.remove_end, String::new()));
+        edits.push((decl.remove_start..decl.remove_end, String::new()));
     }
 
     for usage in usages {
-        let replacement = format!("$<{}, {}>", usage.name, usage.args);
-        replacements.push((usage.ident_start, usage.end, replacement));
+        edits.push((usage.ident_start..usage.ident_start, format!("{apply}<")));
+        edits.push((usage.ident_end..usage.args.start, ", ".to_string()));
+        edits.push((usage.args.end..usage.end, ">".to_string()));
     }
 
-    // Sort by start position descending so replacements don't shift offsets.
-    replacements.sort_by(|a, b| b.0.cmp(&a.0));
+    // Apply right to left so earlier ranges stay valid. At equal starts the
+    // longer range goes first, so an insertion lands before it rather than
+    // being replaced by it.
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse((range.start, range.end)));
 
-    // Remove overlapping replacements (keep the first = outermost by position).
-    let mut filtered = Vec::new();
-    for r in &replacements {
-        if filtered
-            .iter()
-            .any(|(s, e, _): &(usize, usize, String)| r.0 >= *s && r.1 <= *e)
-        {
+    let mut applied_from = usize::MAX;
+    for (range, replacement) in edits {
+        // A `<_>` inside a usage's arguments isn't valid TS either way; keep
+        // the edits that don't overlap rather than splicing garbage.
+        if range.end > applied_from {
             continue;
         }
-        filtered.push(r.clone());
+        applied_from = range.start;
+        offsets.splice(text, range, &replacement);
     }
-
-    let mut result = source.to_string();
-    for (start, end, replacement) in filtered {
-        result = format!("{}{}{}", &result[..start], replacement, &result[end..]);
-    }
-
-    result
 }
 
-fn find_enclosing_scope(chars: &[char], pos: usize, source: &str) -> (usize, usize) {
+fn find_enclosing_scope(bytes: &[u8], mask: &CodeMask, pos: usize) -> (usize, usize) {
     // The HKT declaration is in a type parameter list (e.g. `interface Foo<F<_>> { ... }`).
     // The scope includes everything from the declaration's container start through
     // the closing `}` or `;`.
@@ -272,45 +456,35 @@ fn find_enclosing_scope(chars: &[char], pos: usize, source: &str) -> (usize, usi
     while j > 0 {
         j -= 1;
         // Stop at statement-ending tokens
-        if chars[j] == '}' || chars[j] == ';' {
+        if mask.is_code(j) && (bytes[j] == b'}' || bytes[j] == b';') {
             scope_start_char = j + 1;
             break;
         }
     }
-    let scope_start = char_offset_to_byte(chars, scope_start_char);
+    let scope_start = scope_start_char;
 
     // Scan forward from the declaration to find the end of the scope.
     // Look for the matching closing `}` or `;` at depth 0.
-    let mut scope_end = source.len();
+    let mut scope_end = bytes.len();
     let mut depth = 0;
     let mut j = pos;
-    let mut template_state = TemplateState::new();
 
-    while j < chars.len() {
-        // Handle template literals
-        match template_state.handle_char(chars, j) {
-            HandleResult::Skip(n) => {
-                j += n;
-                continue;
-            }
-            HandleResult::Process => {}
-        }
-
-        if let Some(skip) = skip_non_code(chars, j) {
-            j = skip;
+    while j < bytes.len() {
+        if !mask.is_code(j) {
+            j += 1;
             continue;
         }
-        match chars[j] {
-            '{' => depth += 1,
-            '}' => {
+        match bytes[j] {
+            b'{' => depth += 1,
+            b'}' => {
                 if depth <= 1 {
-                    scope_end = char_offset_to_byte(chars, j + 1);
+                    scope_end = j + 1;
                     break;
                 }
                 depth -= 1;
             }
-            ';' if depth == 0 => {
-                scope_end = char_offset_to_byte(chars, j + 1);
+            b';' if depth == 0 => {
+                scope_end = j + 1;
                 break;
             }
             _ => {}
@@ -321,35 +495,267 @@ fn find_enclosing_scope(chars: &[char], pos: usize, source: &str) -> (usize, usi
     (scope_start, scope_end)
 }
 
-fn find_matching_angle(chars: &[char], start: usize) -> Option<usize> {
+/// Whether the identifier at `ident` is an entry of a type parameter list:
+/// directly after its `<` or a `,`, in a list that follows a declared name
+/// (`interface Foo<`, `class`, `type`, `function`) or precedes a parameter
+/// list (`map<F<_>>(`, `<F<_>>(fa) =>`).
+fn in_type_param_list(bytes: &[u8], mask: &CodeMask, angles: &AngleMatches, ident: usize) -> bool {
+    let mut prev = ident;
+    while prev > 0 && bytes[prev - 1].is_ascii_whitespace() {
+        prev -= 1;
+    }
+    if prev == 0 || !matches!(bytes[prev - 1], b'<' | b',') {
+        return false;
+    }
+
+    // Walk back over earlier entries to the list's `<`.
     let mut depth = 0;
-    let mut i = start;
-    while i < chars.len() {
-        match chars[i] {
-            '<' => depth += 1,
-            '>' => {
-                depth -= 1;
-                if depth == 0 {
-                    return Some(i);
+    let mut j = prev;
+    let open = loop {
+        if j == 0 {
+            return false;
+        }
+        j -= 1;
+        if !mask.is_code(j) {
+            continue;
+        }
+        match bytes[j] {
+            // `=>` in a default (`T = () => void`) isn't a bracket.
+            b'>' if j > 0 && bytes[j - 1] == b'=' => j -= 1,
+            b'>' | b')' | b']' | b'}' => depth += 1,
+            b'<' if depth == 0 => break j,
+            b'(' | b'[' | b'{' | b';' if depth == 0 => return false,
+            b'<' | b'(' | b'[' | b'{' => depth -= 1,
+            _ => {}
+        }
+    };
+
+    // `interface Foo<...`, `class Foo<...`, `type Foo<...`, `function foo<...`
+    let mut name_end = open;
+    while name_end > 0 && bytes[name_end - 1].is_ascii_whitespace() {
+        name_end -= 1;
+    }
+    let mut name_start = name_end;
+    while name_start > 0 && is_ident_byte(bytes[name_start - 1]) {
+        name_start -= 1;
+    }
+    if name_start < name_end {
+        let mut kw_end = name_start;
+        while kw_end > 0 && (bytes[kw_end - 1].is_ascii_whitespace() || bytes[kw_end - 1] == b'*') {
+            kw_end -= 1;
+        }
+        let mut kw_start = kw_end;
+        while kw_start > 0 && is_ident_byte(bytes[kw_start - 1]) {
+            kw_start -= 1;
+        }
+        if matches!(
+            &bytes[kw_start..kw_end],
+            b"interface" | b"class" | b"type" | b"function"
+        ) {
+            return true;
+        }
+    }
+
+    // Generic methods, call signatures, and arrows: `<...>(`.
+    angles.close(open).is_some_and(|close| {
+        bytes[close + 1..]
+            .iter()
+            .find(|b| !b.is_ascii_whitespace())
+            .is_some_and(|&b| b == b'(')
+    })
+}
+
+/// The `>` closing each `<` in the code, found in one pass so that looking
+/// one up doesn't rescan the text: unbalanced input (`F<A<B<C ...`) would
+/// otherwise cost a scan to the end of the file per `<`.
+///
+/// A `<` is closed by the first `>` that brings the count back to where it
+/// was, not crossing a `;`, `{` or `}` while it is the innermost one open.
+struct AngleMatches {
+    /// Each code `<`, in order, with its `>` if it has one.
+    closes: Vec<(usize, Option<usize>)>,
+}
+
+impl AngleMatches {
+    fn new(bytes: &[u8], mask: &CodeMask) -> Self {
+        let mut closes = Vec::new();
+        // Indices into `closes` of the `<` still open, innermost last, and
+        // whether each can still be closed.
+        let mut open: Vec<(usize, bool)> = Vec::new();
+        for (i, &b) in bytes.iter().enumerate() {
+            if !mask.is_code(i) {
+                continue;
+            }
+            match b {
+                b'<' => {
+                    open.push((closes.len(), true));
+                    closes.push((i, None));
                 }
+                b'>' => {
+                    if let Some((idx, true)) = open.pop() {
+                        closes[idx].1 = Some(i);
+                    }
+                }
+                // Don't cross statement boundaries
+                b';' | b'{' | b'}' => {
+                    if let Some(innermost) = open.last_mut() {
+                        innermost.1 = false;
+                    }
+                }
+                _ => {}
             }
-            // Don't cross statement boundaries
-            ';' | '{' | '}' if depth <= 1 => return None,
-            _ => {}
         }
-        i += 1;
+        AngleMatches { closes }
+    }
+
+    /// The `>` matching the `<` at `open`.
+    fn close(&self, open: usize) -> Option<usize> {
+        let idx = self
+            .closes
+            .binary_search_by_key(&open, |&(at, _)| at)
+            .ok()?;
+        self.closes[idx].1
     }
-    None
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::preprocess::Severity;
+
+    fn rewrite(input: &str) -> (String, PreprocessReport) {
+        let mut report = PreprocessReport::default();
+        let mut output = input.to_string();
+        rewrite_hkt(&mut output, "$", &mut report);
+        (output, report)
+    }
+
+    #[test]
+    fn unbalanced_angles_do_not_go_quadratic() {
+        // Rescanning to the end of the file per `<` would take hours on this
+        // input, so a bound this loose still catches it on a slow machine.
+        let soup = "F<A<B; ".repeat(200_000);
+        let input = format!("interface Functor<F<_>> {{\n  map: {soup}\n}}\n");
+        let started = std::time::Instant::now();
+        let (output, report) = rewrite(&input);
+        assert!(started.elapsed() < std::time::Duration::from_secs(60));
+        assert!(output.contains(&soup), "unmatched `<` are left as written");
+        assert!(
+            report
+                .diagnostics
+                .iter()
+                .any(|d| d.message.contains("no matching `>`")),
+            "{:?}",
+            report.diagnostics
+        );
+    }
+
+    #[test]
+    fn strict_mode_reports_disabled_hkt() {
+        let source = "interface Functor<F<_>> {\n  map: (fa: F<number>) => F<string>;\n}\n";
+        let syntax = sc_ast::ScSyntax {
+            hkt: false,
+            strict: true,
+            ..sc_ast::ScSyntax::default()
+        };
+        let preprocessed = crate::preprocess::preprocess_with_report(source, &syntax);
+        assert_eq!(preprocessed.code, source);
+        let errors: Vec<_> = preprocessed
+            .report
+            .diagnostics
+            .iter()
+            .map(|d| (d.message.as_str(), &source[d.span.clone()]))
+            .collect();
+        assert_eq!(
+            errors,
+            [("HKT syntax found but hkt feature is disabled", "F<_>")]
+        );
+    }
+
+    #[test]
+    fn usages_with_the_wrong_number_of_arguments_are_errors() {
+        let source = "\
+interface Tri<F<_, _, _>> {
+  ok: F<A, Map<K, V>, (a: A, b: B) => C>;
+  short: F<A, B>;
+  long: F<A, B, C, D,>;
+}
+function f<G<_>>(g: G<[A, B]>, h: G<A, B>): void {}
+";
+        let (output, report) = rewrite(source);
+        assert!(
+            output.contains("ok: $<F, A, Map<K, V>, (a: A, b: B) => C>;"),
+            "{output}"
+        );
+        let errors: Vec<_> = report
+            .diagnostics
+            .iter()
+            .map(|d| (d.severity, d.message.as_str(), &source[d.span.clone()]))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                (
+                    Severity::Error,
+                    "`F` takes 3 type arguments (declared `F<_, _, _>`) but is given 2",
+                    "F<A, B>"
+                ),
+                (
+                    Severity::Error,
+                    "`F` takes 3 type arguments (declared `F<_, _, _>`) but is given 4",
+                    "F<A, B, C, D,>"
+                ),
+                (
+                    Severity::Error,
+                    "`G` takes 1 type argument (declared `G<_>`) but is given 2",
+                    "G<A, B>"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn analyze_lists_declarations_across_interfaces() {
+        let source = "\
+/** Mappable. */
+interface Functor<F<_>> {
+  map: <A, B>(fa: F<A>, f: (a: A) => B) => F<B>;
+}
+
+interface Bifunctor<P<_, _>> {
+  bimap: <A, B, C, D>(pab: P<A, B>) => P<C, D>;
+}
+
+type NaturalTransformation<F<_>, G<_>> = <A>(fa: F<A>) => G<A>;
+";
+        let decls: Vec<_> = analyze_hkt(source)
+            .into_iter()
+            .map(|info| {
+                let scope = &source[info.scope.clone()];
+                let owner = scope.split(['<', ' ']).nth(1).unwrap();
+                (info.name, info.arity, &source[info.span], owner)
+            })
+            .collect();
+        assert_eq!(
+            decls,
+            [
+                ("F".to_string(), 1, "F<_>", "Functor"),
+                ("P".to_string(), 2, "P<_, _>", "Bifunctor"),
+                ("F".to_string(), 1, "F<_>", "NaturalTransformation"),
+                ("G".to_string(), 1, "G<_>", "NaturalTransformation"),
+            ]
+        );
+        let functor = &analyze_hkt(source)[0];
+        assert!(source[functor.scope.clone()].starts_with("interface Functor"));
+        assert!(source[functor.scope.clone()].ends_with("F<B>;\n}"));
+        assert!(analyze_hkt("const a = b |> f;").is_empty());
+    }
 
     #[test]
     fn hkt_basic_declaration() {
         let input = "interface Functor<F<_>> {\n  map: <A, B>(fa: F<A>) => F<B>;\n}";
-        let output = rewrite_hkt(input);
+        let (output, report) = rewrite(input);
+        assert!(report.diagnostics.is_empty(), "{:?}", report.diagnostics);
         assert!(output.contains("Functor<F>"), "Should strip <_>: {output}");
         assert!(output.contains("$<F, A>"), "Should rewrite F<A>: {output}");
         assert!(output.contains("$<F, B>"), "Should rewrite F<B>: {output}");
@@ -359,11 +765,104 @@ mod tests {
     fn hkt_no_rewrite_outside_scope() {
         let input =
             "interface Functor<F<_>> { map: (fa: F<A>) => F<B>; }\nconst x: F<number> = foo;";
-        let output = rewrite_hkt(input);
+        let (output, _) = rewrite(input);
         // F<number> outside the interface scope should NOT be rewritten
         assert!(
             output.contains("F<number>"),
             "Should not rewrite outside scope: {output}"
         );
     }
+
+    #[test]
+    fn hkt_unused_declaration_warns() {
+        let input = "interface Foo<F<_>> { x: number }";
+        let (output, report) = rewrite(input);
+        assert_eq!(output, "interface Foo<F> { x: number }");
+        assert_eq!(report.diagnostics.len(), 1, "{:?}", report.diagnostics);
+
+        let diag = &report.diagnostics[0];
+        assert_eq!(diag.severity, crate::preprocess::Severity::Warning);
+        assert!(diag.message.contains("`F`"), "{}", diag.message);
+        assert_eq!(&input[diag.span.clone()], "F<_>");
+    }
+
+    #[test]
+    fn hkt_shadowed_outer_declaration_warns_once() {
+        let input = "interface Outer<F<_>> { inner: <F<_>>(fa: F<A>) => void; }";
+        let (_, report) = rewrite(input);
+        assert_eq!(report.diagnostics.len(), 1, "{:?}", report.diagnostics);
+        assert_eq!(
+            report.diagnostics[0].span.start,
+            input.find("F<_>").unwrap()
+        );
+    }
+
+    #[test]
+    fn hkt_trailing_comma_in_declaration_and_usage() {
+        let input = "interface Functor<F<_,>> { map: <A>(fa: F<A,>) => F<A>; }";
+        let (output, _) = rewrite(input);
+        assert_eq!(
+            output,
+            "interface Functor<F> { map: <A>(fa: $<F, A>) => $<F, A>; }"
+        );
+    }
+
+    #[test]
+    fn hkt_nested_usages_both_rewrite() {
+        let input = "interface C<F<_>, G<_>> { run: <A>(x: F<G<A>>) => G<F< A >>; }";
+        let (output, report) = rewrite(input);
+        assert_eq!(
+            output,
+            "interface C<F, G> { run: <A>(x: $<F, $<G, A>>) => $<G, $<F, A>>; }"
+        );
+        assert_eq!(report.count(RewriteKind::HktUsage), 4);
+    }
+
+    #[test]
+    fn hkt_in_non_code_is_untouched() {
+        // Each region also holds a `;` or `}` that would end the scope early.
+        let input = "interface X<F<_>> { a: F<A>; b: \"F<B>; }\"; /* F<C>; } */ c: `F<D>; }`; d: F<E>; }\n// G<_>> F<H>\nconst r = /F<I>;}/;";
+        let (output, report) = rewrite(input);
+        assert_eq!(
+            output,
+            "interface X<F> { a: $<F, A>; b: \"F<B>; }\"; /* F<C>; } */ c: `F<D>; }`; d: $<F, E>; }\n// G<_>> F<H>\nconst r = /F<I>;}/;"
+        );
+        assert!(report.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn hkt_unicode_names_and_identifier_suffixes() {
+        let input = "type T<Éffect<_>, F<_>> = [Éffect<A>, mapF<B>, F<C>, Fé<D>];";
+        let (output, report) = rewrite(input);
+        assert_eq!(
+            output,
+            "type T<Éffect, F> = [$<Éffect, A>, mapF<B>, $<F, C>, Fé<D>];"
+        );
+        assert!(report.diagnostics.is_empty(), "{:?}", report.diagnostics);
+    }
+
+    #[test]
+    fn hkt_placeholder_outside_type_params_warns() {
+        let input = "const a = F<_>;\ninterface Ok<G<_>> { g: G<number>; }";
+        let (output, report) = rewrite(input);
+        assert_eq!(
+            output,
+            "const a = F<_>;\ninterface Ok<G> { g: $<G, number>; }"
+        );
+        assert_eq!(report.diagnostics.len(), 1, "{:?}", report.diagnostics);
+        assert_eq!(report.diagnostics[0].span, 10..14);
+        assert_eq!(report.count(RewriteKind::HktDecl), 1);
+    }
+
+    #[test]
+    fn hkt_custom_apply_name() {
+        let input = "interface Functor<F<_>> { map: <A>(fa: F<A>) => F<A>; }";
+        let mut report = PreprocessReport::default();
+        let mut output = input.to_string();
+        rewrite_hkt(&mut output, "HKT", &mut report);
+        assert_eq!(
+            output,
+            "interface Functor<F> { map: <A>(fa: HKT<F, A>) => HKT<F, A>; }"
+        );
+    }
 }
@@ -0,0 +1,383 @@
+//! Experimental incremental preprocessing for editors and watch mode.
+//!
+//! [`PreprocessState`] runs the passes one top-level statement at a time and
+//! keeps each statement's output. [`retransform`] then reruns them only on
+//! the statement an edit falls in, and falls back to a full run when the edit
+//! spans statements or moves a statement boundary.
+//!
+//! A statement ends at a `;`, or at a `}` followed by a declaration keyword
+//! (`function`, `class`, `const`, ...), outside brackets, strings, comments,
+//! regexes and templates. No rewrite crosses such a boundary: operands stop
+//! at `;` or a keyword, and an HKT scope ends at the `}` or `;` closing its
+//! declaration. So for valid input the stitched output is what
+//! [`preprocess_with_report`] gives for the whole file. The one difference is
+//! a missing operand: only its own statement is left as written.
+
+use std::ops::Range;
+
+use sc_ast::ScSyntax;
+
+use super::util::{self, is_ident_byte, HandleResult, TemplateState};
+use super::{preprocess_with_report, Diagnostic, PreprocessReport, Preprocessed, Rewrite};
+
+/// Replace `range` of the previous source with `text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+/// A preprocessed file, kept per top-level statement so an edit only reruns
+/// the passes on the statement it touches.
+#[derive(Debug, Clone)]
+pub struct PreprocessState {
+    source: String,
+    syntax: ScSyntax,
+    statements: Vec<Statement>,
+    output: Preprocessed,
+}
+
+#[derive(Debug, Clone)]
+struct Statement {
+    /// Byte range in the source.
+    range: Range<usize>,
+    /// The passes' output for this statement alone, with spans relative to
+    /// `range.start`.
+    output: Preprocessed,
+}
+
+impl PreprocessState {
+    /// Preprocess all of `source`.
+    pub fn new(source: &str, syntax: &ScSyntax) -> Self {
+        let bytes = source.as_bytes();
+        let mut statements = Vec::new();
+        let mut start = 0;
+        while start < bytes.len() {
+            let end = statement_end(bytes, start);
+            statements.push(Statement::preprocess(source, start..end, syntax));
+            start = end;
+        }
+        Self::stitch(source.to_string(), syntax.clone(), statements)
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The preprocessed file, with spans into [`source`](Self::source).
+    pub fn output(&self) -> &Preprocessed {
+        &self.output
+    }
+
+    /// Byte ranges of the top-level statements in [`source`](Self::source).
+    pub fn statements(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        self.statements.iter().map(|s| s.range.clone())
+    }
+
+    /// Join the statements' outputs into one, shifting their spans into the
+    /// whole source.
+    fn stitch(source: String, syntax: ScSyntax, statements: Vec<Statement>) -> Self {
+        let mut code = String::with_capacity(source.len());
+        let mut report = PreprocessReport::default();
+        for statement in &statements {
+            let shift = statement.range.start;
+            let part = &statement.output.report;
+            code.push_str(&statement.output.code);
+            report
+                .diagnostics
+                .extend(part.diagnostics.iter().map(|d| Diagnostic {
+                    span: shifted(&d.span, shift),
+                    ..d.clone()
+                }));
+            report
+                .rewrites
+                .extend(part.rewrites.iter().map(|r| Rewrite {
+                    kind: r.kind,
+                    span: shifted(&r.span, shift),
+                    head: shifted(&r.head, shift),
+                    operands: r.operands.iter().map(|o| shifted(o, shift)).collect(),
+                }));
+        }
+        // As if the last statement were rewritten first: each statement's
+        // edits then land at its start in the source, untouched by the
+        // statements before it.
+        for statement in statements.iter().rev() {
+            report
+                .offsets
+                .extend_shifted(&statement.output.report.offsets, statement.range.start);
+        }
+        let output = Preprocessed { code, report };
+        Self {
+            source,
+            syntax,
+            statements,
+            output,
+        }
+    }
+
+    /// The statements of `source` (this state's source with `edit` applied),
+    /// rerunning the passes on the edited statement only. `None` if the edit
+    /// spans statements or moves where they split.
+    fn reuse_statements(&self, source: &str, edit: &TextEdit) -> Option<Vec<Statement>> {
+        let index = self
+            .statements
+            .iter()
+            .position(|s| s.range.start <= edit.range.start && edit.range.end <= s.range.end)?;
+        let old_end = self.statements[index].range.end;
+        let start = self.statements[index].range.start;
+        let end = old_end + edit.text.len() - edit.range.len();
+
+        // Every statement starts with fresh scanner state, so if the edited
+        // one still ends in the same place, the rest split as before. The
+        // one before it can end at a `}` only because this one starts with a
+        // keyword, so check it too.
+        let bytes = source.as_bytes();
+        if statement_end(bytes, start) != end {
+            return None;
+        }
+        if let Some(before) = index.checked_sub(1).map(|i| &self.statements[i].range) {
+            if statement_end(bytes, before.start) != before.end {
+                return None;
+            }
+        }
+
+        let mut statements = self.statements.clone();
+        statements[index] = Statement::preprocess(source, start..end, &self.syntax);
+        for statement in &mut statements[index + 1..] {
+            let range = &statement.range;
+            statement.range = range.start - old_end + end..range.end - old_end + end;
+        }
+        Some(statements)
+    }
+}
+
+impl Statement {
+    fn preprocess(source: &str, range: Range<usize>, syntax: &ScSyntax) -> Self {
+        let output = preprocess_with_report(&source[range.clone()], syntax);
+        Self { range, output }
+    }
+}
+
+/// Apply `edit` to `prev`'s source and preprocess the result.
+///
+/// Only the statement containing the edit is rescanned when that gives the
+/// same result as a full run; otherwise the whole file is.
+///
+/// # Panics
+///
+/// If `edit.range` is out of bounds or not on `char` boundaries of the
+/// previous source.
+pub fn retransform(prev: &PreprocessState, edit: TextEdit) -> PreprocessState {
+    let mut source = prev.source.clone();
+    source.replace_range(edit.range.clone(), &edit.text);
+    match prev.reuse_statements(&source, &edit) {
+        Some(statements) => PreprocessState::stitch(source, prev.syntax.clone(), statements),
+        None => PreprocessState::new(&source, &prev.syntax),
+    }
+}
+
+fn shifted(range: &Range<usize>, shift: usize) -> Range<usize> {
+    range.start + shift..range.end + shift
+}
+
+/// The end of the top-level statement starting at `start`: just past the
+/// `;` or `}` that ends it, or the end of `bytes`.
+fn statement_end(bytes: &[u8], start: usize) -> usize {
+    let mut depth: i32 = 0;
+    let mut template_state = TemplateState::new();
+    let mut i = start;
+
+    while i < bytes.len() {
+        match template_state.handle_char(bytes, i) {
+            HandleResult::Skip(n) => {
+                i += n;
+                continue;
+            }
+            HandleResult::Process => {}
+        }
+
+        // Not a `CodeMask`: that would scan the whole file to check one
+        // statement.
+        if let Some(skip) = util::skip_non_code(bytes, i) {
+            i = skip;
+            continue;
+        }
+
+        let top_level = !template_state.in_template();
+        match bytes[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' => depth -= 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 && top_level && starts_declaration(bytes, i + 1) {
+                    return i + 1;
+                }
+            }
+            b';' if depth == 0 && top_level => return i + 1,
+            _ => {}
+        }
+        i += 1;
+    }
+
+    bytes.len()
+}
+
+/// Whether the next token at or after `pos` (past whitespace and comments)
+/// is a declaration keyword, or there is none.
+fn starts_declaration(bytes: &[u8], pos: usize) -> bool {
+    let pos = util::skip_trivia(bytes, pos);
+    if pos == bytes.len() {
+        return true;
+    }
+    let word = next_word(bytes, pos);
+    // `using` and `await using` only declare when a binding follows:
+    // `using(x)` and `await using` on its own are expressions.
+    if word == b"using" || word == b"await" && next_word(bytes, pos + 5) == b"using" {
+        let after = util::skip_trivia(bytes, pos + word.len());
+        let after = match word {
+            b"await" => util::skip_trivia(bytes, after + 5),
+            _ => after,
+        };
+        return bytes
+            .get(after)
+            .is_some_and(|&b| is_ident_byte(b) && !b.is_ascii_digit());
+    }
+    matches!(
+        word,
+        b"function"
+            | b"class"
+            | b"interface"
+            | b"type"
+            | b"enum"
+            | b"const"
+            | b"let"
+            | b"var"
+            | b"export"
+            | b"import"
+            | b"declare"
+            | b"namespace"
+            | b"abstract"
+    )
+}
+
+/// The identifier at `pos`, past whitespace and comments; empty if there is
+/// none.
+fn next_word(bytes: &[u8], pos: usize) -> &[u8] {
+    let pos = util::skip_trivia(bytes, pos);
+    let len = bytes[pos..]
+        .iter()
+        .take_while(|&&b| is_ident_byte(b))
+        .count();
+    &bytes[pos..pos + len]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(state: &PreprocessState, at: &str, text: &str) -> TextEdit {
+        let start = state.source().find(at).unwrap();
+        TextEdit {
+            range: start..start + at.len(),
+            text: text.to_string(),
+        }
+    }
+
+    fn assert_matches_full_run(state: &PreprocessState) {
+        let full = preprocess_with_report(state.source(), &ScSyntax::default());
+        let output = state.output();
+        assert_eq!(output.code, full.code);
+        assert_eq!(output.report.diagnostics, full.report.diagnostics);
+        for pos in 0..=output.code.len() {
+            assert_eq!(
+                output.report.offsets.to_original(pos),
+                full.report.offsets.to_original(pos),
+                "offset {pos} of {:?}",
+                output.code
+            );
+        }
+    }
+
+    #[test]
+    fn splits_at_semicolons_and_declaration_braces() {
+        let source = "const a = x |> f;\nfunction g() { if (y) { return 1; } }\nexport const b = `;${c};`;\nh({ k: 1 })";
+        let state = PreprocessState::new(source, &ScSyntax::default());
+        let statements: Vec<_> = state.statements().map(|r| &source[r]).collect();
+        assert_eq!(
+            statements,
+            [
+                "const a = x |> f;",
+                "\nfunction g() { if (y) { return 1; } }",
+                "\nexport const b = `;${c};`;",
+                "\nh({ k: 1 })",
+            ]
+        );
+        assert_matches_full_run(&state);
+    }
+
+    #[test]
+    fn using_declarations_start_statements() {
+        let source = "function g() {}\nusing r = a |> f\nif (y) {}\nawait using s = b |> f\nif (z) {}\nusing(c)";
+        let state = PreprocessState::new(source, &ScSyntax::default());
+        let statements: Vec<_> = state.statements().map(|r| &source[r]).collect();
+        assert_eq!(
+            statements,
+            [
+                "function g() {}",
+                "\nusing r = a |> f\nif (y) {}",
+                "\nawait using s = b |> f\nif (z) {}\nusing(c)",
+            ]
+        );
+        assert_matches_full_run(&state);
+    }
+
+    #[test]
+    fn edit_inside_a_statement_reruns_only_that_statement() {
+        let source = "const a = 1 :: [];\nconst b = x |> f;\ninterface I<F<_>> { m: F<A>; }\n";
+        let state = PreprocessState::new(source, &ScSyntax::default());
+
+        let next = retransform(&state, edit(&state, "x |> f", "y |> f |> g"));
+        assert_eq!(next.source(), source.replace("x |> f", "y |> f |> g"));
+        assert_matches_full_run(&next);
+        let rewrites = &next.output().report.rewrites;
+        assert!(rewrites
+            .iter()
+            .any(|r| &next.source()[r.span.clone()] == "F<A>"));
+    }
+
+    #[test]
+    fn edit_that_moves_a_boundary_falls_back_to_a_full_run() {
+        let source = "const a = x;\nconst b = y |> f;\n";
+        let state = PreprocessState::new(source, &ScSyntax::default());
+
+        // Dropping the `;` joins the first two statements.
+        let next = retransform(&state, edit(&state, ";\nconst b", "\nconst b"));
+        assert_eq!(next.statements().count(), state.statements().count() - 1);
+        assert_matches_full_run(&next);
+
+        // Opening a comment swallows everything after it.
+        let next = retransform(&state, edit(&state, "x", "x /*"));
+        assert_eq!(next.statements().count(), 1);
+        assert_eq!(next.output().code, next.source());
+    }
+
+    #[test]
+    fn typing_through_a_file_matches_full_runs() {
+        let source = "const a = xs |> map(f);\nfunction g<F<_>>(x: F<number>) { return x; }\n";
+        let mut state = PreprocessState::new("", &ScSyntax::default());
+        for (i, c) in source.char_indices() {
+            let end = state.source().len();
+            state = retransform(
+                &state,
+                TextEdit {
+                    range: end..end,
+                    text: c.to_string(),
+                },
+            );
+            assert_eq!(state.source(), &source[..i + c.len_utf8()]);
+            if !state.output().report.has_errors() {
+                assert_matches_full_run(&state);
+            }
+        }
+    }
+}
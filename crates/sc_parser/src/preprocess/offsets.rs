@@ -0,0 +1,123 @@
+//! Mapping from positions in the preprocessed text back to the original source.
+//!
+//! Every pass records the edits it makes, in the order it applies them, so a
+//! map built after all passes still points at the file the user wrote.
+
+use std::ops::Range;
+
+/// Bytes `start..end` of the text at the time were replaced by `new_len` bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Edit {
+    start: usize,
+    end: usize,
+    new_len: usize,
+}
+
+/// The edits the preprocessor applied, in application order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OffsetMap {
+    edits: Vec<Edit>,
+}
+
+impl OffsetMap {
+    /// Replace `range` of `text` with `replacement` and record the edit.
+    ///
+    /// `text` must be the text as of the edits recorded so far, i.e. the
+    /// current output of the passes.
+    pub fn splice(&mut self, text: &mut String, range: Range<usize>, replacement: &str) {
+        if range.is_empty() && replacement.is_empty() {
+            return;
+        }
+        self.edits.push(Edit {
+            start: range.start,
+            end: range.end,
+            new_len: replacement.len(),
+        });
+        text.replace_range(range, replacement);
+    }
+
+    /// Append `other`'s edits, moved `shift` bytes later. For joining the
+    /// maps of separately preprocessed pieces of one file.
+    pub(super) fn extend_shifted(&mut self, other: &OffsetMap, shift: usize) {
+        self.edits.extend(other.edits.iter().map(|edit| Edit {
+            start: edit.start + shift,
+            end: edit.end + shift,
+            new_len: edit.new_len,
+        }));
+    }
+
+    /// Whether no pass changed the text.
+    pub fn is_identity(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// Map a byte offset in the preprocessed text to the original source.
+    ///
+    /// Offsets inside inserted text (`__binop__(`, `$<`, ...) map to the start
+    /// of whatever that text replaced.
+    pub fn to_original(&self, pos: usize) -> usize {
+        self.edits.iter().rev().fold(pos, |pos, edit| {
+            if pos < edit.start {
+                pos
+            } else if pos >= edit.start + edit.new_len {
+                pos - edit.new_len + (edit.end - edit.start)
+            } else {
+                edit.start
+            }
+        })
+    }
+
+    /// Map a range in the preprocessed text to the original source.
+    pub fn to_original_range(&self, range: Range<usize>) -> Range<usize> {
+        let start = self.to_original(range.start);
+        start..self.to_original(range.end).max(start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sc_ast::ScSyntax;
+
+    use super::*;
+    use crate::preprocess::preprocess_with_report;
+
+    #[test]
+    fn maps_across_growing_and_shrinking_edits() {
+        let mut text = "ab<_>cd".to_string();
+        let mut map = OffsetMap::default();
+        map.splice(&mut text, 2..5, "");
+        map.splice(&mut text, 0..0, "XYZ");
+        assert_eq!(text, "XYZabcd");
+
+        assert_eq!(map.to_original(0), 0);
+        assert_eq!(map.to_original(3), 0);
+        assert_eq!(map.to_original(5), 5);
+        assert_eq!(map.to_original(6), 6);
+    }
+
+    #[test]
+    fn hkt_usage_maps_back_through_operator_rewrites() {
+        let source = "const r = a |> f;\nfunction g<F<_>>(x: F<number>): F<string> {}\n";
+        let out = preprocess_with_report(source, &ScSyntax::default());
+        assert!(out.code.contains("$<F, number>"));
+
+        let offsets = &out.report.offsets;
+        let usage = out.code.find("$<F, number>").unwrap();
+        assert_eq!(
+            offsets.to_original(usage),
+            source.find("F<number>").unwrap()
+        );
+
+        let result = out.code.find("$<F, string>").unwrap();
+        assert_eq!(
+            offsets.to_original(result),
+            source.find("F<string>").unwrap()
+        );
+
+        // Text the passes kept maps exactly, even after both passes shifted it.
+        let x = out.code.find("(x:").unwrap() + 1;
+        assert_eq!(offsets.to_original(x), source.find("(x:").unwrap() + 1);
+        let f = out.code.find("\", f)").unwrap() + 3;
+        assert_eq!(offsets.to_original(f), source.find("f;").unwrap());
+    }
+}
@@ -1,52 +1,37 @@
-//! Operator rewriting pass for pipeline (`|>`) and cons (`::`).
+//! Operator rewriting pass for pipeline (`|>`), cons (`::`), and bind (`obj::method`).
 //!
 //! Iteratively finds custom operators in expression context and rewrites
-//! them to `__binop__` calls. Operators in strings, comments, and type
-//! contexts are left untouched.
+//! them to `__binop__` calls (or `.bind()` calls for bind). Operators in
+//! strings, comments, and type contexts are left untouched.
 
-use sc_ast::ScSyntax;
+use std::collections::BTreeSet;
+use std::ops::Range;
 
-use super::util::char_offset_to_byte;
+use sc_ast::{ConsLowering, PipeStyle, ScBinaryOp, ScSyntax};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Op {
-    Pipeline,
-    Cons,
-}
-
-impl Op {
-    fn precedence(self) -> u8 {
-        match self {
-            Op::Pipeline => 1,
-            Op::Cons => 5,
-        }
-    }
-
-    fn is_right_assoc(self) -> bool {
-        match self {
-            Op::Pipeline => false,
-            Op::Cons => true,
-        }
-    }
-
-    fn text(self) -> &'static str {
-        match self {
-            Op::Pipeline => "|>",
-            Op::Cons => "::",
-        }
-    }
-}
+use super::report::{Diagnostic, PreprocessReport, Rewrite, RewriteKind};
+use super::util::{
+    boundary_after, boundary_before, is_ident_byte, matching_bracket, skip_trivia, trimmed_range,
+    CodeMask,
+};
 
 #[derive(Debug, Clone)]
 struct OpOccurrence {
-    op: Op,
+    op: ScBinaryOp,
     byte_start: usize,
     byte_end: usize,
 }
 
-/// Rewrite all custom operators in the source.
-pub fn rewrite_operators(source: &str, syntax: &ScSyntax) -> String {
-    let mut result = source.to_string();
+/// Rewrite all custom operators in `text`, in place.
+///
+/// If any operator is missing an operand, an error is reported for each one
+/// and the text is left unchanged: splicing an empty operand would only hand
+/// SWC a `__binop__(, ...)` call it rejects with a far less helpful message.
+pub fn rewrite_operators(text: &mut String, syntax: &ScSyntax, report: &mut PreprocessReport) {
+    if report_missing_operands(text, syntax, report) {
+        return;
+    }
+
     let mut iterations = 0;
     let max_iterations = 1000;
 
@@ -56,7 +41,8 @@ pub fn rewrite_operators(source: &str, syntax: &ScSyntax) -> String {
             break;
         }
 
-        let occurrences = find_operator_occurrences(&result, syntax);
+        let mask = CodeMask::new(text.as_bytes());
+        let occurrences = find_operator_occurrences(text, &mask, syntax, None);
         if occurrences.is_empty() {
             break;
         }
@@ -66,26 +52,363 @@ pub fn rewrite_operators(source: &str, syntax: &ScSyntax) -> String {
         // rightmost for right-assoc.
         let next = select_next_operator(&occurrences);
 
-        let left = find_left_operand(&result, next.byte_start, next.op);
-        let right = find_right_operand(&result, next.byte_end, next.op);
+        let left = find_left_operand(text, &mask, next.byte_start, next.op);
+        let right = find_right_operand(text, &mask, next.byte_end, next.op);
+
+        let left_text = trimmed_range(text, &mask, left..next.byte_start);
+        let right_text = trimmed_range(text, &mask, next.byte_end..right);
+        let offsets = &mut report.offsets;
+        report.rewrites.push(Rewrite {
+            kind: RewriteKind::from(next.op),
+            span: offsets.to_original_range(left_text.start..right_text.end),
+            head: offsets.to_original_range(next.byte_start..next.byte_end),
+            operands: vec![
+                offsets.to_original_range(left_text.clone()),
+                offsets.to_original_range(right_text.clone()),
+            ],
+        });
+        match next.op {
+            ScBinaryOp::Bind => {
+                let replacement = format!(
+                    "{}.bind({})",
+                    &text[right_text.clone()],
+                    &text[left_text.clone()]
+                );
+                offsets.splice(text, left..right, &replacement);
+            }
+            // `a |> _.foo()` is `a.foo()`: the left operand takes the topic's
+            // place, so it's evaluated once, where the `_` was.
+            ScBinaryOp::Pipeline
+                if syntax.pipe_style == PipeStyle::Hack
+                    && is_topic_receiver(text.as_bytes(), right_text.start) =>
+            {
+                let topic_end = right_text.start + 1;
+                if is_member_chain(text.as_bytes(), &mask, left_text.clone()) {
+                    offsets.splice(text, left_text.end..topic_end, "");
+                } else {
+                    offsets.splice(text, left_text.end..topic_end, ")");
+                    offsets.splice(text, left_text.start..left_text.start, "(");
+                }
+            }
+            // `a :: [b, c]` is `[a, b, c]`: the head joins the literal, so a
+            // chain ending in one folds into a single array. Any other tail
+            // is spread.
+            ScBinaryOp::Cons if syntax.cons_lowering == ConsLowering::Array => {
+                match array_literal_elements(text.as_bytes(), &mask, right_text.clone()) {
+                    Some(elements) if trimmed_range(text, &mask, elements.clone()).is_empty() => {
+                        offsets.splice(text, left_text.end..elements.end, "");
+                    }
+                    Some(elements) => offsets.splice(text, left_text.end..elements.start, ", "),
+                    None => {
+                        offsets.splice(text, right_text.end..right_text.end, "]");
+                        offsets.splice(text, left_text.end..right_text.start, ", ...");
+                    }
+                }
+                offsets.splice(text, left..left_text.start, "[");
+            }
+            // Splice around the operands (right to left, so earlier offsets
+            // stay valid) instead of replacing the whole expression: operands
+            // and the operator itself then keep exact positions in the map.
+            // Whatever follows the right operand stays: a comment there may
+            // end the line the statement ends on.
+            // A dedicated helper takes no operator string, so the operator
+            // goes with the gap around it.
+            _ => {
+                offsets.splice(text, right_text.end..right_text.end, ")");
+                if let Some(helper) = syntax.helpers.get(next.op) {
+                    offsets.splice(text, left_text.end..right_text.start, ", ");
+                    offsets.splice(text, left..left_text.start, &format!("{helper}("));
+                } else {
+                    offsets.splice(text, next.byte_end..right_text.start, "\", ");
+                    offsets.splice(text, left_text.end..next.byte_start, ", \"");
+                    offsets.splice(text, left..left_text.start, "__binop__(");
+                }
+            }
+        }
+    }
+}
 
-        let left_text = result[left..next.byte_start].trim();
-        let right_text = result[next.byte_end..right].trim();
-        let replacement = format!(
-            "__binop__({}, \"{}\", {})",
-            left_text,
-            next.op.text(),
-            right_text
-        );
+/// Report each `|>` or `::` in code whose feature is disabled, for
+/// [`strict`](ScSyntax::strict) mode. Left as written, SWC would reject it
+/// without saying the syntax is only switched off.
+pub fn report_disabled_operators(text: &str, syntax: &ScSyntax, report: &mut PreprocessReport) {
+    let all = ScSyntax {
+        pipeline: true,
+        cons: true,
+        ..syntax.clone()
+    };
+    let mask = CodeMask::new(text.as_bytes());
+    for occ in find_operator_occurrences(text, &mask, &all, None) {
+        let enabled = match occ.op {
+            ScBinaryOp::Pipeline => syntax.pipeline,
+            ScBinaryOp::Cons => syntax.cons,
+            ScBinaryOp::Bind => true,
+        };
+        if !enabled {
+            let name = RewriteKind::from(occ.op).name();
+            report.diagnostics.push(Diagnostic::error(
+                format!("{name} syntax found but {name} feature is disabled"),
+                report
+                    .offsets
+                    .to_original_range(occ.byte_start..occ.byte_end),
+            ));
+        }
+    }
+}
 
-        result = format!("{}{}{}", &result[..left], replacement, &result[right..]);
+/// Report each `|>` or `::` left in code after every pass has run, for
+/// [`error_on_residual`](ScSyntax::error_on_residual), whether its feature
+/// is off or the rewrite loop stopped before reaching it.
+pub fn report_residual_operators(text: &str, syntax: &ScSyntax, report: &mut PreprocessReport) {
+    let all = ScSyntax {
+        pipeline: true,
+        cons: true,
+        ..syntax.clone()
+    };
+    let mask = CodeMask::new(text.as_bytes());
+    for occ in find_operator_occurrences(text, &mask, &all, None) {
+        let op = &text[occ.byte_start..occ.byte_end];
+        report.diagnostics.push(Diagnostic::error(
+            format!("`{op}` was left unrewritten"),
+            report
+                .offsets
+                .to_original_range(occ.byte_start..occ.byte_end),
+        ));
+    }
+}
+
+/// Warn about each `|>` whose right operand is a binary expression, for
+/// [`lint_pipe_operands`](ScSyntax::lint_pipe_operands). `a |> b + c` pipes
+/// into `b + c`, since `|>` binds loosest; the usual intent is `(a |> b) + c`.
+pub fn report_binary_pipe_targets(text: &str, syntax: &ScSyntax, report: &mut PreprocessReport) {
+    let mask = CodeMask::new(text.as_bytes());
+    for occ in find_operator_occurrences(text, &mask, syntax, None) {
+        if occ.op != ScBinaryOp::Pipeline {
+            continue;
+        }
+        let right = find_right_operand(text, &mask, occ.byte_end, occ.op);
+        let right_text = trimmed_range(text, &mask, occ.byte_end..right);
+        let Some(op) = top_level_binary_operator(text.as_bytes(), &mask, right_text.clone()) else {
+            continue;
+        };
+        report.diagnostics.push(Diagnostic::warning(
+            format!(
+                "`|>` pipes into all of `{}`; parenthesize it if that's meant, or the pipeline to apply `{}` to its result",
+                &text[right_text.clone()],
+                &text[op]
+            ),
+            report.offsets.to_original_range(right_text),
+        ));
+    }
+}
+
+/// Warn about each `_` in a `|>` right operand that [`PipeStyle::Hack`]
+/// leaves an ordinary identifier: only a leading `_.`, `_?.` or `_[` is the
+/// topic, so `a |> f(_)` calls `f(_)` with `a`, not `f(a)`.
+pub fn report_stray_topics(text: &str, syntax: &ScSyntax, report: &mut PreprocessReport) {
+    let bytes = text.as_bytes();
+    let mask = CodeMask::new(bytes);
+    // A `_` inside nested pipes is in several right operands.
+    let mut stray = BTreeSet::new();
+    for occ in find_operator_occurrences(text, &mask, syntax, None) {
+        if occ.op != ScBinaryOp::Pipeline {
+            continue;
+        }
+        let right = find_right_operand(text, &mask, occ.byte_end, occ.op);
+        let right_text = trimmed_range(text, &mask, occ.byte_end..right);
+        stray.extend(right_text.filter(|&i| {
+            mask.is_code(i)
+                && bytes[i] == b'_'
+                && !bytes.get(i + 1).is_some_and(|&b| is_ident_byte(b))
+                && (i == 0 || !is_ident_byte(bytes[i - 1]))
+                // A property named `_`, as in `x._`.
+                && !text[..i].trim_end().ends_with('.')
+                && !(is_topic_receiver(bytes, i) && text[..i].trim_end().ends_with("|>"))
+        }));
+    }
+    for i in stray {
+        report.diagnostics.push(Diagnostic::warning(
+            "`_` is only the topic at the start of a `|>` right operand, as in `_.m()`; here it's an ordinary identifier",
+            report.offsets.to_original_range(i..i + 1),
+        ));
+    }
+}
+
+/// Warn about each unspaced `::` between two bare names (`Ns::member`), for
+/// [`strict`](ScSyntax::strict) mode. That's how C++ or Rust reach into a
+/// namespace or enum; TypeScript uses `Ns.member`, and as cons it conses
+/// `Ns` onto `member`. A spaced `x :: xs` is left alone.
+pub fn report_namespace_cons(text: &str, syntax: &ScSyntax, report: &mut PreprocessReport) {
+    let bytes = text.as_bytes();
+    let mask = CodeMask::new(bytes);
+    for occ in find_operator_occurrences(text, &mask, syntax, None) {
+        if occ.op != ScBinaryOp::Cons || !is_bind_shape(bytes, occ.byte_start) {
+            continue;
+        }
+        let left = find_left_operand(text, &mask, occ.byte_start, occ.op);
+        let right = find_right_operand(text, &mask, occ.byte_end, occ.op);
+        let left_text = trimmed_range(text, &mask, left..occ.byte_start);
+        let right_text = trimmed_range(text, &mask, occ.byte_end..right);
+        if !is_identifier(&bytes[left_text.clone()]) || !is_identifier(&bytes[right_text.clone()]) {
+            continue;
+        }
+        report.diagnostics.push(Diagnostic::warning(
+            format!(
+                "`{}` conses `{}` onto `{}`; for member access write `{}.{}`",
+                &text[left_text.start..right_text.end],
+                &text[left_text.clone()],
+                &text[right_text.clone()],
+                &text[left_text.clone()],
+                &text[right_text.clone()]
+            ),
+            report
+                .offsets
+                .to_original_range(left_text.start..right_text.end),
+        ));
+    }
+}
+
+fn is_identifier(bytes: &[u8]) -> bool {
+    bytes.first().is_some_and(|&b| is_ident_start(b)) && bytes.iter().all(|&b| is_ident_char(b))
+}
+
+/// Report every operator with an empty left or right operand, and in
+/// [`strict`](ScSyntax::strict) mode every `|>` in a type. Returns whether
+/// any were found.
+///
+/// An operator directly followed by another (`a |>|> f`) or by a stray `:`
+/// (`a ::: b`) is reported once, as an unexpected second operator, rather
+/// than as a missing operand on each side of the gap.
+///
+/// With [`require_operator_spaces`](ScSyntax::require_operator_spaces), an
+/// operator with both operands but no whitespace on one side is reported.
+///
+/// `a |> await f` is an error too. It could only mean calling the awaited
+/// `f` with `a`, which is rarely what was meant; `await (a |> f)` awaits
+/// the call.
+///
+/// Checked on the pass input, before any splicing, so the spans point at the
+/// operators as written. Rewriting never fills in an empty operand, so this
+/// catches everything the loop would otherwise splice as `__binop__(, ...)`.
+fn report_missing_operands(source: &str, syntax: &ScSyntax, report: &mut PreprocessReport) -> bool {
+    let mask = CodeMask::new(source.as_bytes());
+    let mut type_pipes = Vec::new();
+    let occurrences = find_operator_occurrences(
+        source,
+        &mask,
+        syntax,
+        syntax.strict.then_some(&mut type_pipes),
+    );
+
+    // Left alone, SWC would stop at the `>` of what was likely `A | >B` or
+    // a misplaced pipeline, without saying why.
+    let mut found = !type_pipes.is_empty();
+    for pipe in type_pipes {
+        report.diagnostics.push(Diagnostic::error(
+            "`|>` is not a type operator; did you mean `|` then `>`?",
+            report.offsets.to_original_range(pipe),
+        ));
     }
 
-    result
+    let mut unexpected_at = None;
+    for occ in &occurrences {
+        if unexpected_at == Some(occ.byte_start) {
+            continue;
+        }
+        let left = find_left_operand(source, &mask, occ.byte_start, occ.op);
+        let right = find_right_operand(source, &mask, occ.byte_end, occ.op);
+        let left_text = trimmed_range(source, &mask, left..occ.byte_start);
+        let right_text = trimmed_range(source, &mask, occ.byte_end..right);
+        let start = right_text.start;
+        if occ.op == ScBinaryOp::Pipeline
+            && &source[start..scan_word(source.as_bytes(), start)] == "await"
+        {
+            let awaited = trimmed_range(source, &mask, start + "await".len()..right_text.end);
+            // Placeholders for an operand that's missing as well.
+            let operand = |range: Range<usize>, placeholder| match &source[range] {
+                "" => placeholder,
+                text => text,
+            };
+            report.diagnostics.push(Diagnostic::error(
+                format!(
+                    "`await` can't be a pipe target; write `await ({} |> {})` to await the result",
+                    operand(left_text, "x"),
+                    operand(awaited, "f"),
+                ),
+                report
+                    .offsets
+                    .to_original_range(start..start + "await".len()),
+            ));
+            found = true;
+            continue;
+        }
+        let missing = if left_text.is_empty() {
+            "left"
+        } else if right_text.is_empty() {
+            let next = skip_trivia(source.as_bytes(), occ.byte_end);
+            let next_len = match occurrences.iter().find(|o| o.byte_start == next) {
+                Some(o) => o.byte_end - o.byte_start,
+                None if source[next..].starts_with(':') => 1,
+                None => 0,
+            };
+            if next_len > 0 {
+                report.diagnostics.push(Diagnostic::error(
+                    format!(
+                        "unexpected operator `{}` after `{}`",
+                        &source[next..next + next_len],
+                        &source[occ.byte_start..occ.byte_end]
+                    ),
+                    report.offsets.to_original_range(next..next + next_len),
+                ));
+                unexpected_at = Some(next);
+                found = true;
+                continue;
+            }
+            "right"
+        } else if syntax.require_operator_spaces
+            && occ.op != ScBinaryOp::Bind
+            && !is_spaced(source.as_bytes(), occ.byte_start..occ.byte_end)
+        {
+            let op = &source[occ.byte_start..occ.byte_end];
+            report.diagnostics.push(Diagnostic::error(
+                format!("`{op}` needs whitespace on both sides, as in `a {op} b`"),
+                report
+                    .offsets
+                    .to_original_range(occ.byte_start..occ.byte_end),
+            ));
+            found = true;
+            continue;
+        } else {
+            continue;
+        };
+        let span = report
+            .offsets
+            .to_original_range(occ.byte_start..occ.byte_end);
+        report.diagnostics.push(Diagnostic::error(
+            format!(
+                "{} requires a {missing} operand",
+                RewriteKind::from(occ.op).name()
+            ),
+            span,
+        ));
+        found = true;
+    }
+    found
 }
 
-fn find_operator_occurrences(source: &str, syntax: &ScSyntax) -> Vec<OpOccurrence> {
-    let chars: Vec<char> = source.chars().collect();
+/// The operators to rewrite, in source order. `|>` in a type context is
+/// skipped, and added to `type_pipes` if given.
+///
+/// An ambient declaration (`declare global { ... }`, `declare module "m"
+/// { ... }`, `declare const x: T;`) is all type context: there's no value
+/// code in it to rewrite.
+fn find_operator_occurrences(
+    source: &str,
+    mask: &CodeMask,
+    syntax: &ScSyntax,
+    mut type_pipes: Option<&mut Vec<Range<usize>>>,
+) -> Vec<OpOccurrence> {
+    let bytes = source.as_bytes();
     let mut occurrences = Vec::new();
     let mut i = 0;
 
@@ -94,170 +417,202 @@ fn find_operator_occurrences(source: &str, syntax: &ScSyntax) -> Vec<OpOccurrenc
     let mut angle_bracket_depth: i32 = 0;
     let mut in_type_alias = false;
     let mut in_interface = false;
-
-    // Template literal state: stack where each entry is the brace depth in the current interpolation.
-    // Empty = not in a template literal
-    // Entry 0 = in the literal part (between ` and ${ or between } and ${ or `)
-    // Entry > 0 = inside an interpolation with that brace depth
-    let mut template_stack: Vec<i32> = Vec::new();
-
-    while i < chars.len() {
-        // Handle template literal state first
-        if !template_stack.is_empty() {
-            let depth = *template_stack.last().unwrap();
-            if depth == 0 {
-                // In literal part - skip until ${ or closing `
-                if chars[i] == '\\' && i + 1 < chars.len() {
-                    i += 2;
-                    continue;
-                }
-                if chars[i] == '$' && i + 1 < chars.len() && chars[i + 1] == '{' {
-                    // Start of interpolation
-                    *template_stack.last_mut().unwrap() = 1;
-                    i += 2;
-                    continue;
-                }
-                if chars[i] == '`' {
-                    // End of this template literal
-                    template_stack.pop();
-                    i += 1;
-                    continue;
-                }
-                // Skip literal character
-                i += 1;
-                continue;
-            }
-            // In interpolation - track braces but process code normally
-            // Note: `{` increments depth, `}` decrements, nested `` ` `` handled below
-        }
-
-        // Check for template literal start (either not in one, or inside an interpolation)
-        if chars[i] == '`' {
-            template_stack.push(0); // Start in literal part
+    // The nesting of the interface body, once it opens. Its `}` ends the
+    // interface, with or without a `;` after it.
+    let mut interface_body: Option<i32> = None;
+    // Bracket nesting, and the nesting and open `?`s at a `case`/`default`
+    // whose label `:` hasn't been reached yet. That `:` ends the label, not
+    // a type annotation.
+    let mut nesting: i32 = 0;
+    let mut case_label: Option<(i32, i32)> = None;
+    // The nesting at a `declare` whose statement hasn't ended, and of the
+    // block it opened, if any.
+    let mut declare: Option<i32> = None;
+    let mut declare_block: Option<i32> = None;
+    // The nesting of each enclosing class body, and whether a `class`
+    // keyword's body is yet to open. A `name:` in a class body starts a
+    // field's type, not a label.
+    let mut class_bodies: Vec<i32> = Vec::new();
+    let mut class_pending = false;
+
+    while i < bytes.len() {
+        // Strings, comments, regexes and template text.
+        if !mask.is_code(i) {
             i += 1;
             continue;
         }
 
-        // Track braces when inside a template interpolation
-        if !template_stack.is_empty() {
-            let depth = template_stack.last_mut().unwrap();
-            if *depth > 0 {
-                match chars[i] {
-                    '{' => *depth += 1,
-                    '}' => {
-                        *depth -= 1;
-                        if *depth == 0 {
-                            // End of interpolation, back to literal part
-                            i += 1;
-                            continue;
-                        }
-                    }
-                    _ => {}
-                }
-            }
-        }
-
-        // Skip strings, comments (but NOT template literals - handled above)
-        if let Some(skip) = skip_non_code(&chars, i) {
-            i = skip;
-            continue;
-        }
-
-        let byte_pos = char_offset_to_byte(&chars, i);
-
         // Track keywords
-        if is_word_start(&chars, i) {
-            let word_end = scan_word(&chars, i);
-            let word: String = chars[i..word_end].iter().collect();
-
-            match word.as_str() {
-                "type" => {
+        if is_word_start(bytes, i) {
+            let word_end = scan_word(bytes, i);
+            match &bytes[i..word_end] {
+                // `x.case` and `x.default` are property names.
+                b"case" if i == 0 || bytes[i - 1] != b'.' => case_label = Some((nesting, 0)),
+                b"default"
+                    if (i == 0 || bytes[i - 1] != b'.')
+                        && bytes.get(skip_trivia(bytes, word_end)) == Some(&b':') =>
+                {
+                    case_label = Some((nesting, 0));
+                }
+                b"type" => {
                     in_type_alias = true;
                     type_annotation_depth = 0;
                 }
-                "interface" => {
+                b"interface" => {
                     in_interface = true;
                     type_annotation_depth = 0;
                 }
+                b"declare"
+                    if declare_block.is_none() && is_declare_modifier(bytes, i, word_end) =>
+                {
+                    declare = Some(nesting);
+                }
+                b"class" if i == 0 || bytes[i - 1] != b'.' => class_pending = true,
                 _ => {}
             }
             i = word_end;
             continue;
         }
 
-        match chars[i] {
-            ';' => {
+        match bytes[i] {
+            b'{' if declare.is_some() && declare_block.is_none() => {
+                declare_block = Some(nesting);
+                nesting += 1;
+            }
+            b'}' if declare_block == Some(nesting - 1) => {
+                nesting -= 1;
+                declare_block = None;
+                declare = None;
+            }
+            b'{' if in_interface && interface_body.is_none() && angle_bracket_depth == 0 => {
+                interface_body = Some(nesting);
+                nesting += 1;
+            }
+            b'}' if interface_body == Some(nesting - 1) => {
+                nesting -= 1;
+                interface_body = None;
+                in_interface = false;
+            }
+            // Without a `;`, the line ends the declaration, unless its block
+            // opens on the next line.
+            b'\n'
+                if declare_block.is_none()
+                    && declare == Some(nesting)
+                    && bytes.get(skip_trivia(bytes, i)) != Some(&b'{') =>
+            {
+                declare = None;
+            }
+            b'{' if class_pending && angle_bracket_depth == 0 => {
+                class_pending = false;
+                class_bodies.push(nesting);
+                nesting += 1;
+            }
+            b'}' if class_bodies.last() == Some(&(nesting - 1)) => {
+                class_bodies.pop();
+                nesting -= 1;
+            }
+            b'(' | b'[' | b'{' => nesting += 1,
+            b')' | b']' | b'}' => nesting -= 1,
+            b'?' if is_conditional_question(bytes, i) => {
+                if let Some((_, ternaries)) = case_label.as_mut() {
+                    *ternaries += 1;
+                }
+            }
+            _ => {}
+        }
+
+        match bytes[i] {
+            b';' => {
                 type_annotation_depth = 0;
                 in_type_alias = false;
                 in_interface = false;
+                if declare_block.is_none() {
+                    declare = None;
+                }
             }
-            ':' => {
+            b':' => {
                 // Could be `::`  or type annotation `:`
-                if i + 1 < chars.len() && chars[i + 1] == ':' {
+                if i + 1 < bytes.len() && bytes[i + 1] == b':' {
                     // Potential `::` operator
-                    if syntax.cons
-                        && !in_type_context(
-                            type_annotation_depth,
-                            angle_bracket_depth,
-                            in_type_alias,
-                            in_interface,
-                        )
-                    {
-                        let bs = byte_pos;
-                        let be = char_offset_to_byte(&chars, i + 2);
+                    let op = if syntax.bind && is_bind_shape(bytes, i) {
+                        Some(ScBinaryOp::Bind)
+                    } else if syntax.cons {
+                        Some(ScBinaryOp::Cons)
+                    } else {
+                        None
+                    };
+                    if let Some(op) = op.filter(|_| {
+                        declare.is_none()
+                            && !in_type_context(
+                                type_annotation_depth,
+                                angle_bracket_depth,
+                                in_type_alias,
+                                in_interface,
+                            )
+                    }) {
                         occurrences.push(OpOccurrence {
-                            op: Op::Cons,
-                            byte_start: bs,
-                            byte_end: be,
+                            op,
+                            byte_start: i,
+                            byte_end: i + 2,
                         });
                     }
                     i += 2;
                     continue;
+                } else if let Some((label_nesting, ternaries)) = case_label {
+                    if nesting == label_nesting && ternaries == 0 {
+                        case_label = None;
+                    } else if nesting == label_nesting {
+                        // The `:` of a `?:` in the label.
+                        case_label = Some((label_nesting, ternaries - 1));
+                    } else {
+                        type_annotation_depth += 1;
+                    }
+                } else if class_bodies.last() != Some(&(nesting - 1)) && is_label_colon(bytes, i) {
+                    // `label: x |> f;` labels a statement.
                 } else {
                     // Type annotation colon - increment depth
                     type_annotation_depth += 1;
                 }
             }
-            '|' => {
-                if i + 1 < chars.len() && chars[i + 1] == '>' {
-                    // Pipeline operator
-                    if syntax.pipeline
-                        && !in_type_context(
-                            type_annotation_depth,
-                            angle_bracket_depth,
-                            in_type_alias,
-                            in_interface,
-                        )
-                    {
-                        let bs = byte_pos;
-                        let be = char_offset_to_byte(&chars, i + 2);
-                        occurrences.push(OpOccurrence {
-                            op: Op::Pipeline,
-                            byte_start: bs,
-                            byte_end: be,
-                        });
-                    }
-                    i += 2;
-                    continue;
+            b'|' if i + 1 < bytes.len() && bytes[i + 1] == b'>' => {
+                // Pipeline operator
+                let in_type = declare.is_some()
+                    || in_type_context(
+                        type_annotation_depth,
+                        angle_bracket_depth,
+                        in_type_alias,
+                        in_interface,
+                    );
+                if !syntax.pipeline {
+                    // Neither rewritten nor reported.
+                } else if !in_type {
+                    occurrences.push(OpOccurrence {
+                        op: ScBinaryOp::Pipeline,
+                        byte_start: i,
+                        byte_end: i + 2,
+                    });
+                } else if let Some(type_pipes) = type_pipes.as_deref_mut() {
+                    type_pipes.push(i..i + 2);
                 }
+                i += 2;
+                continue;
             }
-            '<' => {
-                // Could be generic type parameter
-                if i > 0 && (chars[i - 1].is_alphanumeric() || chars[i - 1] == '_') {
-                    angle_bracket_depth += 1;
-                }
+            // Could be generic type parameter
+            b'<' if i > 0
+                && is_ident_char(bytes[i - 1])
+                && bytes[i - 1] != b'$'
+                && type_arguments_end(bytes, mask, i).is_some() =>
+            {
+                angle_bracket_depth += 1;
             }
-            '>' => {
-                if angle_bracket_depth > 0 {
-                    angle_bracket_depth -= 1;
-                }
+            // The `>` of an `=>` in a function type doesn't close the list.
+            b'>' if angle_bracket_depth > 0 && bytes[i - 1] != b'=' => {
+                angle_bracket_depth -= 1;
             }
-            '=' | ')' | '}' | ',' => {
-                if !in_type_alias {
-                    type_annotation_depth = type_annotation_depth.saturating_sub(1);
-                }
+            b'=' | b')' | b'}' | b',' if !in_type_alias => {
+                type_annotation_depth = type_annotation_depth.saturating_sub(1);
             }
-            '{' => {
+            b'{' => {
                 if in_interface {
                     // Don't reset inside interface body
                 } else {
@@ -273,6 +628,139 @@ fn find_operator_occurrences(source: &str, syntax: &ScSyntax) -> Vec<OpOccurrenc
     occurrences
 }
 
+/// Whether the word `declare` at `start..end` starts an ambient declaration:
+/// a declaration keyword or `global` follows on the same line. Otherwise
+/// it's an identifier (`declare(x)`, `obj.declare`, `declare in o`).
+fn is_declare_modifier(bytes: &[u8], start: usize, end: usize) -> bool {
+    if start > 0 && bytes[start - 1] == b'.' {
+        return false;
+    }
+    let next = end
+        + bytes[end..]
+            .iter()
+            .take_while(|&&b| b == b' ' || b == b'\t')
+            .count();
+    if !bytes.get(next).is_some_and(|&b| is_ident_start(b)) {
+        return false;
+    }
+    !is_operator_keyword(&bytes[next..scan_word(bytes, next)])
+}
+
+/// Whether the `?` at `i` starts the `?:` conditional, rather than being
+/// part of `?.` or `??`.
+fn is_conditional_question(bytes: &[u8], i: usize) -> bool {
+    let next = bytes.get(i + 1).copied();
+    let optional_chain = next == Some(b'.') && !bytes.get(i + 2).is_some_and(u8::is_ascii_digit);
+    next != Some(b'?') && (i == 0 || bytes[i - 1] != b'?') && !optional_chain
+}
+
+/// An unspaced `::` with an identifier on both sides (`obj::method`).
+///
+/// The left word must start like an identifier, so `1::rest` stays cons.
+fn is_bind_shape(bytes: &[u8], colon: usize) -> bool {
+    let mut word_start = colon;
+    while word_start > 0 && is_ident_char(bytes[word_start - 1]) {
+        word_start -= 1;
+    }
+
+    word_start < colon
+        && is_ident_start(bytes[word_start])
+        && bytes.get(colon + 2).is_some_and(|&c| is_ident_start(c))
+}
+
+/// Non-ASCII bytes count as identifier bytes: outside strings and comments
+/// they can only be part of a Unicode identifier.
+fn is_ident_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_' || b == b'$' || !b.is_ascii()
+}
+
+fn is_ident_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || !b.is_ascii()
+}
+
+/// The `>` closing the `<` at `open`, if, after a name, it could start type
+/// arguments (`f<T>(x)`, `new Map<K, V>()`) rather than compare (`a<b`):
+/// there's a `>` before anything a type can't hold. A `;` or closing
+/// bracket outside the list, or a value-only operator (`|>`, `&&`, `||`,
+/// `==`, `+`, ...), means it's a comparison, so the rest of the expression
+/// is still code. So does an operand right after the `>`, as TypeScript
+/// reads it: `a < b, c > d` compares twice.
+fn type_arguments_end(bytes: &[u8], mask: &CodeMask, open: usize) -> Option<usize> {
+    let mut angles = 0;
+    let mut brackets = 0;
+    let mut i = open;
+    while i < bytes.len() {
+        if !mask.is_code(i) {
+            i += 1;
+            continue;
+        }
+        let next = bytes.get(i + 1).copied();
+        match bytes[i] {
+            b'<' => angles += 1,
+            b'=' if next == Some(b'>') => i += 1,
+            b'>' => {
+                angles -= 1;
+                if angles == 0 {
+                    let after = bytes.get(skip_trivia(bytes, i + 1)).copied();
+                    let operand =
+                        after.is_some_and(|b| is_ident_char(b) || matches!(b, b'"' | b'\'' | b'`'));
+                    return (!operand).then_some(i);
+                }
+            }
+            b'(' | b'[' | b'{' => brackets += 1,
+            b')' | b']' | b'}' => {
+                if brackets == 0 {
+                    return None;
+                }
+                brackets -= 1;
+            }
+            b';' if brackets == 0 => return None,
+            b'|' if matches!(next, Some(b'>' | b'|')) => return None,
+            b'&' if next == Some(b'&') => return None,
+            b'=' | b'!' if next == Some(b'=') => return None,
+            b'+' | b'*' | b'/' | b'%' => return None,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// The `<` of the type arguments closed by the `>` at `close`, as in
+/// `new Map<K, V>()`, where the `,` doesn't end an operand.
+fn type_arguments_start(bytes: &[u8], mask: &CodeMask, close: usize) -> Option<usize> {
+    let mut angles = 0;
+    let mut brackets = 0;
+    let mut i = close + 1;
+    let open = loop {
+        i = i.checked_sub(1)?;
+        if !mask.is_code(i) {
+            continue;
+        }
+        match bytes[i] {
+            b'>' if i > 0 && bytes[i - 1] == b'=' => i -= 1,
+            b'>' => angles += 1,
+            b'<' => {
+                angles -= 1;
+                if angles == 0 {
+                    break i;
+                }
+            }
+            b')' | b']' | b'}' => brackets += 1,
+            b'(' | b'[' | b'{' => {
+                if brackets == 0 {
+                    return None;
+                }
+                brackets -= 1;
+            }
+            b';' if brackets == 0 => return None,
+            _ => {}
+        }
+    };
+    let named = open > 0 && is_ident_char(bytes[open - 1]);
+    (named && type_arguments_end(bytes, mask, open) == Some(close)).then_some(open)
+}
+
 fn in_type_context(
     type_depth: i32,
     angle_depth: i32,
@@ -300,57 +788,102 @@ fn select_next_operator(occurrences: &[OpOccurrence]) -> &OpOccurrence {
         .expect("select_next_operator called with empty occurrences")
 }
 
-fn find_left_operand(source: &str, op_start: usize, op: Op) -> usize {
-    let chars: Vec<char> = source[..op_start].chars().collect();
-    let mut i = chars.len();
-    let mut depth: i32 = 0;
-
-    // Skip trailing whitespace before the operator
-    while i > 0 && chars[i - 1].is_whitespace() {
-        i -= 1;
+fn find_left_operand(source: &str, mask: &CodeMask, op_start: usize, op: ScBinaryOp) -> usize {
+    if op == ScBinaryOp::Bind {
+        return find_bind_left_operand(source, op_start);
     }
 
-    let _content_end = i;
-
+    // Brackets are skipped a group at a time, so everything below is at
+    // the operand's own level.
+    let bytes = &source.as_bytes()[..op_start];
+    let mut i = boundary_before(bytes, op_start);
     while i > 0 {
         i -= 1;
 
-        match chars[i] {
-            ')' | ']' | '}' => depth += 1,
-            '(' | '[' | '{' => {
-                if depth == 0 {
-                    return boundary_after(source, &chars, i + 1);
+        // A `;`, `,` or `(` inside a string or comment isn't a boundary.
+        if !mask.is_code(i) {
+            continue;
+        }
+
+        // Whole words: some keywords end the operand even though they aren't punctuation.
+        if is_ident_char(bytes[i]) && bytes.get(i + 1).is_none_or(|&c| !is_ident_char(c)) {
+            let mut word_start = i;
+            while word_start > 0 && is_ident_char(bytes[word_start - 1]) {
+                word_start -= 1;
+            }
+            if is_left_boundary_keyword(bytes, mask, word_start, i + 1) {
+                let after = boundary_after(bytes, i + 1);
+                // `yield* xs |> f` delegates to the piped value.
+                if &bytes[word_start..i + 1] == b"yield" && bytes.get(after) == Some(&b'*') {
+                    return boundary_after(bytes, after + 1);
                 }
-                depth -= 1;
+                return after;
+            }
+            i = word_start;
+            continue;
+        }
+
+        // A JSX element is one operand, whatever its attributes (`a="x"`)
+        // and text (`a, b`) hold.
+        // So are type arguments, commas and all.
+        if bytes[i] == b'>' {
+            if let Some(start) =
+                jsx_element_start(bytes, mask, i).or_else(|| type_arguments_start(bytes, mask, i))
+            {
+                i = start;
+                continue;
             }
-            ';' | ',' if depth == 0 => {
-                return boundary_after(source, &chars, i + 1);
+        }
+
+        match bytes[i] {
+            // An unbalanced group runs to the start of the text. The body of
+            // `if (a) x |> f` starts after the header.
+            b')' | b']' | b'}' => match matching_bracket(bytes, mask, i) {
+                Some(open) if bytes[i] == b')' && opens_statement_header(bytes, open) => {
+                    return boundary_after(bytes, i + 1);
+                }
+                Some(open) => i = open,
+                None => return 0,
+            },
+            b'(' | b'[' | b'{' | b';' | b',' => return boundary_after(bytes, i + 1),
+            b'\n' if is_statement_break(source, mask, i) => {
+                return boundary_after(bytes, i + 1);
             }
-            '=' if depth == 0 => {
-                // Don't match => (arrow)
-                if i + 1 < chars.len() && chars[i + 1] == '>' {
-                    continue;
+            b'=' => {
+                // An arrow body is an assignment expression, so `=>` bounds
+                // it like `=` does: `() => a |> f` pipes `a`, not the arrow.
+                if i + 1 < bytes.len() && bytes[i + 1] == b'>' {
+                    return boundary_after(bytes, i + 2);
                 }
-                // Don't match == or ===
-                if i > 0 && chars[i - 1] == '=' {
+                // Assignment, compound or not, binds looser than `|>` and
+                // `::`: `x += y |> f` pipes `y`. Comparisons (`==`, `>=`,
+                // ...) bind tighter, so they stay inside the operand.
+                let start = i - bytes[..i]
+                    .iter()
+                    .rev()
+                    .take_while(|&&b| is_operator_byte(b))
+                    .count();
+                if is_comparison(&bytes[start..=i]) {
+                    i = start;
                     continue;
                 }
-                return boundary_after(source, &chars, i + 1);
+                return boundary_after(bytes, i + 1);
             }
-            '>' if depth == 0 && i > 0 && chars[i - 1] == '|' => {
-                if Op::Pipeline.precedence() <= op.precedence() {
-                    return boundary_after(source, &chars, i + 1);
-                }
+            b'>' if i > 0
+                && bytes[i - 1] == b'|'
+                && ScBinaryOp::Pipeline.precedence() <= op.precedence() =>
+            {
+                return boundary_after(bytes, i + 1);
             }
-            ':' if depth == 0 => {
-                if i > 0 && chars[i - 1] == ':' {
-                    if Op::Cons.precedence() <= op.precedence() {
-                        return boundary_after(source, &chars, i + 1);
+            b':' => {
+                if i > 0 && bytes[i - 1] == b':' {
+                    if ScBinaryOp::Cons.precedence() <= op.precedence() {
+                        return boundary_after(bytes, i + 1);
                     }
-                    // Skip the first ':' of '::'
+                    // Skip the first b':' of '::'
                     i -= 1;
                 } else {
-                    return boundary_after(source, &chars, i + 1);
+                    return boundary_after(bytes, i + 1);
                 }
             }
             _ => {}
@@ -360,252 +893,517 @@ fn find_left_operand(source: &str, op_start: usize, op: Op) -> usize {
     0
 }
 
-/// Return byte offset, skipping leading whitespace after a boundary token.
-fn boundary_after(_source: &str, chars: &[char], pos: usize) -> usize {
-    let mut p = pos;
-    while p < chars.len() && chars[p].is_whitespace() {
-        p += 1;
+/// Whether the `:` at `colon` ends a statement label: a name at the start
+/// of a statement, or after another label, before it.
+fn is_label_colon(bytes: &[u8], colon: usize) -> bool {
+    let end = boundary_before(bytes, colon);
+    let start = end
+        - bytes[..end]
+            .iter()
+            .rev()
+            .take_while(|&&b| is_ident_char(b))
+            .count();
+    if start == end || !is_ident_start(bytes[start]) {
+        return false;
     }
-    char_offset_to_byte(chars, p)
+    let before = boundary_before(bytes, start);
+    before == 0 || matches!(bytes[before - 1], b';' | b'{' | b'}' | b':')
 }
 
-fn find_right_operand(source: &str, op_end: usize, op: Op) -> usize {
-    let rest = &source[op_end..];
-    let chars: Vec<char> = rest.chars().collect();
-    let mut i = 0;
-    let mut depth: i32 = 0;
-
-    while i < chars.len() {
-        // Skip whitespace at the boundary
-        if depth == 0 && chars[i].is_whitespace() && i == 0 {
-            i += 1;
-            continue;
-        }
-
-        match chars[i] {
-            '(' | '[' | '{' => depth += 1,
-            ')' | ']' | '}' => {
-                if depth == 0 {
-                    return op_end + char_offset_to_byte(&chars, i);
-                }
-                depth -= 1;
-            }
-            ';' if depth == 0 => {
-                return op_end + char_offset_to_byte(&chars, i);
-            }
-            ',' if depth == 0 => {
-                return op_end + char_offset_to_byte(&chars, i);
-            }
-            '|' if depth == 0 && i + 1 < chars.len() && chars[i + 1] == '>' => {
-                if op.is_right_assoc() {
-                    if Op::Pipeline.precedence() < op.precedence() {
-                        return op_end + char_offset_to_byte(&chars, i);
-                    }
-                } else if Op::Pipeline.precedence() <= op.precedence() {
-                    return op_end + char_offset_to_byte(&chars, i);
-                }
-            }
-            ':' if depth == 0 && i + 1 < chars.len() && chars[i + 1] == ':' => {
-                if op.is_right_assoc() {
-                    if Op::Cons.precedence() < op.precedence() {
-                        return op_end + char_offset_to_byte(&chars, i);
-                    }
-                } else if Op::Cons.precedence() <= op.precedence() {
-                    return op_end + char_offset_to_byte(&chars, i);
-                }
-                // Skip the second `:` since we've checked `::`.
-                i += 1;
-            }
-            _ => {}
-        }
-        i += 1;
+/// Whether the `(` at `open` starts the header of an `if`, `while`, `for`
+/// or `with` statement, so a statement follows its `)`.
+fn opens_statement_header(bytes: &[u8], open: usize) -> bool {
+    let end = boundary_before(bytes, open);
+    let start = end
+        - bytes[..end]
+            .iter()
+            .rev()
+            .take_while(|&&b| is_ident_char(b))
+            .count();
+    // `o.if(a)` calls a method.
+    if start > 0 && bytes[start - 1] == b'.' {
+        return false;
     }
+    matches!(&bytes[start..end], b"if" | b"while" | b"with") || preceding_word_is_for(bytes, open)
+}
 
-    op_end + char_offset_to_byte(&chars, chars.len())
+fn is_operator_byte(b: u8) -> bool {
+    matches!(
+        b,
+        b'=' | b'!' | b'<' | b'>' | b'+' | b'-' | b'*' | b'/' | b'%' | b'&' | b'|' | b'^' | b'?'
+    )
 }
 
-fn skip_non_code(chars: &[char], i: usize) -> Option<usize> {
-    if i >= chars.len() {
-        return Some(chars.len());
-    }
+/// Whether an operator ending in `=` is a comparison rather than an assignment.
+fn is_comparison(op: &[u8]) -> bool {
+    matches!(op, b"==" | b"===" | b"!=" | b"!==" | b"<=" | b">=")
+}
 
-    // Single-line comment
-    if chars[i] == '/' && i + 1 < chars.len() && chars[i + 1] == '/' {
-        let mut j = i + 2;
-        while j < chars.len() && chars[j] != '\n' {
-            j += 1;
-        }
-        return Some(j + 1);
+/// Whether the word `bytes[start..end]` is a keyword that bounds a left operand.
+///
+/// Keywords that introduce an expression or statement (`return`, `throw`,
+/// `yield`, `export default`, `else`, `do`, `case`) always do. `of`/`in` only count when they
+/// separate a `for (... of/in ...)` header; elsewhere `in` is a relational
+/// operator that binds tighter than `|>`.
+fn is_left_boundary_keyword(bytes: &[u8], mask: &CodeMask, start: usize, end: usize) -> bool {
+    // `xs.of`, `obj.in` are property names, not keywords.
+    if start > 0 && bytes[start - 1] == b'.' {
+        return false;
     }
-
-    // Multi-line comment
-    if chars[i] == '/' && i + 1 < chars.len() && chars[i + 1] == '*' {
-        let mut j = i + 2;
-        while j + 1 < chars.len() {
-            if chars[j] == '*' && chars[j + 1] == '/' {
-                return Some(j + 2);
-            }
-            j += 1;
-        }
-        return Some(chars.len());
+    match &bytes[start..end] {
+        b"return" | b"throw" | b"yield" | b"default" | b"else" | b"do" | b"case" => true,
+        b"of" | b"in" => is_for_header_separator(bytes, mask, start),
+        _ => false,
     }
+}
 
-    // Regex literal - must check before treating `/` as division
-    if chars[i] == '/' && is_regex_context(chars, i) {
-        if let Some(end) = scan_regex_literal(chars, i) {
-            return Some(end);
+/// Whether position `pos` sits directly inside the parentheses of a `for` header.
+fn is_for_header_separator(bytes: &[u8], mask: &CodeMask, pos: usize) -> bool {
+    let mut depth: i32 = 0;
+    let mut i = pos;
+    while i > 0 {
+        i -= 1;
+        if !mask.is_code(i) {
+            continue;
+        }
+        match bytes[i] {
+            b')' | b']' | b'}' => depth += 1,
+            b'(' if depth == 0 => return preceding_word_is_for(bytes, i),
+            b'[' | b'{' if depth == 0 => return false,
+            b'(' | b'[' | b'{' => depth -= 1,
+            b';' if depth == 0 => return false,
+            _ => {}
         }
     }
+    false
+}
 
-    // String literals (NOT template literals - those are handled by template_stack in the main loop)
-    if chars[i] == '"' || chars[i] == '\'' {
-        let quote = chars[i];
-        let mut j = i + 1;
-        while j < chars.len() && chars[j] != quote {
-            if chars[j] == '\\' {
-                j += 1;
-            }
-            j += 1;
+/// Whether the word before the `(` at `paren` is `for` (allowing `for await`).
+fn preceding_word_is_for(bytes: &[u8], paren: usize) -> bool {
+    let mut words = 0;
+    let mut end = paren;
+    // Look at most two words back: `for` or `for await`.
+    while words < 2 {
+        while end > 0 && bytes[end - 1].is_ascii_whitespace() {
+            end -= 1;
+        }
+        let mut start = end;
+        while start > 0 && is_ident_char(bytes[start - 1]) {
+            start -= 1;
+        }
+        if start == end {
+            return false;
+        }
+        match &bytes[start..end] {
+            b"for" => return true,
+            b"await" => {}
+            _ => return false,
         }
-        return Some(if j < chars.len() { j + 1 } else { j });
+        end = start;
+        words += 1;
     }
-
-    None
+    false
 }
 
-/// Determine if `/` at position `i` starts a regex literal based on preceding context.
-/// A `/` starts a regex when it appears where an expression is expected (not after an operand).
-fn is_regex_context(chars: &[char], i: usize) -> bool {
-    // Find the last non-whitespace character/token before position i
-    let mut j = i;
-    while j > 0 && chars[j - 1].is_whitespace() {
-        j -= 1;
+/// Whether the line break at `newline` ends a statement written without a
+/// `;`, so no operand reaches across it.
+///
+/// Like automatic semicolon insertion, a break only ends a statement when the
+/// code before it can end an expression and the code after it can't continue
+/// one: `a\nrender(a)` splits, but `a\n  |> f`, `a\n  .b` and `a =\n  b`
+/// don't.
+fn is_statement_break(source: &str, mask: &CodeMask, newline: usize) -> bool {
+    let bytes = source.as_bytes();
+    let before = trimmed_range(source, mask, 0..newline).end;
+    let after = skip_trivia(bytes, newline);
+    if before == 0 || after == bytes.len() {
+        return false;
     }
 
-    if j == 0 {
-        // Start of input - regex context
-        return true;
+    // A string, regex or template ends an expression too.
+    let last = bytes[before - 1];
+    let ends_expression = if !mask.is_code(before - 1) || last == b'`' {
+        true
+    } else if is_ident_char(last) {
+        let start = before
+            - bytes[..before]
+                .iter()
+                .rev()
+                .take_while(|&&b| is_ident_char(b))
+                .count();
+        let dotted = start > 0 && bytes[start - 1] == b'.';
+        dotted || !is_operator_keyword(&bytes[start..before])
+    } else {
+        matches!(last, b')' | b']' | b'}')
+    };
+    if !ends_expression {
+        return false;
     }
 
-    let prev = chars[j - 1];
-
-    // After these characters, `/` starts a regex (expression expected)
-    if matches!(
-        prev,
-        '(' | '[' | '{' | ',' | ';' | ':' | '=' | '!' | '&' | '|' | '?' | '+' | '-' | '*' | '%'
-            | '^' | '~' | '<' | '>'
-    ) {
-        return true;
+    let next = bytes[after];
+    if is_ident_start(next) {
+        let word = &bytes[after..scan_word(bytes, after)];
+        // TypeScript doesn't continue an expression with `as`/`satisfies`
+        // after a line break either.
+        !matches!(word, b"instanceof" | b"in")
+    } else {
+        next.is_ascii_digit() || matches!(next, b'"' | b'\'' | b'!' | b'~')
     }
+}
 
-    // Check for keywords that precede expressions
-    // We need to look back to see if we're after a keyword like `return`, `case`, etc.
-    if prev.is_alphabetic() || prev == '_' || prev == '$' {
-        // Scan backwards to get the full word
-        let mut word_start = j - 1;
-        while word_start > 0
-            && (chars[word_start - 1].is_alphanumeric()
-                || chars[word_start - 1] == '_'
-                || chars[word_start - 1] == '$')
-        {
-            word_start -= 1;
-        }
-        let word: String = chars[word_start..j].iter().collect();
-
-        // Keywords after which `/` starts a regex
-        return matches!(
-            word.as_str(),
-            "return"
-                | "case"
-                | "throw"
-                | "in"
-                | "of"
-                | "typeof"
-                | "void"
-                | "delete"
-                | "new"
-                | "else"
-                | "do"
-                | "instanceof"
-                | "yield"
-                | "await"
-        );
-    }
+/// Keywords that expect an operand after them, so a line can't end an
+/// expression with one.
+fn is_operator_keyword(word: &[u8]) -> bool {
+    matches!(
+        word,
+        b"typeof"
+            | b"void"
+            | b"delete"
+            | b"await"
+            | b"new"
+            | b"in"
+            | b"of"
+            | b"instanceof"
+            | b"as"
+            | b"satisfies"
+            | b"keyof"
+            | b"extends"
+    )
+}
 
-    // After `)`, `]`, `}`, identifier, number, string - it's division
-    // (prev would be alphanumeric or one of these closing brackets)
-    false
+/// Which kind of JSX tag a `>` closes: `<Foo>`, `</Foo>` or `<Foo />`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum JsxTag {
+    Opening,
+    Closing,
+    SelfClosing,
 }
 
-/// Scan a regex literal starting at position `i`, returning the position after the closing `/` and flags.
-/// Returns None if this doesn't look like a valid regex literal.
-fn scan_regex_literal(chars: &[char], i: usize) -> Option<usize> {
-    if i >= chars.len() || chars[i] != '/' {
-        return None;
+/// The `<` of the JSX element ending with the `>` at `close`: a
+/// self-closing `<Foo />`, or an element ending in a closing tag `</Foo>`.
+/// `None` for any other `>`.
+fn jsx_element_start(bytes: &[u8], mask: &CodeMask, close: usize) -> Option<usize> {
+    let (open, tag) = jsx_tag(bytes, mask, close)?;
+    match tag {
+        JsxTag::SelfClosing => return Some(open),
+        JsxTag::Closing => {}
+        JsxTag::Opening => return None,
     }
 
-    let mut j = i + 1;
-
-    // Scan the regex body - look for closing `/` (not escaped, not in character class)
-    let mut in_char_class = false;
-
-    while j < chars.len() {
-        let c = chars[j];
-
-        // Regex literals cannot span unescaped newlines
-        if c == '\n' || c == '\r' {
-            return None;
-        }
-
-        // Handle escape sequences
-        if c == '\\' && j + 1 < chars.len() {
-            j += 2;
-            continue;
-        }
-
-        // Handle character classes
-        if c == '[' && !in_char_class {
-            in_char_class = true;
-            j += 1;
+    // Back over the children to the matching opening tag. Text between
+    // them is skipped, and `{...}` children as a group.
+    let mut depth = 1;
+    let mut i = open;
+    while i > 0 {
+        i -= 1;
+        if !mask.is_code(i) {
             continue;
         }
-        if c == ']' && in_char_class {
-            in_char_class = false;
-            j += 1;
-            continue;
+        match bytes[i] {
+            b'}' => i = matching_bracket(bytes, mask, i)?,
+            b'>' => {
+                let (open, tag) = jsx_tag(bytes, mask, i)?;
+                match tag {
+                    JsxTag::Closing => depth += 1,
+                    JsxTag::Opening => depth -= 1,
+                    JsxTag::SelfClosing => {}
+                }
+                if depth == 0 {
+                    return Some(open);
+                }
+                i = open;
+            }
+            _ => {}
         }
+    }
+    None
+}
 
-        // Found the closing `/` (not inside character class)
-        if c == '/' && !in_char_class {
-            j += 1;
-            // Scan optional flags: g, i, m, s, u, y, d, v
-            while j < chars.len() && matches!(chars[j], 'g' | 'i' | 'm' | 's' | 'u' | 'y' | 'd' | 'v')
-            {
-                j += 1;
+/// The `<` and kind of the JSX tag ending with the `>` at `close`, if it
+/// reads as one: a tag name (or nothing, for a fragment) after the `<` or
+/// `</`, and attributes with no bare `<` or `>` outside braces and strings.
+fn jsx_tag(bytes: &[u8], mask: &CodeMask, close: usize) -> Option<(usize, JsxTag)> {
+    let mut i = close;
+    let open = loop {
+        i = i.checked_sub(1)?;
+        if !mask.is_code(i) {
+            continue;
+        }
+        match bytes[i] {
+            b'}' => i = matching_bracket(bytes, mask, i)?,
+            b'<' => break i,
+            b'>' | b';' | b'{' | b')' | b'(' => return None,
+            _ => {}
+        }
+    };
+
+    let tag = if bytes[open + 1] == b'/' {
+        JsxTag::Closing
+    } else if mask.is_code(close - 1) && bytes[close - 1] == b'/' && close - 1 > open {
+        JsxTag::SelfClosing
+    } else {
+        JsxTag::Opening
+    };
+    let name = if tag == JsxTag::Closing {
+        open + 2
+    } else {
+        open + 1
+    };
+    let named = bytes.get(name).is_some_and(|&b| is_ident_start(b));
+    match tag {
+        // `<>` and `</>` open and close a fragment.
+        JsxTag::Opening | JsxTag::Closing if name == close => Some((open, tag)),
+        _ if named => Some((open, tag)),
+        _ => None,
+    }
+}
+
+/// The bind receiver is the dotted path directly before `::` (`this.obj::m`).
+fn find_bind_left_operand(source: &str, op_start: usize) -> usize {
+    let prefix = &source.as_bytes()[..op_start];
+    let len = prefix
+        .iter()
+        .rev()
+        .take_while(|&&b| is_ident_char(b) || b == b'.')
+        .count();
+    op_start - len
+}
+
+/// The bound function is the single identifier directly after `::`.
+fn find_bind_right_operand(source: &str, op_end: usize) -> usize {
+    let rest = &source.as_bytes()[op_end..];
+    op_end + rest.iter().take_while(|&&b| is_ident_char(b)).count()
+}
+
+fn find_right_operand(source: &str, mask: &CodeMask, op_end: usize, op: ScBinaryOp) -> usize {
+    if op == ScBinaryOp::Bind {
+        return find_bind_right_operand(source, op_end);
+    }
+
+    // As on the left, brackets are skipped a group at a time. The operand
+    // may start on the next line: `xs |>\n    f`.
+    let bytes = source.as_bytes();
+    let mut i = boundary_after(bytes, op_end);
+    while i < bytes.len() {
+        if !mask.is_code(i) {
+            i += 1;
+            continue;
+        }
+
+        match bytes[i] {
+            // Type arguments are part of the operand: `x |> f<A, B>`.
+            b'<' if is_ident_char(bytes[i - 1]) => {
+                if let Some(close) = type_arguments_end(bytes, mask, i) {
+                    i = close;
+                }
+            }
+            // An unbalanced group runs to the end of the text.
+            b'(' | b'[' | b'{' => match matching_bracket(bytes, mask, i) {
+                Some(close) => i = close,
+                None => return bytes.len(),
+            },
+            b')' | b']' | b'}' | b';' | b',' => return i,
+            b'\n' if is_statement_break(source, mask, i) => return i,
+            b'|' if bytes.get(i + 1) == Some(&b'>') => {
+                if op.is_right_assoc() {
+                    if ScBinaryOp::Pipeline.precedence() < op.precedence() {
+                        return i;
+                    }
+                } else if ScBinaryOp::Pipeline.precedence() <= op.precedence() {
+                    return i;
+                }
+            }
+            b':' if bytes.get(i + 1) == Some(&b':') => {
+                if op.is_right_assoc() {
+                    if ScBinaryOp::Cons.precedence() < op.precedence() {
+                        return i;
+                    }
+                } else if ScBinaryOp::Cons.precedence() <= op.precedence() {
+                    return i;
+                }
+                // Skip the second `:` since we've checked `::`.
+                i += 1;
             }
-            return Some(j);
+            // A lone `:` ends the expression: `case a |> f:`.
+            b':' => return i,
+            _ => {}
         }
+        i += 1;
+    }
+
+    bytes.len()
+}
 
-        j += 1;
+/// Whether the right operand at `start` uses the `_` topic as a receiver:
+/// `_.m()`, `_?.m`, `_[0]`.
+fn is_topic_receiver(bytes: &[u8], start: usize) -> bool {
+    match bytes[start..].strip_prefix(b"_") {
+        Some(rest) => rest.starts_with(b".") || rest.starts_with(b"?.") || rest.starts_with(b"["),
+        None => false,
     }
+}
 
-    // No closing `/` found
+/// Between the brackets of `range`, if it is one array literal: `[a, b]`,
+/// not `[a].concat(b)`.
+fn array_literal_elements(
+    bytes: &[u8],
+    mask: &CodeMask,
+    range: Range<usize>,
+) -> Option<Range<usize>> {
+    if bytes.get(range.start) != Some(&b'[') {
+        return None;
+    }
+    let mut depth = 0;
+    for i in range.clone() {
+        if !mask.is_code(i) {
+            continue;
+        }
+        match bytes[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return (i + 1 == range.end).then_some(range.start + 1..i);
+                }
+            }
+            _ => {}
+        }
+    }
     None
 }
 
-fn is_word_start(chars: &[char], i: usize) -> bool {
-    if !chars[i].is_alphabetic() && chars[i] != '_' && chars[i] != '$' {
+/// Whether `range` is a chain that a member access can follow without
+/// parentheses: names, `.`, `?.`, a postfix `!` and bracketed groups, like
+/// `a.b(c)[0]`. `1`, `a + b`, `!a` or `await a` would need parentheses.
+fn is_member_chain(bytes: &[u8], mask: &CodeMask, range: Range<usize>) -> bool {
+    // Not `{`: at the start of a statement it would open a block.
+    let starts_chain = range
+        .clone()
+        .next()
+        .is_some_and(|i| is_ident_start(bytes[i]) || matches!(bytes[i], b'(' | b'['));
+    if !starts_chain {
         return false;
     }
-    if i > 0 && (chars[i - 1].is_alphanumeric() || chars[i - 1] == '_' || chars[i - 1] == '$') {
+    let mut depth: i32 = 0;
+    for i in range {
+        if !mask.is_code(i) {
+            if depth == 0 {
+                return false;
+            }
+            continue;
+        }
+        match bytes[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth -= 1,
+            _ if depth > 0 => {}
+            b'.' | b'?' | b'!' => {}
+            b => {
+                if !is_ident_char(b) {
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
+/// The first operator at the top level of `range` that makes it a binary
+/// arithmetic, comparison, bitwise or logical expression, like the `+` in
+/// `b + c`. An arrow function has none: its body is its own.
+fn top_level_binary_operator(
+    bytes: &[u8],
+    mask: &CodeMask,
+    range: Range<usize>,
+) -> Option<Range<usize>> {
+    const BINARY: [&[u8]; 24] = [
+        b">>>", b"===", b"!==", b"**", b"&&", b"||", b"??", b"==", b"!=", b"<=", b">=", b"<<",
+        b">>", b"+", b"-", b"*", b"/", b"%", b"&", b"|", b"^", b"<", b">", b"=",
+    ];
+    let mut depth = 0;
+    // Whether the last token ends an operand, so an operator after it is
+    // binary rather than unary.
+    let mut after_operand = false;
+    let mut i = range.start;
+    while i < range.end {
+        let b = bytes[i];
+        if !mask.is_code(i) {
+            // A string, template or regex is an operand; a comment isn't.
+            let comment = b == b'/' && matches!(bytes.get(i + 1), Some(b'/' | b'*'));
+            after_operand |= !comment;
+            while i < range.end && !mask.is_code(i) {
+                i += 1;
+            }
+            continue;
+        }
+        if b.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        match b {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                depth -= 1;
+                after_operand = true;
+            }
+            _ if depth > 0 => {}
+            _ if is_ident_char(b) => {
+                let end = scan_word(bytes, i);
+                after_operand = !is_operator_keyword(&bytes[i..end]);
+                i = end;
+                continue;
+            }
+            // Type arguments: `f<T>(x)`.
+            b'<' if i > 0 && is_ident_char(bytes[i - 1]) => depth += 1,
+            b'>' if depth > 0 => depth -= 1,
+            b'?' if bytes.get(i + 1) == Some(&b'.') => after_operand = false,
+            _ if is_operator_byte(b) => {
+                let end = (i..range.end)
+                    .find(|&j| !is_operator_byte(bytes[j]))
+                    .unwrap_or(range.end);
+                let run = &bytes[i..end];
+                if run == b"=>" {
+                    return None;
+                }
+                // `x!`, `i++`: postfix, so still after an operand.
+                let postfix = matches!(run, b"!" | b"++" | b"--");
+                if after_operand && !postfix {
+                    // Anything else, such as a conditional's `?`, isn't
+                    // linted.
+                    let op = BINARY.iter().find(|op| run.starts_with(op))?;
+                    if *op != b"=" {
+                        return Some(i..i + op.len());
+                    }
+                }
+                after_operand &= postfix;
+                i = end;
+                continue;
+            }
+            _ => after_operand = false,
+        }
+        i += 1;
+    }
+    None
+}
+
+fn is_spaced(bytes: &[u8], op: Range<usize>) -> bool {
+    let before = op.start == 0 || bytes[op.start - 1].is_ascii_whitespace();
+    let after = bytes.get(op.end).is_none_or(|b| b.is_ascii_whitespace());
+    before && after
+}
+
+fn is_word_start(bytes: &[u8], i: usize) -> bool {
+    if !is_ident_start(bytes[i]) {
+        return false;
+    }
+    if i > 0 && is_ident_char(bytes[i - 1]) {
         return false;
     }
     true
 }
 
-fn scan_word(chars: &[char], start: usize) -> usize {
+fn scan_word(bytes: &[u8], start: usize) -> usize {
     let mut i = start;
-    while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$') {
+    while i < bytes.len() && is_ident_char(bytes[i]) {
         i += 1;
     }
     i
@@ -619,34 +1417,607 @@ mod tests {
         ScSyntax::default()
     }
 
+    fn rewrite_with_report(input: &str, syntax: &ScSyntax) -> (String, PreprocessReport) {
+        let mut report = PreprocessReport::default();
+        let mut output = input.to_string();
+        rewrite_operators(&mut output, syntax, &mut report);
+        (output, report)
+    }
+
+    fn rewrite(input: &str, syntax: &ScSyntax) -> String {
+        rewrite_with_report(input, syntax).0
+    }
+
+    #[test]
+    fn records_one_rewrite_per_operator() {
+        let (_, report) = rewrite_with_report("const x = 1 :: 2 :: [] |> f;", &syntax_all());
+        assert_eq!(report.count(RewriteKind::Cons), 2);
+        assert_eq!(report.count(RewriteKind::Pipeline), 1);
+    }
+
+    #[test]
+    fn rewrites_record_original_spans() {
+        let input = "const r = xs |> map(f)   |> take(2);";
+        // Through the whole preprocessor, which trims the mapped spans.
+        let report = crate::preprocess::preprocess_with_report(input, &syntax_all()).report;
+        let spans: Vec<_> = report
+            .rewrites
+            .iter()
+            .map(|r| {
+                let operands: Vec<_> = r.operands.iter().map(|o| &input[o.clone()]).collect();
+                (&input[r.span.clone()], &input[r.head.clone()], operands)
+            })
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                ("xs |> map(f)", "|>", vec!["xs", "map(f)"]),
+                (
+                    "xs |> map(f)   |> take(2)",
+                    "|>",
+                    vec!["xs |> map(f)", "take(2)"]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn pipeline_missing_left_operand_is_an_error() {
+        let input = "const x = |> f;";
+        let (output, report) = rewrite_with_report(input, &syntax_all());
+        assert_eq!(output, input);
+        assert!(report.has_errors());
+        assert_eq!(report.diagnostics.len(), 1);
+        assert_eq!(
+            report.diagnostics[0].message,
+            "pipeline requires a left operand"
+        );
+        assert_eq!(report.diagnostics[0].span, 10..12);
+    }
+
+    #[test]
+    fn pipeline_missing_right_operand_is_an_error() {
+        let input = "a |>;";
+        let (output, report) = rewrite_with_report(input, &syntax_all());
+        assert_eq!(output, input);
+        assert_eq!(report.diagnostics.len(), 1);
+        assert_eq!(
+            report.diagnostics[0].message,
+            "pipeline requires a right operand"
+        );
+        assert_eq!(report.diagnostics[0].span, 2..4);
+        assert!(report.rewrites.is_empty());
+    }
+
+    #[test]
+    fn operator_at_end_of_file_is_an_error() {
+        for (input, message) in [
+            ("const x = a |>", "pipeline requires a right operand"),
+            ("const x = a |>\n", "pipeline requires a right operand"),
+            (
+                "const x = a |> // todo",
+                "pipeline requires a right operand",
+            ),
+            ("const x = a ::", "cons requires a right operand"),
+        ] {
+            let (output, report) = rewrite_with_report(input, &syntax_all());
+            assert_eq!(output, input);
+            let messages: Vec<_> = report.diagnostics.iter().map(|d| &d.message).collect();
+            assert_eq!(messages, [message], "{input:?}");
+            assert_eq!(report.diagnostics[0].span, 12..14, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn declare_blocks_are_not_rewritten() {
+        for input in [
+            "declare global { const a: A |> B; }",
+            "declare module \"m\" {\n  export const xs: A :: B;\n}",
+            "declare namespace N { function f(x: { a: A |> B }): void; }",
+            "export declare const x: A |> B;",
+            "declare global\n{\n  const a: A |> B;\n}",
+        ] {
+            assert_eq!(rewrite(input, &syntax_all()), input);
+        }
+
+        // The declaration ends at its block, `;`, or line.
+        for (input, expected) in [
+            (
+                "declare global { interface W { n: number } }\nconst a = x |> f;",
+                "declare global { interface W { n: number } }\nconst a = __binop__(x, \"|>\", f);",
+            ),
+            (
+                "declare const v: number; const a = x |> f;",
+                "declare const v: number; const a = __binop__(x, \"|>\", f);",
+            ),
+            (
+                "declare const v: number\nconst a = x |> f",
+                "declare const v: number\nconst a = __binop__(x, \"|>\", f)",
+            ),
+            // `declare` as an identifier.
+            ("declare(x |> f);", "declare(__binop__(x, \"|>\", f));"),
+            ("o.declare |> f;", "__binop__(o.declare, \"|>\", f);"),
+        ] {
+            assert_eq!(rewrite(input, &syntax_all()), expected, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn adjacent_operators_are_one_error() {
+        for (input, message, span) in [
+            (
+                "const a = a |>|> f;",
+                "unexpected operator `|>` after `|>`",
+                14..16,
+            ),
+            (
+                "const a = a |> |> f;",
+                "unexpected operator `|>` after `|>`",
+                15..17,
+            ),
+            (
+                "const b = a ::: b;",
+                "unexpected operator `:` after `::`",
+                14..15,
+            ),
+        ] {
+            let (output, report) = rewrite_with_report(input, &syntax_all());
+            assert_eq!(output, input);
+            let messages: Vec<_> = report.diagnostics.iter().map(|d| &d.message).collect();
+            assert_eq!(messages, [message], "{input}");
+            assert_eq!(report.diagnostics[0].span, span, "{input}");
+        }
+    }
+
+    #[test]
+    fn await_is_not_a_pipe_target() {
+        let input = "const a = data |> await parse;";
+        let (output, report) = rewrite_with_report(input, &syntax_all());
+        assert_eq!(output, input);
+        let messages: Vec<_> = report.diagnostics.iter().map(|d| &d.message).collect();
+        assert_eq!(
+            messages,
+            ["`await` can't be a pipe target; write `await (data |> parse)` to await the result"]
+        );
+        assert_eq!(report.diagnostics[0].span, 18..23);
+
+        let (_, report) = rewrite_with_report("r = load(id) |> await parse(opts);", &syntax_all());
+        assert_eq!(
+            report.diagnostics[0].message,
+            "`await` can't be a pipe target; write `await (load(id) |> parse(opts))` to await the result"
+        );
+
+        let (output, report) = rewrite_with_report("const b = x |> (await f);", &syntax_all());
+        assert!(report.diagnostics.is_empty());
+        assert_eq!(output, "const b = __binop__(x, \"|>\", (await f));");
+        let (output, _) = rewrite_with_report("const c = x |> awaited;", &syntax_all());
+        assert_eq!(output, "const c = __binop__(x, \"|>\", awaited);");
+    }
+
+    #[test]
+    fn required_operator_spaces() {
+        let syntax = ScSyntax {
+            require_operator_spaces: true,
+            ..syntax_all()
+        };
+        let spaced = "const a = x |> f;\nconst b = 1 :: [];\n";
+        let (output, report) = rewrite_with_report(spaced, &syntax);
+        assert!(report.diagnostics.is_empty());
+        assert_eq!(
+            output,
+            "const a = __binop__(x, \"|>\", f);\nconst b = __binop__(1, \"::\", []);\n"
+        );
+
+        for (input, message, span) in [
+            (
+                "const a = x|>f;",
+                "`|>` needs whitespace on both sides, as in `a |> b`",
+                11..13,
+            ),
+            (
+                "const a = x |>f;",
+                "`|>` needs whitespace on both sides, as in `a |> b`",
+                12..14,
+            ),
+            (
+                "const b = 1::[];",
+                "`::` needs whitespace on both sides, as in `a :: b`",
+                11..13,
+            ),
+        ] {
+            let (output, report) = rewrite_with_report(input, &syntax);
+            assert_eq!(output, input);
+            let messages: Vec<_> = report.diagnostics.iter().map(|d| &d.message).collect();
+            assert_eq!(messages, [message], "{input}");
+            assert_eq!(report.diagnostics[0].span, span, "{input}");
+
+            let (_, report) = rewrite_with_report(input, &syntax_all());
+            assert!(report.diagnostics.is_empty(), "{input}");
+        }
+
+        let bind = ScSyntax {
+            bind: true,
+            ..syntax
+        };
+        let (output, report) = rewrite_with_report("const g = obj::method;", &bind);
+        assert!(report.diagnostics.is_empty());
+        assert_eq!(output, "const g = method.bind(obj);");
+    }
+
+    #[test]
+    fn hack_topics_outside_the_receiver_are_linted() {
+        let hack = ScSyntax {
+            pipe_style: PipeStyle::Hack,
+            ..ScSyntax::default()
+        };
+        let stray = |input: &str| {
+            let report = crate::preprocess::preprocess_with_report(input, &hack).report;
+            report
+                .diagnostics
+                .iter()
+                .map(|d| (d.severity, d.span.start))
+                .collect::<Vec<_>>()
+        };
+
+        let warning = crate::preprocess::Severity::Warning;
+        assert_eq!(stray("r = xs |> groupBy(_.customer);"), [(warning, 18)]);
+        assert_eq!(stray("r = xs |> _.map(_);"), [(warning, 16)]);
+        assert_eq!(stray("r = xs |> _;"), [(warning, 10)]);
+        // Nested: each `_` once, and a nested receiver is fine.
+        assert_eq!(stray("r = y |> (x |> g(_));"), [(warning, 17)]);
+        for input in [
+            "r = xs |> _.map(f) |> _[0];",
+            "r = xs |> f(g |> _.id);",
+            "r = xs |> f(o._, _x, x_, \"_\");",
+            "r = _(xs) |> f;",
+        ] {
+            assert_eq!(stray(input), [], "{input:?}");
+        }
+        // `_` is an ordinary identifier in the default style.
+        let report =
+            crate::preprocess::preprocess_with_report("r = xs |> f(_);", &ScSyntax::default());
+        assert!(report.report.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn binary_pipe_targets_are_linted() {
+        let syntax = ScSyntax {
+            lint_pipe_operands: true,
+            ..syntax_all()
+        };
+        let lint = |input: &str| {
+            let mut report = PreprocessReport::default();
+            report_binary_pipe_targets(input, &syntax, &mut report);
+            report.diagnostics
+        };
+
+        let preprocessed =
+            crate::preprocess::preprocess_with_report("const a = x |> b + c;", &syntax);
+        assert_eq!(preprocessed.code, "const a = __binop__(x, \"|>\", b + c);");
+        let [diagnostic] = preprocessed.report.diagnostics.as_slice() else {
+            panic!(
+                "expected one diagnostic: {:?}",
+                preprocessed.report.diagnostics
+            );
+        };
+        assert_eq!(diagnostic.severity, crate::preprocess::Severity::Warning);
+        assert_eq!(
+            diagnostic.message,
+            "`|>` pipes into all of `b + c`; parenthesize it if that's meant, \
+             or the pipeline to apply `+` to its result"
+        );
+        assert_eq!(diagnostic.span, 15..20);
+
+        for (input, op) in [
+            ("x |> f || g;", "||"),
+            ("x |> f === y;", "==="),
+            ("x |> f(1) * 2 |> g;", "*"),
+            ("x |> o.f ?? g;", "??"),
+            ("x |> \"s\" + f;", "+"),
+        ] {
+            let diagnostics = lint(input);
+            assert_eq!(diagnostics.len(), 1, "{input}");
+            assert!(
+                diagnostics[0]
+                    .message
+                    .ends_with(&format!("apply `{op}` to its result")),
+                "{input}"
+            );
+        }
+
+        for input in [
+            "x |> f;",
+            "x |> (b + c);",
+            "x |> f(a + b, -c);",
+            "x |> o.f!;",
+            "x |> g<T>(1);",
+            "x |> o?.f;",
+            "x |> -f;",
+            "x |> (y) => y + 1;",
+            "x |> async (y) => y * 2;",
+            "x |> f /* + g */;",
+            "x |> `${a + b}`;",
+            "x |> typeof f;",
+            "x |> c ? f : g;",
+            "total += x |> f;",
+        ] {
+            let diagnostics = lint(input);
+            assert!(diagnostics.is_empty(), "{input}: {diagnostics:?}");
+        }
+
+        let preprocessed = crate::preprocess::preprocess_with_report("x |> b + c;", &syntax_all());
+        assert!(preprocessed.report.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn strict_mode_reports_disabled_operators() {
+        let source = "const a = x |> f;\nconst b = 1 :: [];\n";
+        for (syntax, message, span) in [
+            (
+                ScSyntax {
+                    pipeline: false,
+                    ..ScSyntax::default()
+                },
+                "pipeline syntax found but pipeline feature is disabled",
+                12..14,
+            ),
+            (
+                ScSyntax {
+                    cons: false,
+                    ..ScSyntax::default()
+                },
+                "cons syntax found but cons feature is disabled",
+                30..32,
+            ),
+        ] {
+            let strict = ScSyntax {
+                strict: true,
+                ..syntax.clone()
+            };
+            let report = crate::preprocess::preprocess_with_report(source, &strict).report;
+            let errors: Vec<_> = report
+                .diagnostics
+                .iter()
+                .map(|d| (d.message.as_str(), d.span.clone()))
+                .collect();
+            assert_eq!(errors, [(message, span)]);
+
+            // Outside strict mode the operator is left for SWC to reject.
+            let report = crate::preprocess::preprocess_with_report(source, &syntax).report;
+            assert!(report.diagnostics.is_empty(), "{:?}", report.diagnostics);
+        }
+    }
+
+    #[test]
+    fn strict_mode_reports_pipeline_in_a_type() {
+        let source = "type T = A |> B;\nconst x = a |> f;";
+        let (output, report) = rewrite_with_report(source, &ScSyntax::default());
+        assert!(!report.has_errors());
+        assert_eq!(
+            output,
+            "type T = A |> B;\nconst x = __binop__(a, \"|>\", f);"
+        );
+
+        let strict = ScSyntax {
+            strict: true,
+            ..ScSyntax::default()
+        };
+        let (output, report) = rewrite_with_report(source, &strict);
+        assert_eq!(output, source);
+        let [diag] = report.diagnostics.as_slice() else {
+            panic!("{:?}", report.diagnostics);
+        };
+        assert_eq!(diag.severity, crate::preprocess::Severity::Error);
+        assert!(diag.message.contains("did you mean `|` then `>`?"));
+        assert_eq!(&source[diag.span.clone()], "|>");
+        assert_eq!(diag.span.start, source.find("|>").unwrap());
+    }
+
+    #[test]
+    fn strict_mode_warns_about_namespace_style_cons() {
+        let source =
+            "const a = Foo::bar;\nconst b = x :: xs;\nconst c = y::[];\nconst d = 1::rest;";
+        let (output, report) = rewrite_with_report(source, &ScSyntax::default());
+        assert!(report.diagnostics.is_empty(), "{:?}", report.diagnostics);
+
+        let strict = ScSyntax {
+            strict: true,
+            ..ScSyntax::default()
+        };
+        let preprocessed = crate::preprocess::preprocess_with_report(source, &strict);
+        // Still rewritten: it's only a warning.
+        assert_eq!(preprocessed.code, output);
+        let [diag] = preprocessed.report.diagnostics.as_slice() else {
+            panic!("{:?}", preprocessed.report.diagnostics);
+        };
+        assert_eq!(diag.severity, crate::preprocess::Severity::Warning);
+        assert_eq!(
+            diag.message,
+            "`Foo::bar` conses `Foo` onto `bar`; for member access write `Foo.bar`"
+        );
+        assert_eq!(&source[diag.span.clone()], "Foo::bar");
+    }
+
+    #[test]
+    fn error_on_residual_reports_operators_past_the_iteration_cap() {
+        // One more pipe than the rewrite loop's 1000 iterations.
+        let source = format!("const r = x{};", " |> f".repeat(1001));
+        let report = crate::preprocess::preprocess_with_report(&source, &syntax_all()).report;
+        assert!(report.diagnostics.is_empty(), "{:?}", report.diagnostics);
+
+        let checked = ScSyntax {
+            error_on_residual: true,
+            ..ScSyntax::default()
+        };
+        let report = crate::preprocess::preprocess_with_report(&source, &checked).report;
+        let [diag] = report.diagnostics.as_slice() else {
+            panic!("{:?}", report.diagnostics);
+        };
+        assert_eq!(diag.severity, crate::preprocess::Severity::Error);
+        assert_eq!(diag.message, "`|>` was left unrewritten");
+        assert_eq!(diag.span.start, source.rfind("|>").unwrap());
+    }
+
+    #[test]
+    fn error_on_residual_reports_disabled_operators() {
+        let source = "const a = x |> f;\nconst b = 1 :: [];\nconst c = \"y |> g\";";
+        let checked = ScSyntax {
+            pipeline: false,
+            cons: false,
+            error_on_residual: true,
+            ..ScSyntax::default()
+        };
+        let report = crate::preprocess::preprocess_with_report(source, &checked).report;
+        let spans: Vec<_> = report
+            .diagnostics
+            .iter()
+            .map(|d| &source[d.span.clone()])
+            .collect();
+        assert_eq!(spans, ["|>", "::"]);
+
+        // Nothing is left once both are on.
+        let checked = ScSyntax {
+            error_on_residual: true,
+            ..ScSyntax::default()
+        };
+        let report = crate::preprocess::preprocess_with_report(source, &checked).report;
+        assert!(report.diagnostics.is_empty(), "{:?}", report.diagnostics);
+    }
+
+    #[test]
+    fn pipeline_with_non_ascii_operands() {
+        let input = r#"const größe = "héllo" |> länge |> ñ;"#;
+        let output = rewrite(input, &syntax_all());
+        assert_eq!(
+            output,
+            r#"const größe = __binop__(__binop__("héllo", "|>", länge), "|>", ñ);"#
+        );
+    }
+
     #[test]
     fn pipeline_basic() {
         let input = "const x = a |> f;";
-        let output = rewrite_operators(input, &syntax_all());
+        let output = rewrite(input, &syntax_all());
         assert_eq!(output, r#"const x = __binop__(a, "|>", f);"#);
     }
 
     #[test]
     fn pipeline_chained() {
         let input = "const x = a |> f |> g;";
-        let output = rewrite_operators(input, &syntax_all());
+        let output = rewrite(input, &syntax_all());
         assert_eq!(
             output,
             r#"const x = __binop__(__binop__(a, "|>", f), "|>", g);"#
         );
     }
 
+    #[test]
+    fn pipeline_in_for_of_header() {
+        let input = "for (const x of xs |> f) {}";
+        let output = rewrite(input, &syntax_all());
+        assert_eq!(output, r#"for (const x of __binop__(xs, "|>", f)) {}"#);
+    }
+
+    #[test]
+    fn pipeline_in_for_await_in_header() {
+        let input = "for await (const k in obj |> keys) {}";
+        let output = rewrite(input, &syntax_all());
+        assert_eq!(
+            output,
+            r#"for await (const k in __binop__(obj, "|>", keys)) {}"#
+        );
+    }
+
+    #[test]
+    fn in_operator_stays_in_left_operand() {
+        let input = r#"const has = "k" in o |> f;"#;
+        let output = rewrite(input, &syntax_all());
+        assert_eq!(output, r#"const has = __binop__("k" in o, "|>", f);"#);
+    }
+
+    #[test]
+    fn assertion_keeps_its_type_operand() {
+        let input = "const x = (a |> f) satisfies Foo<A>;\nconst y = x as Foo |> f;";
+        let output = rewrite(input, &syntax_all());
+        assert_eq!(
+            output,
+            "const x = (__binop__(a, \"|>\", f)) satisfies Foo<A>;\nconst y = __binop__(x as Foo, \"|>\", f);"
+        );
+    }
+
+    #[test]
+    fn arrow_body_bounds_left_operand() {
+        let input = "export default () => data |> render;";
+        let output = rewrite(input, &syntax_all());
+        assert_eq!(
+            output,
+            r#"export default () => __binop__(data, "|>", render);"#
+        );
+    }
+
+    #[test]
+    fn statement_keywords_bound_left_operand() {
+        let input = "return x |> f; throw e |> wrap; export default y |> g;";
+        let output = rewrite(input, &syntax_all());
+        assert_eq!(
+            output,
+            r#"return __binop__(x, "|>", f); throw __binop__(e, "|>", wrap); export default __binop__(y, "|>", g);"#
+        );
+    }
+
+    #[test]
+    fn statement_headers_and_labels_bound_left_operand() {
+        for (input, expected) in [
+            ("if (a) x |> f;", r#"if (a) __binop__(x, "|>", f);"#),
+            ("while (a) x :: xs;", r#"while (a) __binop__(x, "::", xs);"#),
+            (
+                "for await (const x of xs) x |> f;",
+                r#"for await (const x of xs) __binop__(x, "|>", f);"#,
+            ),
+            ("l: x |> f;", r#"l: __binop__(x, "|>", f);"#),
+            ("{ a: b: x |> f; }", r#"{ a: b: __binop__(x, "|>", f); }"#),
+            // Calls, not headers.
+            ("r = o.if(a) |> f;", r#"r = __binop__(o.if(a), "|>", f);"#),
+            ("r = o.for(a) |> f;", r#"r = __binop__(o.for(a), "|>", f);"#),
+            ("r = g(a) |> f;", r#"r = __binop__(g(a), "|>", f);"#),
+            // A class field's type, not a label.
+            ("class C { a: T |> U; }", "class C { a: T |> U; }"),
+        ] {
+            assert_eq!(rewrite(input, &syntax_all()), expected, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn case_labels_end_at_their_colon() {
+        let input = "switch (v) { case a |> f: g(v |> h); default: v |> k; }";
+        let output = rewrite(input, &syntax_all());
+        assert_eq!(
+            output,
+            r#"switch (v) { case __binop__(a, "|>", f): g(__binop__(v, "|>", h)); default: __binop__(v, "|>", k); }"#
+        );
+    }
+
+    #[test]
+    fn yield_bounds_left_operand() {
+        let input = "yield x |> f; yield* xs |> h; yield*ys |> h;";
+        let output = rewrite(input, &syntax_all());
+        assert_eq!(
+            output,
+            r#"yield __binop__(x, "|>", f); yield* __binop__(xs, "|>", h); yield*__binop__(ys, "|>", h);"#
+        );
+    }
+
     #[test]
     fn cons_basic() {
         let input = "const x = 1 :: [];";
-        let output = rewrite_operators(input, &syntax_all());
+        let output = rewrite(input, &syntax_all());
         assert_eq!(output, r#"const x = __binop__(1, "::", []);"#);
     }
 
     #[test]
     fn cons_chained() {
         let input = "const x = 1 :: 2 :: [];";
-        let output = rewrite_operators(input, &syntax_all());
+        let output = rewrite(input, &syntax_all());
         assert_eq!(
             output,
             r#"const x = __binop__(1, "::", __binop__(2, "::", []));"#
@@ -656,14 +2027,14 @@ mod tests {
     #[test]
     fn pipeline_in_string_not_rewritten() {
         let input = r#"const s = "a |> b";"#;
-        let output = rewrite_operators(input, &syntax_all());
+        let output = rewrite(input, &syntax_all());
         assert_eq!(output, input);
     }
 
     #[test]
     fn cons_and_pipeline_mixed() {
         let input = "const x = a :: b |> f;";
-        let output = rewrite_operators(input, &syntax_all());
+        let output = rewrite(input, &syntax_all());
         // :: binds tighter than |>
         assert_eq!(
             output,
@@ -671,31 +2042,79 @@ mod tests {
         );
     }
 
+    fn syntax_bind() -> ScSyntax {
+        ScSyntax {
+            bind: true,
+            ..ScSyntax::default()
+        }
+    }
+
+    #[test]
+    fn bind_unspaced_identifiers() {
+        let input = "const h = button::onClick;";
+        let output = rewrite(input, &syntax_bind());
+        assert_eq!(output, "const h = onClick.bind(button);");
+    }
+
+    #[test]
+    fn bind_spaced_stays_cons() {
+        let input = "const x = y :: ys;";
+        let output = rewrite(input, &syntax_bind());
+        assert_eq!(output, r#"const x = __binop__(y, "::", ys);"#);
+    }
+
+    #[test]
+    fn bind_array_tail_stays_cons() {
+        let input = "const x = y::[];";
+        let output = rewrite(input, &syntax_bind());
+        assert_eq!(output, r#"const x = __binop__(y, "::", []);"#);
+    }
+
+    #[test]
+    fn unspaced_is_cons_without_bind_flag() {
+        let input = "const h = button::onClick;";
+        let output = rewrite(input, &syntax_all());
+        assert_eq!(output, r#"const h = __binop__(button, "::", onClick);"#);
+    }
+
+    #[test]
+    fn bind_binds_tighter_than_pipeline_and_cons() {
+        let input = "const x = this.model::render |> run;";
+        let output = rewrite(input, &syntax_bind());
+        assert_eq!(
+            output,
+            r#"const x = __binop__(render.bind(this.model), "|>", run);"#
+        );
+    }
+
     #[test]
     fn regex_pipe_not_rewritten() {
         let input = "const pattern = /foo|bar/g;";
-        let output = rewrite_operators(input, &syntax_all());
+        let output = rewrite(input, &syntax_all());
         assert_eq!(output, input);
     }
 
     #[test]
     fn regex_in_call_not_rewritten() {
         let input = "const result = input.match(/a|b|c/);";
-        let output = rewrite_operators(input, &syntax_all());
+        let output = rewrite(input, &syntax_all());
         assert_eq!(output, input);
     }
 
     #[test]
     fn regex_with_pipeline() {
         let input = "const x = text.match(/a|b/) |> f;";
-        let output = rewrite_operators(input, &syntax_all());
-        assert_eq!(output, r#"const x = __binop__(text.match(/a|b/), "|>", f);"#);
+        let output = rewrite(input, &syntax_all());
+        assert_eq!(
+            output,
+            r#"const x = __binop__(text.match(/a|b/), "|>", f);"#
+        );
     }
 
     #[test]
     fn regex_as_pipeline_operand() {
         let input = "const x = /foo|bar/.test(s) |> Boolean;";
-        let output = rewrite_operators(input, &syntax_all());
+        let output = rewrite(input, &syntax_all());
         assert_eq!(
             output,
             r#"const x = __binop__(/foo|bar/.test(s), "|>", Boolean);"#
@@ -705,49 +2124,254 @@ mod tests {
     #[test]
     fn regex_after_return() {
         let input = "return /a|b/;";
-        let output = rewrite_operators(input, &syntax_all());
+        let output = rewrite(input, &syntax_all());
         assert_eq!(output, input);
     }
 
+    #[test]
+    fn division_after_operand_is_not_a_regex() {
+        // A `/` after `]`, `)`, a template or regex flags divides; read as a
+        // regex it would swallow the `|>` up to the next `/`.
+        for left in ["a[i] / b", "foo() / bar", "`${x}` / 2", "/a|b/g / 2"] {
+            let input = format!("const r = {left} |> f; const s = t / u;");
+            let output = rewrite(&input, &syntax_all());
+            assert_eq!(
+                output,
+                format!(r#"const r = __binop__({left}, "|>", f); const s = t / u;"#)
+            );
+        }
+    }
+
+    #[test]
+    fn string_left_operand_is_taken_whole() {
+        for left in [
+            r#""a; b |> c""#,
+            "'x, (y'",
+            "`n=${n};`",
+            "`${`;`}`",
+            "/[;]/",
+        ] {
+            let input = format!("const r = {left} |> f;");
+            let output = rewrite(&input, &syntax_all());
+            assert_eq!(output, format!(r#"const r = __binop__({left}, "|>", f);"#));
+        }
+    }
+
+    #[test]
+    fn operators_in_non_code_are_untouched_on_either_side() {
+        // Each region holds operators and boundaries (`;`, `,`, `(`) for both
+        // the backward scan of a left operand and the forward scan of a right one.
+        let regions = [
+            r#""a |> b; c :: (d""#,
+            "'a |> b, c :: (d'",
+            "`a |> b; ${x} c :: (d`",
+            "/a|>b;c::(d/",
+            "/* a |> b; c :: (d */ e",
+        ];
+        for region in regions {
+            let input = format!("const r = {region} + a |> b + {region};");
+            let output = rewrite(&input, &syntax_all());
+            // A comment leading the operand is trimmed off it.
+            let left = region
+                .strip_prefix("/* a |> b; c :: (d */ ")
+                .unwrap_or(region);
+            assert_eq!(
+                output,
+                format!(r#"const r = __binop__({left} + a, "|>", b + {region});"#),
+                "{region}"
+            );
+        }
+    }
+
+    #[test]
+    fn comments_around_operands_stay_out_of_the_call() {
+        for (input, expected) in [
+            ("x /* l */ |> /* r */ f;", r#"__binop__(x, "|>", f);"#),
+            // Inside the call, the line comment would swallow `, "|>", f)`.
+            ("x // l\n  |> f;", r#"__binop__(x, "|>", f);"#),
+            // After the call it can stay.
+            ("x |> f // r\n;", "__binop__(x, \"|>\", f) // r\n;"),
+            ("1 // a\n  // b\n  :: [];", r#"__binop__(1, "::", []);"#),
+            ("\"s\" // l\n  |> f;", r#"__binop__("s", "|>", f);"#),
+            ("x /* l *//* m */ |> f;", r#"__binop__(x, "|>", f);"#),
+            // Comments inside an operand are part of it.
+            ("g(a /* c */) |> f;", r#"__binop__(g(a /* c */), "|>", f);"#),
+        ] {
+            assert_eq!(rewrite(input, &syntax_all()), expected, "{input:?}");
+        }
+
+        let (_, report) = rewrite_with_report("x |> /* nothing */;", &syntax_all());
+        assert!(report.has_errors(), "{:?}", report.diagnostics);
+    }
+
+    #[test]
+    fn line_breaks_end_operands_only_where_a_statement_ends() {
+        let input =
+            "const x = a\n  |> f\n  |> g\nlog(x)\nfoo()\nys :: zs\nconst y = o\n  .m() |> h";
+        let output = rewrite(input, &syntax_all());
+        assert_eq!(
+            output,
+            "const x = __binop__(__binop__(a, \"|>\", f), \"|>\", g)\nlog(x)\nfoo()\n__binop__(ys, \"::\", zs)\nconst y = __binop__(o\n  .m(), \"|>\", h)"
+        );
+
+        // Nothing after the break can continue `b`; `c` can't end an
+        // expression before `instanceof`.
+        let output = rewrite(
+            "r = a |> b // done\n'next'\ns = c\n  instanceof C |> k",
+            &syntax_all(),
+        );
+        assert_eq!(
+            output,
+            "r = __binop__(a, \"|>\", b) // done\n'next'\ns = __binop__(c\n  instanceof C, \"|>\", k)"
+        );
+    }
+
+    #[test]
+    fn hack_topic_receiver_takes_the_left_operand() {
+        let hack = ScSyntax {
+            pipe_style: PipeStyle::Hack,
+            ..ScSyntax::default()
+        };
+        for (input, expected) in [
+            ("r = xs |> _.map(f);", "r = xs.map(f);"),
+            ("r = a.b(c)[0] |> _?.d;", "r = a.b(c)[0]?.d;"),
+            ("r = xs |> _[0] |> f;", r#"r = __binop__(xs[0], "|>", f);"#),
+            ("r = !a |> _.b;", "r = (!a).b;"),
+            ("r = { k: 1 } |> _.k;", "r = ({ k: 1 }).k;"),
+            ("r = xs |> g(_.id);", r#"r = __binop__(xs, "|>", g(_.id));"#),
+        ] {
+            assert_eq!(rewrite(input, &hack), expected, "{input:?}");
+        }
+        // `_` is an ordinary identifier in the default style.
+        let output = rewrite("r = xs |> _.map(f);", &ScSyntax::default());
+        assert_eq!(output, r#"r = __binop__(xs, "|>", _.map(f));"#);
+    }
+
+    #[test]
+    fn assignment_bounds_left_operand_but_comparison_does_not() {
+        for assign in [
+            "=", "+=", "-=", "*=", "/=", "%=", "**=", "<<=", ">>=", ">>>=", "&=", "|=", "^=",
+            "&&=", "||=", "??=",
+        ] {
+            let input = format!("x {assign} y |> f;");
+            let output = rewrite(&input, &syntax_all());
+            assert_eq!(output, format!(r#"x {assign} __binop__(y, "|>", f);"#));
+        }
+        for compare in ["==", "===", "!=", "!==", "<=", ">="] {
+            let input = format!("r = a {compare} b |> f;");
+            let output = rewrite(&input, &syntax_all());
+            assert_eq!(output, format!(r#"r = __binop__(a {compare} b, "|>", f);"#));
+        }
+        let output = rewrite("a = b = c :: d;", &syntax_all());
+        assert_eq!(output, r#"a = b = __binop__(c, "::", d);"#);
+    }
+
+    #[test]
+    fn jsx_elements_are_whole_left_operands() {
+        let cases = [
+            (r#"<A b="c" /> |> f"#, r#"__binop__(<A b="c" />, "|>", f)"#),
+            (
+                "<p>a, b = c</p> |> f",
+                r#"__binop__(<p>a, b = c</p>, "|>", f)"#,
+            ),
+            (
+                "<a><b x={() => y} /></a> |> f",
+                r#"__binop__(<a><b x={() => y} /></a>, "|>", f)"#,
+            ),
+            ("<>x</> :: xs", r#"__binop__(<>x</>, "::", xs)"#),
+            // Not an element: `>` as a comparison still ends at `=`.
+            ("r = a > b |> f", r#"r = __binop__(a > b, "|>", f)"#),
+        ];
+        for (input, expected) in cases {
+            let output = rewrite(&format!("{input};"), &syntax_all());
+            assert_eq!(output, format!("{expected};"), "{input}");
+        }
+    }
+
+    #[test]
+    fn unspaced_comparisons_are_not_type_arguments() {
+        let cases = [
+            ("r = a<b |> f", r#"r = __binop__(a<b, "|>", f)"#),
+            (
+                "r = (a<b) === (c>d) |> f",
+                r#"r = __binop__((a<b) === (c>d), "|>", f)"#,
+            ),
+            (
+                "r = a<b && c>d |> f",
+                r#"r = __binop__(a<b && c>d, "|>", f)"#,
+            ),
+            // Type arguments stay in the operand, commas included.
+            (
+                "r = new Map<K, V>() |> f",
+                r#"r = __binop__(new Map<K, V>(), "|>", f)"#,
+            ),
+            ("r = x |> g<A, B>", r#"r = __binop__(x, "|>", g<A, B>)"#),
+            (
+                "r = f<() => void>(x) |> g",
+                r#"r = __binop__(f<() => void>(x), "|>", g)"#,
+            ),
+        ];
+        for (input, expected) in cases {
+            let output = rewrite(&format!("{input};"), &syntax_all());
+            assert_eq!(output, format!("{expected};"), "{input}");
+        }
+    }
+
+    #[test]
+    fn jsx_closing_tag_is_not_a_regex() {
+        let input = "const a = <b>x</b>; const c = d |> f / 2;";
+        let output = rewrite(input, &syntax_all());
+        assert_eq!(
+            output,
+            r#"const a = <b>x</b>; const c = __binop__(d, "|>", f / 2);"#
+        );
+    }
+
     #[test]
     fn regex_with_char_class() {
         let input = "const r = /[a|b]/;";
-        let output = rewrite_operators(input, &syntax_all());
+        let output = rewrite(input, &syntax_all());
         assert_eq!(output, input);
     }
 
     #[test]
     fn regex_with_escaped_slash() {
         let input = r"const r = /a\/b|c/;";
-        let output = rewrite_operators(input, &syntax_all());
+        let output = rewrite(input, &syntax_all());
         assert_eq!(output, input);
     }
 
     #[test]
     fn template_literal_pipeline_in_interpolation() {
         let input = "const msg = `Result: ${data |> f}`;";
-        let output = rewrite_operators(input, &syntax_all());
-        assert_eq!(output, r#"const msg = `Result: ${__binop__(data, "|>", f)}`;"#);
+        let output = rewrite(input, &syntax_all());
+        assert_eq!(
+            output,
+            r#"const msg = `Result: ${__binop__(data, "|>", f)}`;"#
+        );
     }
 
     #[test]
     fn template_literal_literal_part_unchanged() {
         let input = "const msg = `plain |> text`;";
-        let output = rewrite_operators(input, &syntax_all());
+        let output = rewrite(input, &syntax_all());
         assert_eq!(output, input);
     }
 
     #[test]
     fn template_literal_nested() {
         let input = "const msg = `outer ${`inner ${x |> f}`}`;";
-        let output = rewrite_operators(input, &syntax_all());
-        assert_eq!(output, r#"const msg = `outer ${`inner ${__binop__(x, "|>", f)}`}`;"#);
+        let output = rewrite(input, &syntax_all());
+        assert_eq!(
+            output,
+            r#"const msg = `outer ${`inner ${__binop__(x, "|>", f)}`}`;"#
+        );
     }
 
     #[test]
     fn template_literal_multiple_interpolations() {
         let input = "const msg = `a ${a |> fa} b ${b |> fb}`;";
-        let output = rewrite_operators(input, &syntax_all());
+        let output = rewrite(input, &syntax_all());
         assert_eq!(
             output,
             r#"const msg = `a ${__binop__(a, "|>", fa)} b ${__binop__(b, "|>", fb)}`;"#
@@ -757,7 +2381,7 @@ mod tests {
     #[test]
     fn template_literal_cons_in_interpolation() {
         let input = "const list = `Items: ${1 :: 2 :: []}`;";
-        let output = rewrite_operators(input, &syntax_all());
+        let output = rewrite(input, &syntax_all());
         assert_eq!(
             output,
             r#"const list = `Items: ${__binop__(1, "::", __binop__(2, "::", []))}`;"#
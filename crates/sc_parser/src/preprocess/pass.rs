@@ -0,0 +1,80 @@
+//! The pass interface the preprocessor runs, for the built-in passes and for
+//! sugar defined outside this crate.
+
+use sc_ast::{PipeStyle, ScSyntax};
+
+use super::{hkt_pass, operator_pass, PreprocessReport};
+
+/// One text-level rewrite of the preprocessor.
+///
+/// [`preprocess_with_passes`](super::preprocess_with_passes) runs passes in
+/// order over one working copy of the source, each seeing what the ones
+/// before it wrote. Make every edit with
+/// [`OffsetMap::splice`](super::OffsetMap::splice) on `report.offsets`, so
+/// diagnostics and source maps still point into the original file, and map
+/// the spans a pass records back with `report.offsets.to_original_range`.
+pub trait PreprocessPass {
+    /// Short name, for debugging.
+    fn name(&self) -> &str;
+
+    /// Rewrite `text` in place, adding rewrites and diagnostics to `report`.
+    /// A pass for a disabled feature should leave `text` alone.
+    fn run(&self, text: &mut String, syntax: &ScSyntax, report: &mut PreprocessReport);
+}
+
+/// `F<_>` declarations and their usages (when [`ScSyntax::hkt`] is on). With
+/// HKT off in [`strict`](ScSyntax::strict) mode, each declaration is an
+/// error instead.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HktPass;
+
+impl PreprocessPass for HktPass {
+    fn name(&self) -> &str {
+        "hkt"
+    }
+
+    fn run(&self, text: &mut String, syntax: &ScSyntax, report: &mut PreprocessReport) {
+        if syntax.hkt {
+            hkt_pass::rewrite_hkt(text, &syntax.hkt_apply, report);
+        } else if syntax.strict {
+            hkt_pass::report_disabled_hkt(text, report);
+        }
+    }
+}
+
+/// `|>`, `::` and bind, each when enabled in [`ScSyntax`]. In
+/// [`strict`](ScSyntax::strict) mode a `|>` or `::` whose feature is off is
+/// an error, and `Ns::member` a warning.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OperatorPass;
+
+impl PreprocessPass for OperatorPass {
+    fn name(&self) -> &str {
+        "operators"
+    }
+
+    fn run(&self, text: &mut String, syntax: &ScSyntax, report: &mut PreprocessReport) {
+        if syntax.strict && !(syntax.pipeline && syntax.cons) {
+            operator_pass::report_disabled_operators(text, syntax, report);
+        }
+        if syntax.strict && syntax.cons {
+            operator_pass::report_namespace_cons(text, syntax, report);
+        }
+        if syntax.lint_pipe_operands && syntax.pipeline {
+            operator_pass::report_binary_pipe_targets(text, syntax, report);
+        }
+        if syntax.pipeline && syntax.pipe_style == PipeStyle::Hack {
+            operator_pass::report_stray_topics(text, syntax, report);
+        }
+        if syntax.pipeline || syntax.cons || syntax.bind {
+            operator_pass::rewrite_operators(text, syntax, report);
+        }
+    }
+}
+
+/// The built-in passes in the order [`preprocess`](super::preprocess) runs
+/// them: HKT first, so `F<A>` is `$<F, A>` before the operator pass looks
+/// for `<`/`>` type context. Insert a custom pass where its output needs it.
+pub fn default_passes() -> Vec<Box<dyn PreprocessPass>> {
+    vec![Box::new(HktPass), Box::new(OperatorPass)]
+}
@@ -0,0 +1,306 @@
+//! Diagnostics and rewrite records collected while preprocessing.
+
+use std::ops::Range;
+
+use sc_ast::ScBinaryOp;
+
+use super::offsets::OffsetMap;
+
+/// How serious a preprocessing diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A sugarcube-level diagnostic, as opposed to an SWC parse error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// Byte range in the original source.
+    pub span: Range<usize>,
+}
+
+impl Diagnostic {
+    pub fn warning(message: impl Into<String>, span: Range<usize>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: message.into(),
+            span,
+        }
+    }
+
+    pub fn error(message: impl Into<String>, span: Range<usize>) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// 1-based `(line, column)` of the start of the span in `source`.
+    pub fn line_col(&self, source: &str) -> (usize, usize) {
+        line_col(source, self.span.start)
+    }
+}
+
+/// 1-based `(line, column)` of byte offset `pos` in `source`. Columns count
+/// characters, not bytes.
+pub fn line_col(source: &str, pos: usize) -> (usize, usize) {
+    let before = &source[..pos.min(source.len())];
+    // Editors don't show a BOM, so it doesn't take up a column.
+    let before = before.strip_prefix('\u{FEFF}').unwrap_or(before);
+    let line = before.matches('\n').count() + 1;
+    let col = before.rfind('\n').map_or(before.chars().count(), |nl| {
+        before[nl + 1..].chars().count()
+    }) + 1;
+    (line, col)
+}
+
+/// `diag` the way rustc and SWC show errors: the message, where it is, and
+/// its source line with the span underlined by carets, e.g.
+///
+/// ```text
+/// error: pipeline requires a left operand
+///  --> input.ts:1:11
+///   |
+/// 1 | const x = |> f;
+///   |           ^^
+/// ```
+///
+/// A span running onto later lines is underlined to the end of its first.
+pub fn render_diagnostic(filename: &str, source: &str, diag: &Diagnostic) -> String {
+    render(filename, source, diag, false)
+}
+
+/// [`render_diagnostic`] for a terminal: the severity and the carets in
+/// bold ANSI red for an error, yellow for a warning.
+pub fn render_diagnostic_colored(filename: &str, source: &str, diag: &Diagnostic) -> String {
+    render(filename, source, diag, true)
+}
+
+fn render(filename: &str, source: &str, diag: &Diagnostic, color: bool) -> String {
+    let start = diag.span.start.min(source.len());
+    let (line, col) = line_col(source, start);
+    let line_start = match source[..start].rfind('\n') {
+        Some(nl) => nl + 1,
+        // As in `line_col`, a BOM isn't part of what the editor shows.
+        None if source.starts_with('\u{FEFF}') => '\u{FEFF}'.len_utf8().min(start),
+        None => 0,
+    };
+    let line_end = source[start..]
+        .find('\n')
+        .map_or(source.len(), |nl| start + nl);
+
+    let text = source[line_start..line_end].trim_end_matches('\r');
+    // Keep tabs so the carets line up however the terminal expands them.
+    let indent: String = source[line_start..start]
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let width = source[start..diag.span.end.clamp(start, line_end)]
+        .chars()
+        .count()
+        .max(1);
+
+    let (paint, reset) = match (color, diag.severity) {
+        (false, _) => ("", ""),
+        (true, Severity::Error) => ("\x1b[1;31m", "\x1b[0m"),
+        (true, Severity::Warning) => ("\x1b[1;33m", "\x1b[0m"),
+    };
+    let number = line.to_string();
+    let pad = " ".repeat(number.len());
+    format!(
+        "{paint}{}{reset}: {}\n{pad}--> {filename}:{line}:{col}\n{pad} |\n{number} | {text}\n{pad} | {indent}{paint}{}{reset}",
+        diag.severity,
+        diag.message,
+        "^".repeat(width)
+    )
+}
+
+/// The kind of construct a pass rewrote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RewriteKind {
+    Pipeline,
+    Cons,
+    Bind,
+    /// `F<_>` stripped to `F`.
+    HktDecl,
+    /// `F<A>` rewritten to `$<F, A>`.
+    HktUsage,
+    /// A construct of a [`PreprocessPass`](super::PreprocessPass) defined
+    /// outside this crate, by the name it reports.
+    Custom(&'static str),
+}
+
+impl RewriteKind {
+    /// Name used in diagnostics and tool output.
+    pub fn name(self) -> &'static str {
+        match self {
+            RewriteKind::Pipeline => "pipeline",
+            RewriteKind::Cons => "cons",
+            RewriteKind::Bind => "bind",
+            RewriteKind::HktDecl => "hkt-decl",
+            RewriteKind::HktUsage => "hkt-usage",
+            RewriteKind::Custom(name) => name,
+        }
+    }
+
+    /// The operator this kind rewrites, if it is an operator.
+    pub fn operator(self) -> Option<OperatorInfo> {
+        match self {
+            RewriteKind::Pipeline => Some(OperatorInfo::PIPELINE),
+            RewriteKind::Cons => Some(OperatorInfo::CONS),
+            RewriteKind::Bind => Some(OperatorInfo::BIND),
+            RewriteKind::HktDecl | RewriteKind::HktUsage | RewriteKind::Custom(_) => None,
+        }
+    }
+}
+
+impl From<ScBinaryOp> for RewriteKind {
+    fn from(op: ScBinaryOp) -> Self {
+        match op {
+            ScBinaryOp::Pipeline => RewriteKind::Pipeline,
+            ScBinaryOp::Cons => RewriteKind::Cons,
+            ScBinaryOp::Bind => RewriteKind::Bind,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+/// How a sugarcube operator is written and how it groups, as
+/// [`ScBinaryOp::precedence`] and [`ScBinaryOp::is_right_assoc`] define it.
+/// Higher precedence binds tighter; every standard JS operator sits between
+/// bind and cons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperatorInfo {
+    pub text: &'static str,
+    pub precedence: u8,
+    pub associativity: Associativity,
+}
+
+impl OperatorInfo {
+    pub const PIPELINE: Self = Self::of(ScBinaryOp::Pipeline);
+    pub const CONS: Self = Self::of(ScBinaryOp::Cons);
+    pub const BIND: Self = Self::of(ScBinaryOp::Bind);
+
+    pub const fn of(op: ScBinaryOp) -> Self {
+        Self {
+            text: match op {
+                ScBinaryOp::Pipeline => "|>",
+                ScBinaryOp::Cons | ScBinaryOp::Bind => "::",
+            },
+            precedence: op.precedence(),
+            associativity: if op.is_right_assoc() {
+                Associativity::Right
+            } else {
+                Associativity::Left
+            },
+        }
+    }
+}
+
+/// One rewrite a pass applied. Spans are byte ranges in the original source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rewrite {
+    pub kind: RewriteKind,
+    /// The whole construct: `a |> f`, `F<_>`, `F<A>`.
+    pub span: Range<usize>,
+    /// The operator (`|>`, `::`), or the HKT parameter's name.
+    pub head: Range<usize>,
+    /// `[left, right]` for an operator, `[args]` for an HKT usage (`A, B` in
+    /// `F<A, B>`), and empty for an HKT declaration.
+    pub operands: Vec<Range<usize>>,
+}
+
+/// Everything the preprocessor learned about a file besides the rewritten text.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PreprocessReport {
+    pub diagnostics: Vec<Diagnostic>,
+    /// One entry per rewrite, in the order the passes applied them.
+    pub rewrites: Vec<Rewrite>,
+    /// Maps positions in the rewritten text back to the original source.
+    pub offsets: OffsetMap,
+}
+
+impl PreprocessReport {
+    /// Number of rewrites of the given kind.
+    pub fn count(&self, kind: RewriteKind) -> usize {
+        self.rewrites.iter().filter(|r| r.kind == kind).count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|d| d.severity == Severity::Error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sc_ast::ScSyntax;
+
+    use super::*;
+    use crate::preprocess::preprocess_with_report;
+
+    #[test]
+    fn missing_operand_is_underlined() {
+        let source = "const a = 1;\nconst x = |> f;\n";
+        let report = preprocess_with_report(source, &ScSyntax::default()).report;
+        assert_eq!(report.diagnostics.len(), 1, "{:?}", report.diagnostics);
+        assert_eq!(
+            render_diagnostic("input.ts", source, &report.diagnostics[0]),
+            "\
+error: pipeline requires a left operand
+ --> input.ts:2:11
+  |
+2 | const x = |> f;
+  |           ^^"
+        );
+    }
+
+    #[test]
+    fn carets_follow_tabs_and_stop_at_the_end_of_the_line() {
+        let source = "\u{FEFF}\tlet é = [\n  1];";
+        let span = source.find('[').unwrap()..source.len();
+        let diag = Diagnostic::warning("spans lines", span);
+        assert_eq!(
+            render_diagnostic("input.ts", source, &diag),
+            "warning: spans lines\n --> input.ts:1:10\n  |\n1 | \tlet é = [\n  | \t        ^"
+        );
+    }
+
+    #[test]
+    fn colored_render_paints_the_severity_and_carets() {
+        let source = "a |> ;";
+        let error = Diagnostic::error("no operand", 2..4);
+        assert_eq!(
+            render_diagnostic_colored("input.ts", source, &error),
+            "\x1b[1;31merror\x1b[0m: no operand\n --> input.ts:1:3\n  |\n1 | a |> ;\n  |   \x1b[1;31m^^\x1b[0m"
+        );
+        let warning = Diagnostic::warning("odd", 0..1);
+        let colored = render_diagnostic_colored("input.ts", source, &warning);
+        assert!(
+            colored.starts_with("\x1b[1;33mwarning\x1b[0m: odd"),
+            "{colored:?}"
+        );
+        // Without the codes, the same text.
+        let plain = colored.replace("\x1b[1;33m", "").replace("\x1b[0m", "");
+        assert_eq!(plain, render_diagnostic("input.ts", source, &warning));
+    }
+}
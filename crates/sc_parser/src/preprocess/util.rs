@@ -1,56 +1,452 @@
 //! Shared utilities for preprocessing passes.
 
-/// Skip over non-code regions: comments and strings (NOT template literals).
+use std::ops::Range;
+
+/// The sub-range of `range` in `source` with surrounding whitespace and
+/// comments removed. A line comment left in would swallow whatever a rewrite
+/// puts after the operand, and with it the rest of the line.
+pub(super) fn trimmed_range(source: &str, mask: &CodeMask, range: Range<usize>) -> Range<usize> {
+    let bytes = source.as_bytes();
+    let (mut start, mut end) = (range.start, range.end);
+
+    loop {
+        while start < end && bytes[start].is_ascii_whitespace() {
+            start += 1;
+        }
+        if !is_comment_start(bytes, start) {
+            break;
+        }
+        match skip_non_code(bytes, start) {
+            Some(next) if next <= end => start = next,
+            _ => break,
+        }
+    }
+
+    loop {
+        while end > start && bytes[end - 1].is_ascii_whitespace() {
+            end -= 1;
+        }
+        // Only a comment that is the last of the non-code tokens ending at
+        // `end` can be dropped: `"s" // c` keeps the string.
+        let mut run = end;
+        while run > start && !mask.is_code(run - 1) {
+            run -= 1;
+        }
+        let mut last = None;
+        while run < end {
+            last = Some(run);
+            match skip_non_code(bytes, run) {
+                Some(next) if next > run => run = next,
+                _ => break,
+            }
+        }
+        match last {
+            Some(piece) if is_comment_start(bytes, piece) => end = piece,
+            _ => break,
+        }
+    }
+
+    start..end.max(start)
+}
+
+/// The first position at or after `pos` that isn't whitespace or a comment.
+pub(super) fn skip_trivia(bytes: &[u8], mut pos: usize) -> usize {
+    loop {
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if pos >= bytes.len() || !is_comment_start(bytes, pos) {
+            return pos.min(bytes.len());
+        }
+        pos = skip_non_code(bytes, pos).map_or(bytes.len(), |end| end.min(bytes.len()));
+    }
+}
+
+fn is_comment_start(bytes: &[u8], i: usize) -> bool {
+    bytes[i..].starts_with(b"//") || bytes[i..].starts_with(b"/*")
+}
+
+/// Whether `b` can be part of an identifier (any non-ASCII byte counts).
+pub(super) fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || !b.is_ascii()
+}
+
+/// Whether an identifier starting with an uppercase letter (`F`, `Éffect`,
+/// `Ω`) starts at byte `i`, rather than `i` being inside `mapF` or a
+/// multi-byte character.
+pub(super) fn starts_type_name(source: &str, i: usize) -> bool {
+    let bytes = source.as_bytes();
+    if i > 0 && is_ident_byte(bytes[i - 1]) {
+        return false;
+    }
+    // Past that check `i` is a char boundary: a continuation byte always
+    // follows another non-ASCII byte.
+    source[i..].chars().next().is_some_and(char::is_uppercase)
+}
+
+/// Which bytes of a text are code: everything but comments, strings, regex
+/// literals, and the literal text of templates.
+///
+/// A template's `` ` ``, `${` and closing `}` count as code, so a scan in
+/// either direction sees an interpolation as a bracketed group. Build one per
+/// text and hand it to every scanner over that text, so they all agree on
+/// where a string or comment ends.
+///
+/// One bit per byte, so the mask is an eighth the size of the text rather
+/// than a second copy of it.
+pub(super) struct CodeMask {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl CodeMask {
+    pub fn new(bytes: &[u8]) -> Self {
+        Self::scan(bytes, |_| {})
+    }
+
+    /// [`CodeMask::new`], also handing the range of each comment to
+    /// `on_comment`, in order.
+    pub fn scan(bytes: &[u8], mut on_comment: impl FnMut(Range<usize>)) -> Self {
+        let mut mask = Self {
+            words: vec![u64::MAX; bytes.len().div_ceil(64)],
+            len: bytes.len(),
+        };
+        let mut template_state = TemplateState::new();
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let literal_text = template_state.in_literal_part()
+                && bytes[i] != b'`'
+                && !bytes[i..].starts_with(b"${");
+            match template_state.handle_char(bytes, i) {
+                HandleResult::Skip(n) => {
+                    if literal_text {
+                        mask.clear(i..i + n);
+                    }
+                    i += n;
+                    continue;
+                }
+                HandleResult::Process => {}
+            }
+
+            if let Some(end) = skip_non_code(bytes, i) {
+                let end = end.min(bytes.len());
+                mask.clear(i..end);
+                // The line break ending a `//` comment isn't part of it, and
+                // the operator pass needs to see it.
+                if bytes[i..].starts_with(b"//") && bytes[end - 1] == b'\n' {
+                    mask.words[(end - 1) / 64] |= 1 << ((end - 1) % 64);
+                    on_comment(i..end - 1);
+                } else if is_comment_start(bytes, i) {
+                    on_comment(i..end);
+                }
+                i = end;
+                continue;
+            }
+            i += 1;
+        }
+
+        mask
+    }
+
+    /// Whether byte `i` is code. Positions past the end are not.
+    pub fn is_code(&self, i: usize) -> bool {
+        i < self.len && self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    /// Mark the bytes in `range` as not code.
+    fn clear(&mut self, range: Range<usize>) {
+        for i in range {
+            self.words[i / 64] &= !(1 << (i % 64));
+        }
+    }
+}
+
+/// The bracket matching the `(`, `[` or `{` at `at`, scanning forward, or
+/// the `)`, `]` or `}` at `at`, scanning back. Brackets outside code don't
+/// count. `None` if the group is unbalanced, or closed by the wrong kind of
+/// bracket.
+pub(super) fn matching_bracket(bytes: &[u8], mask: &CodeMask, at: usize) -> Option<usize> {
+    let forward = is_open_bracket(*bytes.get(at)?);
+    counterpart(bytes[at])?;
+    let mut expected = Vec::new();
+    let mut i = at;
+    loop {
+        if mask.is_code(i) {
+            if let Some(other) = counterpart(bytes[i]) {
+                if is_open_bracket(bytes[i]) == forward {
+                    expected.push(other);
+                } else if expected.pop() != Some(bytes[i]) {
+                    return None;
+                } else if expected.is_empty() {
+                    return Some(i);
+                }
+            }
+        }
+        if forward {
+            i += 1;
+            if i == bytes.len() {
+                return None;
+            }
+        } else {
+            i = i.checked_sub(1)?;
+        }
+    }
+}
+
+/// Where the operand ending at `pos` ends, with the whitespace before `pos`
+/// left out.
+pub(super) fn boundary_before(bytes: &[u8], pos: usize) -> usize {
+    pos - bytes[..pos]
+        .iter()
+        .rev()
+        .take_while(|b| b.is_ascii_whitespace())
+        .count()
+}
+
+/// Where the operand starting at `pos` starts, with the whitespace after
+/// `pos` left out.
+pub(super) fn boundary_after(bytes: &[u8], pos: usize) -> usize {
+    pos + bytes[pos..]
+        .iter()
+        .take_while(|b| b.is_ascii_whitespace())
+        .count()
+}
+
+fn is_open_bracket(b: u8) -> bool {
+    matches!(b, b'(' | b'[' | b'{')
+}
+
+fn counterpart(b: u8) -> Option<u8> {
+    match b {
+        b'(' => Some(b')'),
+        b'[' => Some(b']'),
+        b'{' => Some(b'}'),
+        b')' => Some(b'('),
+        b']' => Some(b'['),
+        b'}' => Some(b'{'),
+        _ => None,
+    }
+}
+
+/// Skip over non-code regions: comments, strings and regex literals (NOT
+/// template literals).
 ///
 /// Template literals require special handling because they contain `${...}`
 /// interpolations with real code that needs processing. Callers must handle
-/// template literals separately using `TemplateState`.
+/// template literals separately using `TemplateState`, or use [`CodeMask`],
+/// which does both.
 ///
 /// Returns `Some(new_position)` if `i` is at the start of a non-code region,
 /// where `new_position` is the first character after the region.
 /// Returns `None` if `i` is not at the start of a non-code region.
-pub(super) fn skip_non_code(chars: &[char], i: usize) -> Option<usize> {
-    if i >= chars.len() {
-        return Some(chars.len());
+pub(super) fn skip_non_code(bytes: &[u8], i: usize) -> Option<usize> {
+    if i >= bytes.len() {
+        return Some(bytes.len());
     }
 
     // Single-line comment
-    if chars[i] == '/' && i + 1 < chars.len() && chars[i + 1] == '/' {
+    if bytes[i] == b'/' && i + 1 < bytes.len() && bytes[i + 1] == b'/' {
         let mut j = i + 2;
-        while j < chars.len() && chars[j] != '\n' {
+        while j < bytes.len() && bytes[j] != b'\n' {
             j += 1;
         }
         return Some(j + 1);
     }
 
     // Multi-line comment
-    if chars[i] == '/' && i + 1 < chars.len() && chars[i + 1] == '*' {
+    if bytes[i] == b'/' && i + 1 < bytes.len() && bytes[i + 1] == b'*' {
         let mut j = i + 2;
-        while j + 1 < chars.len() {
-            if chars[j] == '*' && chars[j + 1] == '/' {
+        while j + 1 < bytes.len() {
+            if bytes[j] == b'*' && bytes[j + 1] == b'/' {
                 return Some(j + 2);
             }
             j += 1;
         }
-        return Some(chars.len());
+        return Some(bytes.len());
+    }
+
+    // Regex literal - must check before treating `/` as division
+    if bytes[i] == b'/' && is_regex_context(bytes, i) {
+        if let Some(end) = scan_regex_literal(bytes, i) {
+            return Some(end);
+        }
     }
 
     // String literals (NOT template literals - those need special handling)
-    if chars[i] == '"' || chars[i] == '\'' {
-        let quote = chars[i];
+    if bytes[i] == b'"' || bytes[i] == b'\'' {
+        let quote = bytes[i];
         let mut j = i + 1;
-        while j < chars.len() && chars[j] != quote {
-            if chars[j] == '\\' {
+        while j < bytes.len() && bytes[j] != quote {
+            if bytes[j] == b'\\' {
                 j += 1;
             }
             j += 1;
         }
-        return Some(if j < chars.len() { j + 1 } else { j });
+        return Some(if j < bytes.len() { j + 1 } else { j });
     }
 
     None
 }
 
+/// Determine if `/` at position `i` starts a regex literal based on preceding context.
+/// A `/` starts a regex when it appears where an expression is expected (not after an operand).
+fn is_regex_context(bytes: &[u8], i: usize) -> bool {
+    // Find the last non-whitespace character/token before position i
+    let mut j = i;
+    while j > 0 && bytes[j - 1].is_ascii_whitespace() {
+        j -= 1;
+    }
+
+    if j == 0 {
+        // Start of input - regex context
+        return true;
+    }
+
+    let prev = bytes[j - 1];
+
+    // `</` closes a JSX element.
+    if prev == b'<' && j == i {
+        return false;
+    }
+
+    // After these characters, `/` starts a regex (expression expected)
+    if matches!(
+        prev,
+        b'(' | b'['
+            | b'{'
+            | b','
+            | b';'
+            | b':'
+            | b'='
+            | b'!'
+            | b'&'
+            | b'|'
+            | b'?'
+            | b'+'
+            | b'-'
+            | b'*'
+            | b'%'
+            | b'^'
+            | b'~'
+            | b'<'
+            | b'>'
+    ) {
+        return true;
+    }
+
+    // Check for keywords that precede expressions
+    // We need to look back to see if we're after a keyword like `return`, `case`, etc.
+    if is_ident_byte(prev) && !prev.is_ascii_digit() {
+        // Scan backwards to get the full word
+        let mut word_start = j - 1;
+        while word_start > 0 && is_ident_byte(bytes[word_start - 1]) {
+            word_start -= 1;
+        }
+
+        let word = &bytes[word_start..j];
+        // Keywords after which `/` starts a regex
+        let keyword = matches!(
+            word,
+            b"return"
+                | b"case"
+                | b"throw"
+                | b"in"
+                | b"of"
+                | b"typeof"
+                | b"void"
+                | b"delete"
+                | b"new"
+                | b"else"
+                | b"do"
+                | b"instanceof"
+                | b"yield"
+                | b"await"
+        );
+        return keyword && is_keyword_position(bytes, word_start, word);
+    }
+
+    // After `)`, `]`, `}`, identifier, number, string - it's division
+    // (prev would be alphanumeric or one of these closing brackets)
+    false
+}
+
+/// Whether `word`, spelled like a keyword at `start`, is one there: not a
+/// property name (`a.return / 2`), and `of` only after a `for` binding.
+/// `yield` and `await` always are: files parse as modules, where neither can
+/// name a variable.
+fn is_keyword_position(bytes: &[u8], start: usize, word: &[u8]) -> bool {
+    let mut j = start;
+    while j > 0 && bytes[j - 1].is_ascii_whitespace() {
+        j -= 1;
+    }
+    let prev = j.checked_sub(1).map(|j| bytes[j]);
+    if prev == Some(b'.') {
+        return false;
+    }
+    word != b"of" || prev.is_some_and(|b| is_ident_byte(b) || b == b']' || b == b'}')
+}
+
+/// Scan a regex literal starting at position `i`, returning the position after the closing `/` and flags.
+/// Returns None if this doesn't look like a valid regex literal.
+fn scan_regex_literal(bytes: &[u8], i: usize) -> Option<usize> {
+    if i >= bytes.len() || bytes[i] != b'/' {
+        return None;
+    }
+
+    let mut j = i + 1;
+
+    // Scan the regex body - look for closing `/` (not escaped, not in character class)
+    let mut in_char_class = false;
+
+    while j < bytes.len() {
+        let c = bytes[j];
+
+        // Regex literals cannot span unescaped newlines
+        if c == b'\n' || c == b'\r' {
+            return None;
+        }
+
+        // Handle escape sequences
+        if c == b'\\' && j + 1 < bytes.len() {
+            j += 2;
+            continue;
+        }
+
+        // Handle character classes
+        if c == b'[' && !in_char_class {
+            in_char_class = true;
+            j += 1;
+            continue;
+        }
+        if c == b']' && in_char_class {
+            in_char_class = false;
+            j += 1;
+            continue;
+        }
+
+        // Found the closing `/` (not inside character class)
+        if c == b'/' && !in_char_class {
+            j += 1;
+            // Scan optional flags: g, i, m, s, u, y, d, v
+            while j < bytes.len()
+                && matches!(
+                    bytes[j],
+                    b'g' | b'i' | b'm' | b's' | b'u' | b'y' | b'd' | b'v'
+                )
+            {
+                j += 1;
+            }
+            return Some(j);
+        }
+
+        j += 1;
+    }
+
+    // No closing `/` found
+    None
+}
+
 /// State for tracking template literal nesting.
 ///
 /// Each entry in the stack represents a template literal, with the value being
@@ -68,13 +464,11 @@ impl TemplateState {
     }
 
     /// Check if we're currently inside a template literal (either literal part or interpolation).
-    #[allow(dead_code)]
     pub fn in_template(&self) -> bool {
         !self.stack.is_empty()
     }
 
     /// Check if we're in the literal part of a template (should skip this character).
-    #[allow(dead_code)]
     pub fn in_literal_part(&self) -> bool {
         self.stack.last().is_some_and(|&d| d == 0)
     }
@@ -82,10 +476,10 @@ impl TemplateState {
     /// Handle a character and return whether it was consumed by template handling.
     /// If true, the caller should continue to the next character.
     /// If false, the caller should process this character normally.
-    pub fn handle_char(&mut self, chars: &[char], i: usize) -> HandleResult {
+    pub fn handle_char(&mut self, bytes: &[u8], i: usize) -> HandleResult {
         if self.stack.is_empty() {
             // Not in a template literal - check for start
-            if chars[i] == '`' {
+            if bytes[i] == b'`' {
                 self.stack.push(0); // Start in literal part
                 return HandleResult::Skip(1);
             }
@@ -96,15 +490,15 @@ impl TemplateState {
 
         if depth == 0 {
             // In literal part - skip until ${ or closing `
-            if chars[i] == '\\' && i + 1 < chars.len() {
+            if bytes[i] == b'\\' && i + 1 < bytes.len() {
                 return HandleResult::Skip(2);
             }
-            if chars[i] == '$' && i + 1 < chars.len() && chars[i + 1] == '{' {
+            if bytes[i] == b'$' && i + 1 < bytes.len() && bytes[i + 1] == b'{' {
                 // Start of interpolation
                 *self.stack.last_mut().unwrap() = 1;
                 return HandleResult::Skip(2);
             }
-            if chars[i] == '`' {
+            if bytes[i] == b'`' {
                 // End of this template literal
                 self.stack.pop();
                 return HandleResult::Skip(1);
@@ -114,19 +508,19 @@ impl TemplateState {
         }
 
         // In interpolation - track braces and nested templates
-        if chars[i] == '`' {
+        if bytes[i] == b'`' {
             // Nested template literal
             self.stack.push(0);
             return HandleResult::Skip(1);
         }
 
-        if chars[i] == '{' {
+        if bytes[i] == b'{' {
             *self.stack.last_mut().unwrap() += 1;
             // Still process this character (could be part of an object literal in code)
             return HandleResult::Process;
         }
 
-        if chars[i] == '}' {
+        if bytes[i] == b'}' {
             let d = self.stack.last_mut().unwrap();
             *d -= 1;
             if *d == 0 {
@@ -150,7 +544,59 @@ pub(super) enum HandleResult {
     Process,
 }
 
-/// Convert a character index to a byte offset in UTF-8.
-pub(super) fn char_offset_to_byte(chars: &[char], char_idx: usize) -> usize {
-    chars[..char_idx].iter().map(|c| c.len_utf8()).sum()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matching(source: &str, at: usize) -> Option<usize> {
+        matching_bracket(source.as_bytes(), &CodeMask::new(source.as_bytes()), at)
+    }
+
+    #[test]
+    fn matches_nested_groups_in_both_directions() {
+        let source = "f(a[0], { b: (c) })";
+        let close = source.len() - 1;
+        assert_eq!(matching(source, 1), Some(close));
+        assert_eq!(matching(source, close), Some(1));
+        assert_eq!(matching(source, 3), Some(5));
+        assert_eq!(matching(source, 8), Some(close - 1));
+        assert_eq!(matching(source, 0), None);
+    }
+
+    #[test]
+    fn brackets_outside_code_do_not_count() {
+        let source = "(\")\" /* ) */ `${x})`)";
+        assert_eq!(matching(source, 0), Some(source.len() - 1));
+        assert_eq!(matching(source, source.len() - 1), Some(0));
+        // An interpolation is a group of its own.
+        let open = source.find('{').unwrap();
+        assert_eq!(matching(source, open), Some(open + 2));
+    }
+
+    #[test]
+    fn unbalanced_groups_have_no_match() {
+        assert_eq!(matching("(a, b", 0), None);
+        assert_eq!(matching("a, b)", 4), None);
+        assert_eq!(matching("(a]", 0), None);
+        assert_eq!(matching("[a)", 2), None);
+        assert_eq!(matching("f((a)", 1), None);
+        assert_eq!(matching("(a))", 2), Some(0));
+        assert_eq!(matching("(a))", 3), None);
+    }
+
+    #[test]
+    fn boundaries_leave_out_whitespace() {
+        let bytes = b"a =  \n x";
+        assert_eq!(boundary_before(bytes, 6), 3);
+        assert_eq!(boundary_after(bytes, 3), 7);
+        assert_eq!(boundary_after(bytes, 8), 8);
+    }
+
+    #[test]
+    fn scan_reports_comments_but_not_strings() {
+        let source = "a // one\n\"// no\" /* two */ `/* no ${b /* three */}` /x/ // four";
+        let mut comments = Vec::new();
+        CodeMask::scan(source.as_bytes(), |range| comments.push(&source[range]));
+        assert_eq!(comments, ["// one", "/* two */", "/* three */", "// four"]);
+    }
 }
@@ -0,0 +1,89 @@
+//! A `PreprocessPass` defined outside the crate, run alongside the built-in
+//! ones through the public API.
+
+use sc_ast::ScSyntax;
+use sc_parser::preprocess::{
+    default_passes, preprocess_with_passes, PreprocessPass, PreprocessReport, Rewrite, RewriteKind,
+};
+use sc_parser::ParseSession;
+
+/// Uppercases the word after each `shout!`, dropping the marker:
+/// `shout!hello` becomes `HELLO`.
+struct Shout;
+
+impl PreprocessPass for Shout {
+    fn name(&self) -> &str {
+        "shout"
+    }
+
+    fn run(&self, text: &mut String, _syntax: &ScSyntax, report: &mut PreprocessReport) {
+        while let Some(start) = text.find("shout!") {
+            let word_start = start + "shout!".len();
+            let word_end = text[word_start..]
+                .find(|c: char| !c.is_alphanumeric())
+                .map_or(text.len(), |len| word_start + len);
+            let upper = text[word_start..word_end].to_uppercase();
+            report.rewrites.push(Rewrite {
+                kind: RewriteKind::Custom("shout"),
+                span: report.offsets.to_original_range(start..word_end),
+                head: report.offsets.to_original_range(start..word_start),
+                operands: vec![report.offsets.to_original_range(word_start..word_end)],
+            });
+            report.offsets.splice(text, start..word_end, &upper);
+        }
+    }
+}
+
+fn passes() -> Vec<Box<dyn PreprocessPass>> {
+    let mut passes = default_passes();
+    passes.insert(0, Box::new(Shout));
+    passes
+}
+
+#[test]
+fn custom_pass_runs_before_the_built_in_ones() {
+    let source = "const a = shout!x |> f;\nconst b = shout!y :: [];\n";
+    let out = preprocess_with_passes(source, &ScSyntax::default(), &passes());
+    assert_eq!(
+        out.code,
+        "const a = __binop__(X, \"|>\", f);\nconst b = __binop__(Y, \"::\", []);\n"
+    );
+
+    let shouts: Vec<_> = out
+        .report
+        .rewrites
+        .iter()
+        .filter(|r| r.kind == RewriteKind::Custom("shout"))
+        .map(|r| &source[r.span.clone()])
+        .collect();
+    assert_eq!(shouts, ["shout!x", "shout!y"]);
+    assert_eq!(out.report.count(RewriteKind::Pipeline), 1);
+
+    // The built-in rewrites still map back through the custom pass's edits.
+    let pipeline = out
+        .report
+        .rewrites
+        .iter()
+        .find(|r| r.kind == RewriteKind::Pipeline)
+        .unwrap();
+    assert_eq!(&source[pipeline.span.clone()], "shout!x |> f");
+    let f = out.code.find("f)").unwrap();
+    assert_eq!(
+        out.report.offsets.to_original(f),
+        source.find("f;").unwrap()
+    );
+}
+
+#[test]
+fn parse_session_uses_its_passes() {
+    let session = ParseSession::new().with_passes(passes());
+    let parsed = session
+        .parse("const a = shout!x;", "a.ts", &ScSyntax::default(), None)
+        .unwrap();
+    assert_eq!(parsed.preprocessed_source(), "const a = X;");
+
+    let plain = ParseSession::new()
+        .parse("const a = shout!x;", "a.ts", &ScSyntax::default(), None)
+        .unwrap();
+    assert_eq!(plain.preprocessed_source(), "const a = shout!x;");
+}
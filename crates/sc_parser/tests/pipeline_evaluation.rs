@@ -0,0 +1,160 @@
+//! Desugared pipelines evaluate each segment exactly once, left to right.
+//!
+//! Random `|>` chains over calls `s0()`, `s1()`, ... are preprocessed, and the
+//! output's `__binop__` calls are run by a small interpreter with JS argument
+//! order. Every call must run once, in source order, and the result must be
+//! each segment applied to the one before it.
+
+use proptest::prelude::*;
+use sc_ast::ScSyntax;
+use sc_parser::preprocess::preprocess;
+
+/// One `|>` operand: a call, or a parenthesized chain of its own.
+#[derive(Debug, Clone)]
+enum Segment {
+    Call,
+    Group(Vec<Segment>),
+}
+
+/// What evaluating an expression gives: the value of call `sN()`, or a
+/// function applied to a value by `|>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    Call(usize),
+    Applied(Box<Value>, Box<Value>),
+}
+
+fn chain() -> impl Strategy<Value = Vec<Segment>> {
+    let segment = Just(Segment::Call).prop_recursive(3, 24, 4, |inner| {
+        prop_oneof![
+            3 => Just(Segment::Call),
+            1 => prop::collection::vec(inner, 1..4).prop_map(Segment::Group),
+        ]
+    });
+    prop::collection::vec(segment, 1..6)
+}
+
+fn separators() -> impl Strategy<Value = Vec<&'static str>> {
+    prop::collection::vec(
+        prop::sample::select(vec![" |> ", "|>", "\n  |> ", " /* |> */ |> "]),
+        1..8,
+    )
+}
+
+/// Render a chain as source, numbering calls in source order, and return
+/// the value the chain should evaluate to.
+fn render(chain: &[Segment], seps: &[&str], source: &mut String, calls: &mut usize) -> Value {
+    let mut value = None;
+    for segment in chain {
+        if value.is_some() {
+            source.push_str(seps[*calls % seps.len()]);
+        }
+        let operand = match segment {
+            Segment::Call => {
+                source.push_str(&format!("s{calls}()"));
+                *calls += 1;
+                Value::Call(*calls - 1)
+            }
+            Segment::Group(inner) => {
+                source.push('(');
+                let v = render(inner, seps, source, calls);
+                source.push(')');
+                v
+            }
+        };
+        value = Some(match value {
+            None => operand,
+            Some(arg) => Value::Applied(Box::new(operand), Box::new(arg)),
+        });
+    }
+    value.expect("chains are never empty")
+}
+
+/// Evaluates `__binop__(l, "|>", r)`, `sN()` and `(e)`, logging each call.
+struct Interpreter<'a> {
+    text: &'a str,
+    pos: usize,
+    log: Vec<usize>,
+}
+
+impl Interpreter<'_> {
+    fn skip_trivia(&mut self) {
+        loop {
+            let rest = &self.text[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            match trimmed.strip_prefix("/*").and_then(|c| c.find("*/")) {
+                Some(end) => self.pos += end + 4,
+                None => break,
+            }
+        }
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_trivia();
+        let found = self.text[self.pos..].starts_with(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    fn expect(&mut self, token: &str) {
+        assert!(
+            self.eat(token),
+            "expected {token:?} at {:?}",
+            &self.text[self.pos..]
+        );
+    }
+
+    fn expr(&mut self) -> Value {
+        if self.eat("__binop__(") {
+            // Arguments run left to right, then `__binop__` applies `|>`.
+            let arg = self.expr();
+            self.expect(",");
+            self.expect("\"|>\"");
+            self.expect(",");
+            let function = self.expr();
+            self.expect(")");
+            Value::Applied(Box::new(function), Box::new(arg))
+        } else if self.eat("(") {
+            let value = self.expr();
+            self.expect(")");
+            value
+        } else {
+            self.expect("s");
+            let digits = self.text[self.pos..]
+                .bytes()
+                .take_while(u8::is_ascii_digit)
+                .count();
+            let n = self.text[self.pos..self.pos + digits].parse().unwrap();
+            self.pos += digits;
+            self.expect("()");
+            self.log.push(n);
+            Value::Call(n)
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn each_segment_runs_once_left_to_right(chain in chain(), seps in separators()) {
+        let mut expr = String::new();
+        let mut calls = 0;
+        let expected = render(&chain, &seps, &mut expr, &mut calls);
+        let source = format!("const r = {expr};\n");
+
+        let code = preprocess(&source, &ScSyntax::default());
+        let body = code
+            .strip_prefix("const r = ")
+            .and_then(|c| c.strip_suffix(";\n"))
+            .unwrap_or_else(|| panic!("statement not kept: {code:?}"));
+
+        let mut interpreter = Interpreter { text: body, pos: 0, log: Vec::new() };
+        let value = interpreter.expr();
+        interpreter.skip_trivia();
+        prop_assert_eq!(interpreter.pos, body.len(), "trailing text in {:?}", code);
+        prop_assert_eq!(interpreter.log, (0..calls).collect::<Vec<_>>(), "{:?}", code);
+        prop_assert_eq!(value, expected, "{:?}", code);
+    }
+}
@@ -0,0 +1,190 @@
+//! `|>` and `::` group the way a precedence parser would group them.
+//!
+//! Random expressions mixing both operators, parentheses and literals are
+//! preprocessed, and the tree read back from the output's `__binop__` calls
+//! is compared with the one a small Pratt parser builds from the same
+//! expression, using the table on `ScBinaryOp`: `|>` at precedence 1,
+//! left-associative, and `::` at 5, right-associative.
+
+use proptest::prelude::*;
+use sc_ast::{ScBinaryOp, ScSyntax};
+use sc_parser::preprocess::preprocess;
+
+/// An operand as written: a literal, or a parenthesized expression.
+#[derive(Debug, Clone)]
+enum Atom {
+    Literal(&'static str),
+    Paren(Box<Flat>),
+}
+
+/// An expression as written: `atoms[0] ops[0] atoms[1] ...`.
+#[derive(Debug, Clone)]
+struct Flat {
+    atoms: Vec<Atom>,
+    ops: Vec<ScBinaryOp>,
+}
+
+/// The grouping, with parentheses dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Tree {
+    Literal(String),
+    Binary(ScBinaryOp, Box<Tree>, Box<Tree>),
+}
+
+const LITERALS: &[&str] = &[
+    "x", "xs", "f", "$g", "1", "42", "[]", "[1]", "\"|>\"", "'::'", "`a::b`", "null",
+];
+
+fn flat() -> impl Strategy<Value = Flat> {
+    let atom = prop::sample::select(LITERALS)
+        .prop_map(Atom::Literal)
+        .prop_recursive(3, 32, 6, |inner| {
+            prop_oneof![
+                4 => prop::sample::select(LITERALS).prop_map(Atom::Literal),
+                1 => flat_of(inner).prop_map(|f| Atom::Paren(Box::new(f))),
+            ]
+        });
+    flat_of(atom)
+}
+
+fn flat_of(atom: impl Strategy<Value = Atom>) -> impl Strategy<Value = Flat> {
+    prop::collection::vec(
+        (
+            atom,
+            prop_oneof![Just(ScBinaryOp::Pipeline), Just(ScBinaryOp::Cons)],
+        ),
+        1..7,
+    )
+    .prop_map(|pairs| {
+        let (atoms, mut ops): (Vec<_>, Vec<_>) = pairs.into_iter().unzip();
+        ops.pop();
+        Flat { atoms, ops }
+    })
+}
+
+fn separators() -> impl Strategy<Value = Vec<(&'static str, &'static str)>> {
+    prop::collection::vec(
+        prop::sample::select(vec![(" ", " "), ("", ""), ("\n  ", " "), (" ", "\n")]),
+        1..8,
+    )
+}
+
+fn render(flat: &Flat, seps: &[(&str, &str)], source: &mut String, count: &mut usize) {
+    for (i, atom) in flat.atoms.iter().enumerate() {
+        if i > 0 {
+            let (before, after) = seps[*count % seps.len()];
+            *count += 1;
+            source.push_str(before);
+            source.push_str(&flat.ops[i - 1].to_string());
+            source.push_str(after);
+        }
+        match atom {
+            Atom::Literal(text) => source.push_str(text),
+            Atom::Paren(inner) => {
+                source.push('(');
+                render(inner, seps, source, count);
+                source.push(')');
+            }
+        }
+    }
+}
+
+/// The reference grouping.
+fn pratt(flat: &Flat) -> Tree {
+    fn atom(atom: &Atom) -> Tree {
+        match atom {
+            Atom::Literal(text) => Tree::Literal(text.to_string()),
+            Atom::Paren(inner) => pratt(inner),
+        }
+    }
+
+    fn expr(flat: &Flat, pos: &mut usize, min_prec: u8) -> Tree {
+        let mut left = atom(&flat.atoms[*pos]);
+        while let Some(&op) = flat.ops.get(*pos) {
+            let (prec, right_assoc) = (op.precedence(), op.is_right_assoc());
+            if prec < min_prec {
+                break;
+            }
+            *pos += 1;
+            let right = expr(flat, pos, if right_assoc { prec } else { prec + 1 });
+            left = Tree::Binary(op, Box::new(left), Box::new(right));
+        }
+        left
+    }
+
+    expr(flat, &mut 0, 0)
+}
+
+/// Reads back `__binop__(l, "op", r)`, `(e)` and the literals.
+struct Reader<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn rest(&self) -> &str {
+        &self.text[self.pos..]
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.pos += self.rest().len() - self.rest().trim_start().len();
+        let found = self.rest().starts_with(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    fn expect(&mut self, token: &str) {
+        assert!(self.eat(token), "expected {token:?} at {:?}", self.rest());
+    }
+
+    fn tree(&mut self) -> Tree {
+        if self.eat("__binop__(") {
+            let left = self.tree();
+            self.expect(",");
+            let op = if self.eat("\"|>\"") {
+                ScBinaryOp::Pipeline
+            } else {
+                self.expect("\"::\"");
+                ScBinaryOp::Cons
+            };
+            self.expect(",");
+            let right = self.tree();
+            self.expect(")");
+            Tree::Binary(op, Box::new(left), Box::new(right))
+        } else if self.eat("(") {
+            let tree = self.tree();
+            self.expect(")");
+            tree
+        } else {
+            let literal = LITERALS
+                .iter()
+                .filter(|l| self.rest().starts_with(**l))
+                .max_by_key(|l| l.len())
+                .unwrap_or_else(|| panic!("no literal at {:?}", self.rest()));
+            self.pos += literal.len();
+            Tree::Literal(literal.to_string())
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn operators_group_like_a_pratt_parser(flat in flat(), seps in separators()) {
+        let mut expr = String::new();
+        render(&flat, &seps, &mut expr, &mut 0);
+        let source = format!("const r = {expr};\n");
+
+        let code = preprocess(&source, &ScSyntax::default());
+        let body = code
+            .strip_prefix("const r = ")
+            .and_then(|c| c.strip_suffix(";\n"))
+            .unwrap_or_else(|| panic!("statement not kept: {code:?}"));
+
+        let mut reader = Reader { text: body, pos: 0 };
+        let tree = reader.tree();
+        prop_assert_eq!(reader.rest().trim(), "", "trailing text in {:?}", code);
+        prop_assert_eq!(tree, pratt(&flat), "{:?}", code);
+    }
+}
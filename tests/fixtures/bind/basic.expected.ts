@@ -0,0 +1 @@
+const handler = onClick.bind(button);
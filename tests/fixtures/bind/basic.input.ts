@@ -0,0 +1 @@
+const handler = button::onClick;
@@ -0,0 +1 @@
+const render = draw.bind(this.view);
@@ -0,0 +1 @@
+const render = this.view::draw;
@@ -0,0 +1,3 @@
+const list = __binop__(head, "::", tail);
+const withArray = __binop__(x, "::", []);
+const literal = __binop__(1, "::", rest);
@@ -0,0 +1,3 @@
+const list = head :: tail;
+const withArray = x::[];
+const literal = 1::rest;
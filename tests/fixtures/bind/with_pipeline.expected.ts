@@ -0,0 +1 @@
+const run = __binop__(update.bind(model), "|>", schedule);
@@ -0,0 +1 @@
+const run = model::update |> schedule;
@@ -0,0 +1,4 @@
+const list = 1 :: 2 :: 3 :: [];
+const one = x :: [];
+const onto = 0 :: [1, 2];
+const nested = [1] :: [] :: [];
@@ -0,0 +1,24 @@
+const bigints = [
+    1n,
+    2n
+];
+const mixed = [
+    0x1Fn,
+    1_000,
+    1e3,
+    0b101,
+    0o17,
+    .5
+];
+const spread = [
+    10n,
+    ...rest
+];
+const strings = [
+    "aA",
+    'b',
+    `c${d}`
+];
+const regexes = [
+    /x+/g
+];
@@ -0,0 +1,4 @@
+const withTail = 1 :: 2 :: xs;
+const called = head :: rest();
+const concat = a :: [b].concat(c);
+const mixed = f(a) :: g(b) :: [c, ...d];
@@ -0,0 +1,7 @@
+const y = [
+    __binop__(1, "::", xs)
+] as const;
+const z = __binop__(([
+    __binop__(1, "::", xs)
+] as const), "|>", f);
+const pair = (__binop__(head, "::", tail)) as readonly number[];
@@ -0,0 +1,3 @@
+const y = [1 :: xs] as const;
+const z = ([1 :: xs] as const) |> f;
+const pair = (head :: tail) as readonly number[];
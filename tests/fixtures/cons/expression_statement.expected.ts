@@ -0,0 +1,11 @@
+__binop__(x, "::", xs);
+function f() {
+    __binop__(y, "::", ys);
+}
+if (ok) {
+    push(a);
+}
+__binop__(z, "::", zs);
+{
+    __binop__(1, "::", []);
+}
\ No newline at end of file
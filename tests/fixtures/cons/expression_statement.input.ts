@@ -0,0 +1,11 @@
+x :: xs;
+function f() {
+    y :: ys;
+}
+if (ok) {
+    push(a);
+}
+z :: zs;
+{
+    1 :: [];
+}
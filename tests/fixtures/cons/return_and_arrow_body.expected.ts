@@ -0,0 +1,8 @@
+function f(a, b) {
+    return __binop__(a, "::", __binop__(b, "::", []));
+}
+const g = ()=>__binop__(x, "::", ys);
+const h = ()=>__binop__(1, "::", __binop__(2, "::", []));
+const k = (x)=>{
+    return __binop__(x, "::", xs);
+};
@@ -0,0 +1,8 @@
+function f(a, b) {
+    return a :: b :: [];
+}
+const g = () => x :: ys;
+const h = () => 1 :: 2 :: [];
+const k = (x) => {
+    return x :: xs;
+};
@@ -0,0 +1 @@
+const h = __binop__(button, "::", onClick);
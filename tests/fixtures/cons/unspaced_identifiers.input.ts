@@ -0,0 +1 @@
+const h = button::onClick;
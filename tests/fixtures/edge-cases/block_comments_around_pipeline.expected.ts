@@ -0,0 +1,3 @@
+const a = __binop__(x, "|>", f);
+const b = __binop__(xs, "::", ys);
+const c = __binop__(g(y), "|>", h);
@@ -0,0 +1,3 @@
+const a = x /* left */ |> /* mid */ f;
+const b = xs /* the list */ :: /* the rest */ ys;
+const c = g(y /* inside */) |> h /* after */;
@@ -0,0 +1,2 @@
+__binop__(data, "|>", render);
+const x = __binop__(__binop__(items, "|>", sort), "|>", take);
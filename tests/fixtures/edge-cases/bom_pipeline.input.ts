@@ -0,0 +1,2 @@
+﻿data |> render;
+const x = items |> sort |> take;
@@ -0,0 +1,5 @@
+// Nothing here but comments.
+
+/*
+ * const x = a |> f;
+ */
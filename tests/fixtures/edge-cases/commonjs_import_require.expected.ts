@@ -0,0 +1,5 @@
+import path = require("path");
+import type Os = require("os");
+export import Url = require("url");
+const base = __binop__(__binop__(__filename, "|>", path.dirname), "|>", path.basename);
+const parts = __binop__(base, "::", []);
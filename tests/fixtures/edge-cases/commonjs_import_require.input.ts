@@ -0,0 +1,6 @@
+import path = require("path");
+import type Os = require("os");
+export import Url = require("url");
+
+const base = __filename |> path.dirname |> path.basename;
+const parts = base :: [];
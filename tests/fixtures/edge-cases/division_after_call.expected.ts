@@ -0,0 +1,2 @@
+const scaled = __binop__(foo() / bar, "|>", g);
+const half = __binop__(obj.size() / 2, "|>", Math.floor);
@@ -0,0 +1,2 @@
+const scaled = foo() / bar |> g;
+const half = obj.size() / 2 |> Math.floor;
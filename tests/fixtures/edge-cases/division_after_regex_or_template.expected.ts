@@ -0,0 +1,2 @@
+const hits = __binop__(/a|b/g / 2, "|>", f);
+const loud = __binop__(`${word}` / 1, "|>", shout);
@@ -0,0 +1,2 @@
+const hits = /a|b/g / 2 |> f;
+const loud = `${word}` / 1 |> shout;
@@ -0,0 +1,2 @@
+const ratio = __binop__(a[i] / b, "|>", f);
+const nested = __binop__(grid[r][c] / total, "|>", round);
@@ -0,0 +1,2 @@
+const ratio = a[i] / b |> f;
+const nested = grid[r][c] / total |> round;
@@ -0,0 +1,9 @@
+const x = __binop__(count / total, "|>", pct);
+const half = __binop__(returnValue / 2, "|>", f);
+const share = __binop__(stats.in / 2, "|>", pct / 100);
+const ratio = __binop__(iter.return / 2, "|>", scale / 3);
+const of = 8;
+const quarter = __binop__(of / 4, "|>", f / 2);
+for (const m of /a|b/g.exec(s) ?? []){
+    __binop__(m, "|>", log);
+}
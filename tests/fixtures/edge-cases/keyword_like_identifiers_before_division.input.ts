@@ -0,0 +1,9 @@
+const x = count / total |> pct;
+const half = returnValue / 2 |> f;
+const share = stats.in / 2 |> pct / 100;
+const ratio = iter.return / 2 |> scale / 3;
+const of = 8;
+const quarter = of / 4 |> f / 2;
+for (const m of /a|b/g.exec(s) ?? []) {
+    m |> log;
+}
@@ -0,0 +1,2 @@
+const total = __binop__(__binop__(items, "|>", prices), "|>", sum);
+const list = __binop__(head, "::", tail);
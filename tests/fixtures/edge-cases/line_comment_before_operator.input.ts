@@ -0,0 +1,7 @@
+const total = items // every item
+  |> prices // then their prices
+  |> sum;
+
+const list = head // first
+  // and then
+  :: tail;
@@ -0,0 +1,5 @@
+const total = __binop__(__binop__(items, "|>", map(price)), "|>", sum);
+render(total);
+log("start");
+__binop__(queue, "|>", drain);
+const name = __binop__(user.profile.name, "|>", trim);
@@ -0,0 +1,11 @@
+const total = items
+  |> map(price)
+  |> sum
+render(total)
+
+log("start")
+queue
+  |> drain
+const name = user
+  .profile
+  .name |> trim
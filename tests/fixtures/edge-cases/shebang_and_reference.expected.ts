@@ -0,0 +1,3 @@
+#!/usr/bin/env node
+/// <reference types="node" />
+const args = __binop__(process.argv, "|>", parseArgs);
@@ -0,0 +1,4 @@
+#!/usr/bin/env node
+/// <reference types="node" />
+// ordinary comments are still dropped
+const args = process.argv |> parseArgs;
@@ -0,0 +1,2 @@
+const a = __binop__("stop; here |> not", "|>", shout);
+const b = __binop__('x, y' + "(z", "|>", f);
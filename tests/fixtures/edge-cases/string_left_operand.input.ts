@@ -0,0 +1,2 @@
+const a = "stop; here |> not" |> shout;
+const b = 'x, y' + "(z" |> f;
@@ -0,0 +1,4 @@
+const a = __binop__(`n=${n}; done`, "|>", f);
+const b = __binop__(`a ${__binop__(x, "|>", g)}, b`, "|>", h);
+const c = __binop__(`${`;`}`, "::", rest);
+const d = __binop__(x + y, "|>", f);
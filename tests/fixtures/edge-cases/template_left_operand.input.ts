@@ -0,0 +1,4 @@
+const a = `n=${n}; done` |> f;
+const b = `a ${x |> g}, b` |> h;
+const c = `${`;`}` :: rest;
+const d = x /* ; |> */ + y |> f;
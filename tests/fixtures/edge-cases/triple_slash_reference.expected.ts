@@ -0,0 +1,3 @@
+/// <reference path="./globals.d.ts" />
+/// <reference types="node" />
+const result = __binop__(__binop__(data, "|>", parse), "|>", validate);
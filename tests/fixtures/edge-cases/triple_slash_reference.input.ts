@@ -0,0 +1,4 @@
+/// <reference path="./globals.d.ts" />
+/// <reference types="node" />
+
+const result = data |> parse |> validate;
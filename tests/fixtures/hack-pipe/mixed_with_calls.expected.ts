@@ -0,0 +1,2 @@
+const total = __binop__(orders.filter(isOpen), "|>", sumBy(price)).toFixed(2);
+const grouped = __binop__(orders, "|>", groupBy(_.customer));
@@ -0,0 +1,2 @@
+const total = orders |> _.filter(isOpen) |> sumBy(price) |> _.toFixed(2);
+const grouped = orders |> groupBy(_.customer);
@@ -0,0 +1,2 @@
+const first = xs[0];
+const last = xs[xs.length - 1];
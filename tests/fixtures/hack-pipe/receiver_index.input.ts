@@ -0,0 +1,2 @@
+const first = xs |> _[0];
+const last = xs |> _[xs.length - 1];
@@ -0,0 +1,3 @@
+const names = users.map(getName);
+const upper = name.trim().toUpperCase();
+const maybe = config.items?.length;
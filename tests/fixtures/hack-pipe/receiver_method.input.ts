@@ -0,0 +1,3 @@
+const names = users |> _.map(getName);
+const upper = name |> _.trim() |> _.toUpperCase();
+const maybe = config.items |> _?.length;
@@ -0,0 +1,3 @@
+const sum = (a + b).toFixed(2);
+const digits = (1).toString();
+const awaited = (await load()).body;
@@ -0,0 +1,3 @@
+const sum = a + b |> _.toFixed(2);
+const digits = 1 |> _.toString();
+const awaited = await load() |> _.body;
@@ -0,0 +1,4 @@
+function go(xs: number[]) {
+    return __pipe__(__pipe__(xs, map((x)=>__pipe__(x, double))), sum);
+}
+const deep = __pipe__((__cons__(a, b)), f(__pipe__(c, g)));
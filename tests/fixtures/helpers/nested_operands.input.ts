@@ -0,0 +1,4 @@
+function go(xs: number[]) {
+  return xs |> map((x) => x |> double) |> sum;
+}
+const deep = (a :: b) |> f(c |> g);
@@ -0,0 +1,3 @@
+const result = __pipe__(__pipe__(data, parse), validate);
+const list = __cons__(1, __cons__(2, __cons__(3, [])));
+const mixed = __pipe__(__cons__(head, tail), render);
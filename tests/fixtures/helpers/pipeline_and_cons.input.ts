@@ -0,0 +1,3 @@
+const result = data |> parse |> validate;
+const list = 1 :: 2 :: 3 :: [];
+const mixed = head :: tail |> render;
@@ -0,0 +1,3 @@
+interface Functor<F> {
+    map: <A, B>(fa: HKT<F, A>, f: (a: A) => B) => HKT<F, B>;
+}
@@ -0,0 +1,3 @@
+type Elem<F, A> = $<F, A> extends Array<infer E> ? E : never;
+type Unwrap<F, T> = T extends $<F, infer A> ? A : T;
+type Nested<F, A> = $<F, A> extends Promise<infer P extends string> ? $<F, P> : $<F, A> extends readonly (infer R)[] ? R : never;
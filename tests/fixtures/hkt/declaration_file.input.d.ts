@@ -0,0 +1,13 @@
+export declare interface Functor<F<_>> {
+  map<A, B>(fa: F<A>, f: (a: A) => B): F<B>;
+}
+export declare function lift<F<_>, A>(f: Functor<F>, a: A): F<A>;
+declare module "hkt-lib" {
+  export interface Monad<M<_>> {
+    flatMap<A, B>(ma: M<A>, f: (a: A) => M<B>): M<B>;
+  }
+  export function pure<M<_>, A>(a: A): M<A>;
+}
+export function overload(x: string): void;
+export function overload(x: number): void;
+export const version: string;
@@ -1,4 +1,3 @@
 interface Nested<F, G> {
-    fg: <A>(fa: $<F, G<A>>) => $<G, F<A>>;
+    fg: <A>(fa: $<F, $<G, A>>) => $<G, $<F, A>>;
 }
-
@@ -0,0 +1,4 @@
+interface Traverse<F<_>, G<_>> {
+  sequence: <A>(fga: F<G<A>>) => G<F<A>>;
+  twice: <A>(ffa: F<F<G< A, >>>) => F<G<Array<A>>>;
+}
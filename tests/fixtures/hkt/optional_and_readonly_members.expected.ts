@@ -0,0 +1,12 @@
+interface Store<F> {
+    readonly current?: $<F, State>;
+    pending?: $<F, Action>;
+    readonly history: $<F, State>[];
+    [key: string]: $<F, unknown> | undefined;
+    readonly [index: number]: $<F, Action>;
+    reset?(initial: $<F, State>): $<F, void>;
+}
+type Options<F> = {
+    readonly retry?: $<F, number>;
+    [name: string]: $<F, string> | undefined;
+};
@@ -0,0 +1,13 @@
+interface Store<F<_>> {
+  readonly current?: F<State>;
+  pending?: F<Action>;
+  readonly history: F<State>[];
+  [key: string]: F<unknown> | undefined;
+  readonly [index: number]: F<Action>;
+  reset?(initial: F<State>): F<void>;
+}
+
+type Options<F<_>> = {
+  readonly retry?: F<number>;
+  [name: string]: F<string> | undefined;
+};
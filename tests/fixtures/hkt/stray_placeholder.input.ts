@@ -0,0 +1,4 @@
+const a = F<_>;
+interface Functor<F<_>> {
+  map: <A, B>(fa: F<A>, f: (a: A) => B) => F<B>;
+}
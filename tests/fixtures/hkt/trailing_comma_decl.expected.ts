@@ -0,0 +1,3 @@
+interface Functor<F> {
+    map: <A, B>(fa: $<F, A>, f: (a: A) => B) => $<F, B>;
+}
@@ -0,0 +1,3 @@
+interface Functor<F<_,>> {
+  map: <A, B>(fa: F<A,>, f: (a: A) => B) => F<B>;
+}
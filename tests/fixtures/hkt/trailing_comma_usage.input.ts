@@ -0,0 +1,3 @@
+interface Bifunctor<F<_, _,>> {
+  bimap: <A, B, C, D>(fab: F<A, B,>, f: (a: A) => C, g: (b: B) => D) => F<C, D>;
+}
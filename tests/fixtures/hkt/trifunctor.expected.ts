@@ -0,0 +1,8 @@
+interface Trifunctor<F> {
+    trimap: <A, B, C, D, E, G>(fa: $<F, A, B, C>, f: (a: A) => D, g: (b: B) => E, h: (c: C) => G) => $<F, D, E, G>;
+    lift: <A>(a: A) => $<F, A, Map<string, A>, [A, A]>;
+    nested: <A>(fa: $<F, $<F, A, A, A>, A, A>) => $<F, A, A, A>;
+}
+function first<F, A>(fa: $<F, A, Record<string, number>, (a: A, b: A) => void>): A {
+    return undefined as unknown as A;
+}
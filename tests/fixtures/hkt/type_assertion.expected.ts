@@ -0,0 +1,7 @@
+function lift<F>(x: unknown) {
+    const y = x as $<F, A>;
+    const z = <$<F, string>>x;
+    const s = (x as $<F, number>) satisfies $<F, number>;
+    return y as $<F, A> | $<F, B>;
+}
+const outside = x as F<A>;
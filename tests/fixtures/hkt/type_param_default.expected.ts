@@ -0,0 +1,10 @@
+interface Foo<F, A = $<F, number>> {
+    value: $<F, A>;
+}
+type Bar<F, B extends $<F, string> = $<F, string>> = $<F, B>;
+class Baz<F, C = $<F, unknown>> {
+    c!: $<F, C>;
+}
+function wrap<F, A = $<F, number>>(fa: A): $<F, A> {
+    return lift(fa);
+}
@@ -0,0 +1,10 @@
+interface Foo<F<_>, A = F<number>> {
+    value: F<A>;
+}
+type Bar<F<_>, B extends F<string> = F<string>> = F<B>;
+class Baz<F<_>, C = F<unknown>> {
+    c!: F<C>;
+}
+function wrap<F<_>, A = F<number>>(fa: A): F<A> {
+    return lift(fa);
+}
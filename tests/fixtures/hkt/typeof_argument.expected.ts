@@ -0,0 +1,9 @@
+declare const config: {
+    nested: string[];
+};
+interface Settings<F> {
+    foo: $<F, typeof config>;
+    entries: $<F, typeof config.nested[number]>;
+    keys: $<F, keyof typeof config>;
+    raw: typeof config;
+}
@@ -0,0 +1,8 @@
+declare const config: { nested: string[] };
+
+interface Settings<F<_>> {
+  foo: F<typeof config>;
+  entries: F<typeof config.nested[number]>;
+  keys: F<keyof typeof config>;
+  raw: typeof config;
+}
@@ -0,0 +1,8 @@
+interface Functor<Éffect> {
+    map: <A, B>(fa: $<Éffect, A>, f: (a: A) => B) => $<Éffect, B>;
+}
+function lift<Ω, E>(ω: Functor<Ω>, fa: $<Ω, E>): $<Ω, [E]> {
+    return ω.map(fa, (e)=>[
+            e
+        ]);
+}
@@ -0,0 +1,8 @@
+// HKT parameters can be named with any uppercase letter, not just ASCII.
+interface Functor<Éffect<_>> {
+  map: <A, B>(fa: Éffect<A>, f: (a: A) => B) => Éffect<B>;
+}
+
+function lift<Ω<_>, E>(ω: Functor<Ω>, fa: Ω<E>): Ω<[E]> {
+  return ω.map(fa, (e) => [e]);
+}
@@ -0,0 +1,8 @@
+const a = obj[__binop__(key, "|>", normalize)];
+const b = table[__binop__(x, "::", xs)];
+const c = __binop__(cache[__binop__(id, "|>", hash)][__binop__(field, "|>", lower)], "|>", read);
+obj[__binop__(name, "|>", slug)] = __binop__(value, "|>", encode);
+const d = grid[__binop__(row, "::", [])][col];
+const e = m[__binop__(k, "|>", f)]?.[__binop__(j, "|>", g)];
+const h = rows[__binop__(i, "|>", clamp)].cells[(__binop__(j, "|>", clamp))];
+const { [__binop__(k, "|>", key)]: v } = src;
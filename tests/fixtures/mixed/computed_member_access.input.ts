@@ -0,0 +1,8 @@
+const a = obj[key |> normalize];
+const b = table[x :: xs];
+const c = cache[id |> hash][field |> lower] |> read;
+obj[name |> slug] = value |> encode;
+const d = grid[row :: []][col];
+const e = m[k |> f]?.[j |> g];
+const h = rows[i |> clamp].cells[(j |> clamp)];
+const { [k |> key]: v } = src;
@@ -0,0 +1,15 @@
+declare global {
+    interface Functor<F> {
+        map<A, B>(fa: $<F, A>, f: (a: A) => B): $<F, B>;
+    }
+    const registry: Map<string, unknown>;
+}
+declare module "lists" {
+    export function cons<A>(head: A, tail: A[]): A[];
+}
+export const doubled = __binop__([
+    1,
+    2,
+    3
+], "|>", map((x)=>x * 2));
+export const list = __binop__(1, "::", __binop__(2, "::", []));
@@ -0,0 +1,2 @@
+const a = __binop__(x, "|>", __binop__(f, "::", c));
+const b = __binop__(xs, "|>", __binop__(map(f), "::", __binop__(ys, "::", zs)));
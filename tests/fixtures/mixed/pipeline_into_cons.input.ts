@@ -0,0 +1,2 @@
+const a = x |> f :: c;
+const b = xs |> map(f) :: ys :: zs;
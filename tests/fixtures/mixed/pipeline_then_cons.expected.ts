@@ -1,2 +1 @@
-__binop__(head, "|>", (__binop__((x)=>x, "::", tail)));
-
+__binop__(head, "|>", ((x)=>__binop__(x, "::", tail)));
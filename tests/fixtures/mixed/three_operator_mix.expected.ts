@@ -0,0 +1,3 @@
+const a = __binop__(__binop__(a, "::", b), "|>", __binop__(c, "::", d));
+const b = __binop__(__binop__(x, "|>", __binop__(g, "::", h)), "|>", k);
+const c = __binop__(__binop__(1, "::", __binop__(2, "::", xs)), "|>", sum);
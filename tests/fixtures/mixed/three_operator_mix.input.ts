@@ -0,0 +1,3 @@
+const a = a :: b |> c :: d;
+const b = x |> g :: h |> k;
+const c = 1 :: 2 :: xs |> sum;
@@ -0,0 +1,14 @@
+function a(x) {
+    return __binop__(x, "|>", f);
+}
+function b(x) {
+    throw __binop__(x, "|>", toError);
+}
+if (ok) run();
+else __binop__(input, "|>", report);
+do __binop__(input, "|>", step);
+while (more)
+if (ok) __binop__(input, "|>", report);
+while(more)__binop__(input, "|>", step);
+for (const x of xs)__binop__(x, "|>", log);
+outer: __binop__(input, "|>", step);
@@ -0,0 +1,12 @@
+function a(x) {
+  return x |> f;
+}
+function b(x) {
+  throw x |> toError;
+}
+if (ok) run(); else input |> report;
+do input |> step; while (more);
+if (ok) input |> report;
+while (more) input |> step;
+for (const x of xs) x |> log;
+outer: input |> step;
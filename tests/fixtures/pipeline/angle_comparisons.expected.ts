@@ -0,0 +1,9 @@
+const r = __binop__((a < b) === (c > d), "|>", Boolean);
+const inRange = __binop__((lo < x) && (x < hi), "|>", Boolean);
+const below = __binop__(count < limit, "|>", Boolean);
+for(let i = 0; i < n; i++){
+    total = __binop__(total, "|>", add(i));
+}
+const cache = __binop__(new Map<string, number>(), "|>", freeze);
+const pick = __binop__(entries, "|>", select<Key, Value>);
+const call = __binop__(f<T>(x), "|>", g);
@@ -0,0 +1,13 @@
+const r = (a < b) === (c > d) |> Boolean;
+
+// Unspaced comparisons aren't type arguments.
+const inRange = (lo<x) && (x<hi) |> Boolean;
+const below = count<limit |> Boolean;
+for (let i = 0; i<n; i++) {
+    total = total |> add(i);
+}
+
+// Type arguments are, commas and all.
+const cache = new Map<string, number>() |> freeze;
+const pick = entries |> select<Key, Value>;
+const call = f<T>(x) |> g;
@@ -0,0 +1,3 @@
+const g = ()=>__binop__(data, "|>", render);
+const h = (a: A): B =>__binop__(__binop__(a.b, "|>", wrap), "|>", finish);
+items.map((x)=>__binop__(x, "|>", double));
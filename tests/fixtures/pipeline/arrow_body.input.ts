@@ -0,0 +1,3 @@
+const g = () => data |> render;
+const h = (a: A): B => a.b |> wrap |> finish;
+items.map(x => x |> double);
@@ -0,0 +1,4 @@
+const x = (__binop__(a, "|>", f)) satisfies Foo;
+const y = (__binop__(input, "|>", parse)) satisfies Record<string, number>;
+const z = (__binop__(a, "|>", f)) as unknown as Foo;
+const w = __binop__((g satisfies (x: number) => number), "|>", h);
@@ -0,0 +1,4 @@
+const x = (a |> f) satisfies Foo;
+const y = (input |> parse) satisfies Record<string, number>;
+const z = (a |> f) as unknown as Foo;
+const w = (g satisfies (x: number) => number) |> h;
@@ -0,0 +1,3 @@
+let a, b;
+a = b = __binop__(raw, "|>", parse);
+obj.x = obj.y = __binop__(1, "|>", double);
@@ -0,0 +1,3 @@
+let a, b;
+a = b = raw |> parse;
+obj.x = obj.y = 1 |> double;
@@ -0,0 +1,3 @@
+const same = __binop__(a === b, "|>", describe);
+const big = __binop__(n >= limit, "|>", Boolean);
+const diff = __binop__(x != y, "|>", not);
@@ -0,0 +1,3 @@
+const same = a === b |> describe;
+const big = n >= limit |> Boolean;
+const diff = x != y |> not;
@@ -0,0 +1,6 @@
+let total = 0;
+total += __binop__(items, "|>", sum);
+total -= __binop__(refunds, "|>", sum);
+total *= __binop__(rate, "|>", normalize);
+cache ??= __binop__(load(), "|>", parse);
+flags |= __binop__(mask, "|>", toBits);
@@ -0,0 +1,6 @@
+let total = 0;
+total += items |> sum;
+total -= refunds |> sum;
+total *= rate |> normalize;
+cache ??= load() |> parse;
+flags |= mask |> toBits;
@@ -0,0 +1,5 @@
+function greet(name = __binop__(rawName, "|>", trim), greeting = "hi") {
+    return `${greeting} ${name}`;
+}
+const area = (w = 1, h = __binop__(size, "|>", halve))=>w * h;
+function pad(width: number = __binop__(cols, "|>", half), fill: string = " ") {}
@@ -0,0 +1,5 @@
+function greet(name = rawName |> trim, greeting = "hi") {
+  return `${greeting} ${name}`;
+}
+const area = (w = 1, h = size |> halve) => w * h;
+function pad(width: number = cols |> half, fill: string = " ") {}
@@ -0,0 +1,5 @@
+const { port = __binop__(env.PORT, "|>", parseInt), host = "localhost" } = config;
+const [first = __binop__(fallback, "|>", head)] = items;
+function connect({ retries = __binop__(base, "|>", double) }) {
+    return retries;
+}
@@ -0,0 +1,5 @@
+const { port = env.PORT |> parseInt, host = "localhost" } = config;
+const [first = fallback |> head] = items;
+function connect({ retries = base |> double }) {
+  return retries;
+}
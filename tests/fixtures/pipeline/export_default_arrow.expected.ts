@@ -0,0 +1 @@
+export default ()=>__binop__(data, "|>", render);
@@ -0,0 +1,3 @@
+for(const key in __binop__(config, "|>", withDefaults)){
+    console.log(key);
+}
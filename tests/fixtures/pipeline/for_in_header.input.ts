@@ -0,0 +1,3 @@
+for (const key in config |> withDefaults) {
+  console.log(key);
+}
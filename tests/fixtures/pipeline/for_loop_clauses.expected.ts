@@ -0,0 +1,8 @@
+for(let i = 0; i < n; i = __binop__(i, "|>", next)){}
+for(let i: number = __binop__(start, "|>", clamp); (__binop__(i, "|>", valid)); i = __binop__(i, "|>", step)){
+    visit(__binop__(i, "|>", label));
+}
+for(let node = head; __binop__(node, "|>", hasNext); node = __binop__(node, "|>", advance)){}
+for(;;){
+    if (__binop__(x, "|>", done)) break;
+}
@@ -0,0 +1,8 @@
+for (let i = 0; i < n; i = i |> next) {}
+for (let i: number = start |> clamp; (i |> valid); i = i |> step) {
+  visit(i |> label);
+}
+for (let node = head; node |> hasNext; node = node |> advance) {}
+for (;;) {
+  if (x |> done) break;
+}
@@ -0,0 +1,3 @@
+for (const x of __binop__(items, "|>", filter(isActive))){
+    console.log(x);
+}
@@ -0,0 +1,3 @@
+for (const x of items |> filter(isActive)) {
+  console.log(x);
+}
@@ -0,0 +1,9 @@
+function* numbers(xs) {
+    yield __binop__(x, "|>", double);
+    yield* __binop__(xs, "|>", evens);
+    const got = yield __binop__(input, "|>", parse);
+}
+async function* stream(source) {
+    yield __binop__(await source.next(), "|>", decode);
+    yield* __binop__(source, "|>", chunks);
+}
@@ -0,0 +1,9 @@
+function* numbers(xs) {
+  yield x |> double;
+  yield* xs |> evens;
+  const got = yield input |> parse;
+}
+async function* stream(source) {
+  yield await source.next() |> decode;
+  yield* source |> chunks;
+}
@@ -0,0 +1,9 @@
+const id = <T,>(x: T)=>__binop__(x, "|>", f);
+const both = <T, U extends object>(x: T, u: U)=>__binop__(x, "|>", g(u));
+const load = async <T,>(url: string)=>__binop__((await fetch(url)), "|>", decode<T>);
+const Item = <T,>({ value }: {
+    value: T;
+})=><b>{__binop__(value, "|>", show)}</b>;
+const list = <T,>(xs: T[])=>{
+    return __binop__(xs, "|>", __binop__(map(show), "::", rest));
+};
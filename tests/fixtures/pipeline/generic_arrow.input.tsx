@@ -0,0 +1,7 @@
+const id = <T,>(x: T) => x |> f;
+const both = <T, U extends object>(x: T, u: U) => x |> g(u);
+const load = async <T,>(url: string) => (await fetch(url)) |> decode<T>;
+const Item = <T,>({ value }: { value: T }) => <b>{value |> show}</b>;
+const list = <T,>(xs: T[]) => {
+  return xs |> map(show) :: rest;
+};
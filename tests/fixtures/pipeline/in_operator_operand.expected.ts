@@ -0,0 +1 @@
+const hasKey = __binop__("k" in o, "|>", Boolean);
@@ -0,0 +1 @@
+const hasKey = "k" in o |> Boolean;
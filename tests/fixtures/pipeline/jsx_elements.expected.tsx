@@ -0,0 +1,8 @@
+const el = <Foo/>;
+const x = __binop__(data, "|>", render);
+const bold = <b>x</b>;
+const half = __binop__(total, "|>", split / 2);
+const icon = <Icon name="a/b" size={16}/>;
+const mounted = __binop__(<Panel title="Stats" onClose={()=>open > 0}/>, "|>", mount);
+const list = __binop__(<ul><li key="a">one, two</li><li key="b">{__binop__(n, "|>", show)}</li></ul>, "|>", render);
+const frag = __binop__(<>a = b</>, "|>", wrap);
@@ -0,0 +1,10 @@
+const el = <Foo /> ; const x = data |> render;
+
+// `/` in a tag is neither a regex nor division.
+const bold = <b>x</b>; const half = total |> split / 2;
+const icon = <Icon name="a/b" size={16} />;
+
+// An element is a whole left operand, attributes and text included.
+const mounted = <Panel title="Stats" onClose={() => open > 0} /> |> mount;
+const list = <ul><li key="a">one, two</li><li key="b">{n |> show}</li></ul> |> render;
+const frag = <>a = b</> |> wrap;
@@ -0,0 +1,9 @@
+let cache: string | undefined;
+let ready = false;
+let name = "";
+cache ??= __binop__(load(), "|>", parse);
+ready ||= __binop__(flags, "|>", isReady);
+name &&= __binop__(__binop__(name, "|>", trim), "|>", capitalize);
+config.retries ??= __binop__(defaults, "|>", pick("retries"));
+state[key] ||= __binop__(a || b, "|>", fallback);
+items &&= __binop__(__binop__(head, "::", tail), "|>", compact);
@@ -0,0 +1,10 @@
+let cache: string | undefined;
+let ready = false;
+let name = "";
+
+cache ??= load() |> parse;
+ready ||= flags |> isReady;
+name &&= name |> trim |> capitalize;
+config.retries ??= defaults |> pick("retries");
+state[key] ||= a || b |> fallback;
+items &&= head :: tail |> compact;
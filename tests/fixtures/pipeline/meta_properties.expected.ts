@@ -0,0 +1,7 @@
+const url = __binop__(import.meta.url, "|>", parse);
+const dir = __binop__(__binop__(import.meta.dirname, "|>", resolve), "|>", normalize);
+const asset = __binop__("./logo.svg", "|>", import.meta.resolve);
+function Base() {
+    const ctor = __binop__(new.target, "|>", describe);
+    return __binop__(new.target.name, "|>", log);
+}
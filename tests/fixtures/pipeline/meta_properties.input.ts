@@ -0,0 +1,8 @@
+const url = import.meta.url |> parse;
+const dir = import.meta.dirname |> resolve |> normalize;
+const asset = "./logo.svg" |> import.meta.resolve;
+
+function Base() {
+  const ctor = new.target |> describe;
+  return new.target.name |> log;
+}
@@ -0,0 +1,2 @@
+const names = __binop__(__binop__(__binop__(__binop__(users, "|>", filter(isActive)), "|>", map((u)=>u.name)), "|>", sortBy(identity)), "|>", take(10));
+const list = __binop__(first, "::", __binop__(second, "::", rest));
@@ -0,0 +1,9 @@
+const names = users
+  |> filter(isActive)
+  |> map((u) => u.name)
+  |> sortBy(identity)
+  |> take(10);
+
+const list = first
+  :: second
+  :: rest;
@@ -0,0 +1,18 @@
+const o = {
+    m (a: number): number {
+        return __binop__(a, "|>", f);
+    },
+    get g () {
+        return __binop__(y, "|>", h);
+    },
+    set s (v: number){
+        this.v = __binop__(v, "|>", clamp);
+    },
+    async load (url: string) {
+        return __binop__(url, "|>", fetch);
+    },
+    [name] () {
+        __binop__(a, "|>", log);
+    },
+    key: __binop__(z, "|>", k)
+};
@@ -0,0 +1,18 @@
+const o = {
+  m(a: number): number {
+    return a |> f;
+  },
+  get g() {
+    return y |> h;
+  },
+  set s(v: number) {
+    this.v = v |> clamp;
+  },
+  async load(url: string) {
+    return url |> fetch;
+  },
+  [name]() {
+    a |> log;
+  },
+  key: z |> k,
+};
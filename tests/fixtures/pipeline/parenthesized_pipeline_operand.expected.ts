@@ -0,0 +1,4 @@
+const r = __binop__(a, "|>", (__binop__(b, "|>", f)));
+const s = __binop__(__binop__(a, "|>", (__binop__(b, "|>", f))), "|>", g);
+const t = __binop__(__binop__(a, "|>", (__binop__(b, "|>", (__binop__(c, "|>", h))))), "|>", g);
+const u = __binop__(a, "|>", (__binop__(b, "|>", f))(c));
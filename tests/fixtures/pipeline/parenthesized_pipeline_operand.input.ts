@@ -0,0 +1,4 @@
+const r = a |> (b |> f);
+const s = a |> (b |> f) |> g;
+const t = a |> (b |> (c |> h)) |> g;
+const u = a |> (b |> f)(c);
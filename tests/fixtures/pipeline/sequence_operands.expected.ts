@@ -0,0 +1,4 @@
+const pair = __binop__((a, b), "|>", f);
+const chained = __binop__(__binop__((log(a), b), "|>", f), "|>", g);
+const inner = (a, __binop__(b, "|>", f));
+a, __binop__(b, "|>", f);
@@ -0,0 +1,7 @@
+// Parenthesized, the sequence is the left operand.
+const pair = (a, b) |> f;
+const chained = (log(a), b) |> f |> g;
+
+// Unparenthesized, `,` binds looser than `|>`: only `b` is piped.
+const inner = (a, b |> f);
+a, b |> f;
@@ -0,0 +1,18 @@
+switch(__binop__(x, "|>", kind)){
+    case __binop__(a, "|>", f):
+        log(__binop__(x, "|>", show));
+        break;
+    case __binop__("b", "|>", g):
+        {
+            const ys = __binop__(1, "::", xs);
+            return __binop__(ys, "|>", h);
+        }
+    case ok ? a : b:
+        run(__binop__(x, "|>", f));
+        break;
+    case __binop__(y?.z, "|>", f):
+    case __binop__((n ?? 0), "|>", h):
+        y = __binop__(x, "|>", f);
+    default:
+        __binop__(z, "|>", track);
+}
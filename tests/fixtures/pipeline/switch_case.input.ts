@@ -0,0 +1,17 @@
+switch (x |> kind) {
+  case a |> f:
+    log(x |> show);
+    break;
+  case "b" |> g: {
+    const ys = 1 :: xs;
+    return ys |> h;
+  }
+  case ok ? a : b:
+    run(x |> f);
+    break;
+  case y?.z |> f:
+  case (n ?? 0) |> h:
+    y = x |> f;
+  default:
+    z |> track;
+}
@@ -0,0 +1,8 @@
+const rows = __binop__(sql`SELECT * FROM t WHERE a = ${a}; -- x, y`, "|>", execute);
+const query = __binop__(db.sql`SELECT ${__binop__(cols, "|>", join)} FROM ${table}`, "|>", run);
+const typed = __binop__(tag<Row>`x=${x}`, "|>", parse);
+const page = __binop__(__binop__(html`<p>${name}</p>`, "|>", render), "|>", mount);
+const called = __binop__(makeTag()`a`, "|>", g);
+const raw = __binop__(String.raw`\d+|>`, "|>", RegExp);
+const list = __binop__(css`a { b: c }`, "::", styles);
+const last = __binop__(xs, "|>", sql`count ${n}`);
@@ -0,0 +1,10 @@
+// A tagged template is one left operand: the tag, its type arguments, and
+// the whole template, whatever its text and interpolations hold.
+const rows = sql`SELECT * FROM t WHERE a = ${a}; -- x, y` |> execute;
+const query = db.sql`SELECT ${cols |> join} FROM ${table}` |> run;
+const typed = tag<Row>`x=${x}` |> parse;
+const page = html`<p>${name}</p>` |> render |> mount;
+const called = makeTag()`a` |> g;
+const raw = String.raw`\d+|>` |> RegExp;
+const list = css`a { b: c }` :: styles;
+const last = xs |> sql`count ${n}`;
@@ -0,0 +1,7 @@
+const résultat = __binop__(__binop__(données, "|>", normaliser), "|>", 表示);
+function nom(personne: {
+    prénom: string;
+}) {
+    return __binop__(personne.prénom, "|>", trim);
+}
+const liste = __binop__(élément, "::", reste);
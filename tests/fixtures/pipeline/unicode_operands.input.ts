@@ -0,0 +1,8 @@
+// Operands are whole identifiers, whatever script they are written in.
+const résultat = données |> normaliser |> 表示;
+
+function nom(personne: { prénom: string }) {
+  return personne.prénom |> trim;
+}
+
+const liste = élément :: reste;
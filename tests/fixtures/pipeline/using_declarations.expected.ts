@@ -0,0 +1,14 @@
+using r = __binop__(acquire(), "|>", wrap);
+using typed: Disposable = __binop__(open(path), "|>", guard);
+using a = __binop__(x, "|>", f), b = __binop__(y, "|>", g);
+async function run(url: string) {
+    await using conn = __binop__(connect(url), "|>", pool);
+    for (using h of __binop__(handles, "|>", opened)){
+        __binop__(h, "|>", use);
+    }
+    for (await using s of __binop__(streams(url), "|>", buffered)){
+        __binop__(s, "|>", drain);
+    }
+}
+function later() {}
+using last = __binop__(z, "|>", finish);
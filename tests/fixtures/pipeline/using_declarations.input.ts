@@ -0,0 +1,18 @@
+// `using` and `await using` (TS 5.2) declare like `const`: the initializer
+// is the pipeline, and each declarator is its own operand boundary.
+using r = acquire() |> wrap;
+using typed: Disposable = open(path) |> guard;
+using a = x |> f, b = y |> g;
+
+async function run(url: string) {
+    await using conn = connect(url) |> pool;
+    for (using h of handles |> opened) {
+        h |> use;
+    }
+    for (await using s of streams(url) |> buffered) {
+        s |> drain;
+    }
+}
+
+function later() {}
+using last = z |> finish
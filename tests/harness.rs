@@ -1,18 +1,32 @@
 //! Golden-file test harness for sugarcube.
 //!
 //! Discovers `.input.ts` files under `tests/fixtures/`, runs the sugarcube
-//! pipeline (parse → desugar → codegen), and compares output against the
-//! corresponding `.expected.ts` file.
+//! pipeline (`sc_cli::transform`: parse → desugar → codegen), and compares
+//! output against the corresponding `.expected.ts` file. `.input.tsx`
+//! fixtures are parsed as TSX and compared against `.expected.tsx`, and
+//! `.input.d.ts` fixtures as declaration files against `.expected.d.ts`.
+//!
+//! The same inputs also check that incremental preprocessing agrees with a
+//! full run.
 //!
 //! Set `SC_UPDATE_FIXTURES=1` to overwrite expected files with actual output.
+//!
+//! Set `SC_NORMALIZE=1` to compare layout-independently: expected and actual
+//! output are both re-parsed as plain TypeScript and re-emitted with the
+//! default codegen config before comparing, so an expected file written by
+//! hand or by another formatter only fails on a real difference.
+//!
+//! A `syntax.json` file in a fixture directory overrides `ScSyntax` for the
+//! fixtures in that directory (missing fields keep their defaults), e.g.
+//! `{ "bind": true }`.
 
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use sc_ast::ScSyntax;
-use sc_desugar::desugar_module;
+use sc_cli::{transform, TransformOptions};
 use sc_parser::parse_sugarcube;
-use swc_ecma_codegen::{text_writer::JsWriter, Emitter, Node};
+use sc_parser::preprocess::{preprocess_with_report, retransform, PreprocessState, TextEdit};
 
 fn fixtures_dir() -> PathBuf {
     // CARGO_MANIFEST_DIR is crates/sc_test/, so go up two levels to workspace root.
@@ -31,13 +45,9 @@ fn collect_input_files(dir: &Path) -> Vec<PathBuf> {
         return files;
     }
     for entry in walkdir(dir) {
-        if entry.extension().is_some_and(|e| e == "ts")
-            && entry
-                .file_name()
-                .unwrap()
-                .to_str()
-                .is_some_and(|n| n.ends_with(".input.ts"))
-        {
+        if entry.file_name().unwrap().to_str().is_some_and(|n| {
+            n.ends_with(".input.ts") || n.ends_with(".input.tsx") || n.ends_with(".input.d.ts")
+        }) {
             files.push(entry);
         }
     }
@@ -60,37 +70,49 @@ fn walkdir(dir: &Path) -> Vec<PathBuf> {
     result
 }
 
-fn run_pipeline(source: &str, filename: &str) -> Result<String> {
-    let syntax = ScSyntax::default();
-    let parsed = parse_sugarcube(source, filename, &syntax, None)?;
-    let module = desugar_module(parsed.module);
-
-    let mut buf = Vec::new();
-    {
-        let writer = JsWriter::new(parsed.source_map.clone(), "\n", &mut buf, None);
-        let mut emitter = Emitter {
-            cfg: swc_ecma_codegen::Config::default()
-                .with_target(swc_ecma_ast::EsVersion::latest()),
-            cm: parsed.source_map,
-            comments: None,
-            wr: writer,
-        };
-        module.emit_with(&mut emitter)?;
+/// Load the `ScSyntax` for fixtures in `dir`: `syntax.json` if present, else defaults.
+fn fixture_syntax(dir: &Path) -> Result<ScSyntax> {
+    let config = dir.join("syntax.json");
+    if !config.exists() {
+        return Ok(ScSyntax::default());
     }
+    let text = std::fs::read_to_string(&config)?;
+    Ok(serde_json::from_str(&text)?)
+}
 
-    Ok(String::from_utf8(buf)?)
+/// What `sc preprocess` emits for `source`.
+fn run_pipeline(source: &str, filename: &str, syntax: &ScSyntax) -> Result<String> {
+    Ok(transform(filename, source, syntax, &TransformOptions::default())?.code)
 }
 
-fn verify_valid_typescript(output: &str, filename: &str) -> Result<()> {
-    let syntax = ScSyntax {
+/// Every sugarcube extension off: output and expected files are plain TypeScript.
+fn plain_syntax() -> ScSyntax {
+    ScSyntax {
         pipeline: false,
         cons: false,
         hkt: false,
-    };
-    parse_sugarcube(output, filename, &syntax, None)?;
+        bind: false,
+        ..ScSyntax::default()
+    }
+}
+
+fn verify_valid_typescript(output: &str, filename: &str) -> Result<()> {
+    parse_sugarcube(output, filename, &plain_syntax(), None)?;
     Ok(())
 }
 
+/// `code` re-parsed and re-emitted, for `SC_NORMALIZE` comparisons.
+/// `filename` picks TSX parsing the same way it did for the fixture.
+fn normalize(code: &str, filename: &str) -> Result<String> {
+    Ok(transform(
+        filename,
+        code,
+        &plain_syntax(),
+        &TransformOptions::default(),
+    )?
+    .code)
+}
+
 #[test]
 fn golden_file_tests() {
     let fixtures = fixtures_dir();
@@ -103,13 +125,14 @@ fn golden_file_tests() {
     );
 
     let update_mode = std::env::var("SC_UPDATE_FIXTURES").is_ok();
+    let normalize_mode = std::env::var("SC_NORMALIZE").is_ok();
     let mut failures = Vec::new();
 
     for input_path in &input_files {
         let expected_path = input_path
             .to_str()
             .unwrap()
-            .replace(".input.ts", ".expected.ts");
+            .replace(".input.", ".expected.");
         let expected_path = PathBuf::from(&expected_path);
 
         let test_name = input_path
@@ -126,8 +149,16 @@ fn golden_file_tests() {
             }
         };
 
+        let syntax = match fixture_syntax(input_path.parent().unwrap()) {
+            Ok(s) => s,
+            Err(e) => {
+                failures.push(format!("{test_name}: failed to load syntax.json: {e}"));
+                continue;
+            }
+        };
+
         let filename = input_path.display().to_string();
-        let actual = match run_pipeline(&source, &filename) {
+        let actual = match run_pipeline(&source, &filename, &syntax) {
             Ok(s) => s,
             Err(e) => {
                 failures.push(format!("{test_name}: pipeline failed: {e}"));
@@ -157,6 +188,24 @@ fn golden_file_tests() {
                 continue;
             }
         };
+        let (expected, actual) = if normalize_mode {
+            match (
+                normalize(&expected, &filename),
+                normalize(&actual, &filename),
+            ) {
+                (Ok(expected), Ok(actual)) => (expected, actual),
+                (Err(e), _) => {
+                    failures.push(format!("{test_name}: failed to normalize expected: {e}"));
+                    continue;
+                }
+                (_, Err(e)) => {
+                    failures.push(format!("{test_name}: failed to normalize actual: {e}"));
+                    continue;
+                }
+            }
+        } else {
+            (expected, actual)
+        };
         if actual.trim() != expected.trim() {
             failures.push(format!(
                 "{test_name}: output mismatch\n--- expected ---\n{}\n--- actual ---\n{}",
@@ -199,7 +248,7 @@ fn roundtrip_tests() {
         };
 
         let filename = input_path.display().to_string();
-        let output = match run_pipeline(&source, &filename) {
+        let output = match run_pipeline(&source, &filename, &ScSyntax::default()) {
             Ok(s) => s,
             Err(e) => {
                 failures.push(format!("{test_name}: pipeline failed: {e}"));
@@ -223,3 +272,57 @@ fn roundtrip_tests() {
         );
     }
 }
+
+/// Incremental preprocessing must agree with a full run on every fixture,
+/// both from scratch and after an edit at the start of each statement.
+#[test]
+fn incremental_preprocess_tests() {
+    let fixtures = fixtures_dir();
+    let mut failures = Vec::new();
+
+    for input_path in collect_input_files(&fixtures) {
+        let test_name = input_path
+            .strip_prefix(&fixtures)
+            .unwrap()
+            .display()
+            .to_string();
+        let source = std::fs::read_to_string(&input_path).unwrap();
+        let syntax = fixture_syntax(input_path.parent().unwrap()).unwrap();
+
+        let state = PreprocessState::new(&source, &syntax);
+        let mut sources = vec![state.clone()];
+        for statement in state.statements() {
+            let at = statement.start..statement.start;
+            sources.push(retransform(
+                &state,
+                TextEdit {
+                    range: at,
+                    text: "\n".to_string(),
+                },
+            ));
+        }
+
+        for state in sources {
+            let full = preprocess_with_report(state.source(), &syntax);
+            if full.report.has_errors() {
+                continue;
+            }
+            if state.output().code != full.code {
+                failures.push(format!(
+                    "{test_name}: incremental output differs\n--- full ---\n{}\n--- incremental ---\n{}",
+                    full.code.trim(),
+                    state.output().code.trim()
+                ));
+                break;
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        panic!(
+            "\n{} incremental test(s) failed:\n\n{}",
+            failures.len(),
+            failures.join("\n\n")
+        );
+    }
+}